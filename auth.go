@@ -0,0 +1,39 @@
+package main
+
+import (
+	"fmt"
+
+	"repo-pack/config"
+	"repo-pack/helpers"
+)
+
+// runAuth implements the auth subcommand, which manages the token stored in the system keyring.
+func runAuth(args []string) error {
+	if len(args) != 1 {
+		return fmt.Errorf("usage: repo-pack auth login|logout")
+	}
+
+	switch args[0] {
+	case "login":
+		token, err := helpers.ReadSecret("GitHub token: ")
+		if err != nil {
+			return fmt.Errorf("failed to read token: %v", err)
+		}
+		if token == "" {
+			return fmt.Errorf("no token given")
+		}
+		if err := config.StoreKeyringToken(token); err != nil {
+			return err
+		}
+		fmt.Println("[-] Token stored in the keyring, set \"use_keyring\": true in the configuration file to use it")
+		return nil
+	case "logout":
+		if err := config.DeleteKeyringToken(); err != nil {
+			return err
+		}
+		fmt.Println("[-] Token removed from the keyring")
+		return nil
+	default:
+		return fmt.Errorf("unknown auth command %q, expected login or logout", args[0])
+	}
+}
@@ -0,0 +1,139 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"strings"
+)
+
+// subcommands are the words accepted in place of flags as the first argument.
+var subcommands = []string{"auth", "completions", "config", "version"}
+
+// completionFlag is a flag as shell completion scripts need it.
+type completionFlag struct {
+	name        string
+	takesValue  bool
+	description string
+}
+
+// completionFlags returns every flag of a run, in the order the flag package lists them.
+func completionFlags() []completionFlag {
+	defineFlags(&options{})
+
+	var flags []completionFlag
+	flag.VisitAll(func(f *flag.Flag) {
+		boolFlag, ok := f.Value.(interface{ IsBoolFlag() bool })
+		flags = append(flags, completionFlag{
+			name:        f.Name,
+			takesValue:  !ok || !boolFlag.IsBoolFlag(),
+			description: f.Usage,
+		})
+	})
+	return flags
+}
+
+// runCompletions implements the completions subcommand, printing the completion script of a shell.
+func runCompletions(args []string) error {
+	if len(args) != 1 {
+		return fmt.Errorf("usage: repo-pack completions bash|zsh|fish|powershell")
+	}
+
+	flags := completionFlags()
+	var script string
+	switch args[0] {
+	case "bash":
+		script = bashCompletion(flags)
+	case "zsh":
+		script = zshCompletion(flags)
+	case "fish":
+		script = fishCompletion(flags)
+	case "powershell":
+		script = powershellCompletion(flags)
+	default:
+		return fmt.Errorf("unsupported shell %q, expected bash, zsh, fish or powershell", args[0])
+	}
+	fmt.Print(script)
+	return nil
+}
+
+func bashCompletion(flags []completionFlag) string {
+	names := make([]string, len(flags))
+	for i, f := range flags {
+		names[i] = "--" + f.name
+	}
+
+	var b strings.Builder
+	b.WriteString("# bash completion for repo-pack, load with: source <(repo-pack completions bash)\n")
+	b.WriteString("_repo_pack() {\n")
+	b.WriteString("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n")
+	b.WriteString("    if [[ $COMP_CWORD -eq 1 && $cur != -* ]]; then\n")
+	fmt.Fprintf(&b, "        COMPREPLY=($(compgen -W \"%s\" -- \"$cur\"))\n", strings.Join(subcommands, " "))
+	b.WriteString("        return\n")
+	b.WriteString("    fi\n")
+	b.WriteString("    if [[ $cur == -* ]]; then\n")
+	fmt.Fprintf(&b, "        COMPREPLY=($(compgen -W \"%s\" -- \"$cur\"))\n", strings.Join(names, " "))
+	b.WriteString("    fi\n")
+	b.WriteString("}\n")
+	b.WriteString("complete -o default -F _repo_pack repo-pack\n")
+	return b.String()
+}
+
+func zshCompletion(flags []completionFlag) string {
+	escape := strings.NewReplacer("'", `'\''`, "[", `\[`, "]", `\]`, ":", `\:`)
+
+	var b strings.Builder
+	b.WriteString("#compdef repo-pack\n")
+	b.WriteString("# zsh completion for repo-pack, save as _repo-pack in a directory of $fpath\n")
+	b.WriteString("_arguments \\\n")
+	for _, f := range flags {
+		if f.takesValue {
+			fmt.Fprintf(&b, "  '--%s=[%s]:value:_default' \\\n", f.name, escape.Replace(f.description))
+		} else {
+			fmt.Fprintf(&b, "  '--%s[%s]' \\\n", f.name, escape.Replace(f.description))
+		}
+	}
+	fmt.Fprintf(&b, "  '1::command:(%s)'\n", strings.Join(subcommands, " "))
+	return b.String()
+}
+
+func fishCompletion(flags []completionFlag) string {
+	escape := strings.NewReplacer(`\`, `\\`, "'", `\'`)
+
+	var b strings.Builder
+	b.WriteString("# fish completion for repo-pack, load with: repo-pack completions fish | source\n")
+	fmt.Fprintf(&b, "complete -c repo-pack -n '__fish_use_subcommand' -f -a '%s'\n", strings.Join(subcommands, " "))
+	for _, f := range flags {
+		required := ""
+		if f.takesValue {
+			required = " -r"
+		}
+		fmt.Fprintf(&b, "complete -c repo-pack -l %s%s -d '%s'\n", f.name, required, escape.Replace(f.description))
+	}
+	return b.String()
+}
+
+func powershellCompletion(flags []completionFlag) string {
+	escape := strings.NewReplacer("'", "''")
+
+	var b strings.Builder
+	b.WriteString("# PowerShell completion for repo-pack, load with: repo-pack completions powershell | Out-String | Invoke-Expression\n")
+	b.WriteString("Register-ArgumentCompleter -Native -CommandName repo-pack -ScriptBlock {\n")
+	b.WriteString("    param($wordToComplete, $commandAst, $cursorPosition)\n")
+	b.WriteString("    $completions = @(\n")
+	for _, command := range subcommands {
+		fmt.Fprintf(&b, "        @('%s', 'subcommand'),\n", command)
+	}
+	for i, f := range flags {
+		separator := ","
+		if i == len(flags)-1 {
+			separator = ""
+		}
+		fmt.Fprintf(&b, "        @('--%s', '%s')%s\n", f.name, escape.Replace(f.description), separator)
+	}
+	b.WriteString("    )\n")
+	b.WriteString("    $completions | Where-Object { $_[0] -like \"$wordToComplete*\" } | ForEach-Object {\n")
+	b.WriteString("        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterName', $_[1])\n")
+	b.WriteString("    }\n")
+	b.WriteString("}\n")
+	return b.String()
+}
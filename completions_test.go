@@ -0,0 +1,49 @@
+package main
+
+import (
+	"flag"
+	"strings"
+	"testing"
+)
+
+// testCompletionFlags defines the flags of a run once, the default flag set panics on redefinition.
+var testCompletionFlags = completionFlags()
+
+// checkEveryFlag fails t for each defined flag whose completion, as format renders its name, is missing from script.
+func checkEveryFlag(t *testing.T, script string, format func(name string) string) {
+	t.Helper()
+
+	count := 0
+	flag.VisitAll(func(f *flag.Flag) {
+		count++
+		if !strings.Contains(script, format(f.Name)) {
+			t.Errorf("expected the script to complete --%s", f.Name)
+		}
+	})
+	if count == 0 || count != len(testCompletionFlags) {
+		t.Errorf("expected the %d defined flags to be completed, got: %d", count, len(testCompletionFlags))
+	}
+}
+
+func TestBashCompletion(t *testing.T) {
+	// Pad every word of the compgen lists with spaces so the first and last flags match too.
+	script := strings.NewReplacer(`"`, " ", " ", "  ").Replace(bashCompletion(testCompletionFlags))
+	checkEveryFlag(t, script, func(name string) string { return " --" + name + " " })
+}
+
+func TestZshCompletion(t *testing.T) {
+	script := zshCompletion(testCompletionFlags)
+	checkEveryFlag(t, script, func(name string) string {
+		if f := flag.Lookup(name); f != nil {
+			if b, ok := f.Value.(interface{ IsBoolFlag() bool }); ok && b.IsBoolFlag() {
+				return "'--" + name + "["
+			}
+		}
+		return "'--" + name + "=["
+	})
+}
+
+func TestFishCompletion(t *testing.T) {
+	script := fishCompletion(testCompletionFlags)
+	checkEveryFlag(t, script, func(name string) string { return "complete -c repo-pack -l " + name + " " })
+}
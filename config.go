@@ -0,0 +1,59 @@
+package main
+
+import (
+	"fmt"
+
+	"repo-pack/config"
+)
+
+// runConfig implements the config subcommand, which reads and writes the configuration file.
+func runConfig(args []string) error {
+	usage := fmt.Errorf("usage: repo-pack config get <key> | set <key> <value> | path")
+	if len(args) == 0 {
+		return usage
+	}
+
+	switch args[0] {
+	case "path":
+		if len(args) != 1 {
+			return usage
+		}
+		path, err := config.Path()
+		if err != nil {
+			return err
+		}
+		fmt.Println(path)
+		return nil
+	case "get":
+		if len(args) != 2 {
+			return usage
+		}
+		cfg, err := config.Load()
+		if err != nil {
+			return fmt.Errorf("failed to load configuration: %v", err)
+		}
+		value, err := cfg.Get(args[1])
+		if err != nil {
+			return err
+		}
+		fmt.Println(value)
+		return nil
+	case "set":
+		if len(args) != 3 {
+			return usage
+		}
+		cfg, err := config.Load()
+		if err != nil {
+			return fmt.Errorf("failed to load configuration: %v", err)
+		}
+		if err := cfg.Set(args[1], args[2]); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return fmt.Errorf("failed to save configuration: %v", err)
+		}
+		return nil
+	default:
+		return fmt.Errorf("unknown config command %q, expected get, set or path", args[0])
+	}
+}
@@ -0,0 +1,301 @@
+package config
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"log"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"unicode/utf8"
+)
+
+// Config holds user defaults read from config.json in the repo-pack configuration directory.
+// Command line flags take precedence over it.
+type Config struct {
+	// DefaultOutputDir is where files are saved when --output is not given.
+	DefaultOutputDir string `json:"default_output_dir,omitempty"`
+
+	// UseKeyring looks the token up in the system keyring, see `repo-pack auth login`.
+	UseKeyring bool `json:"use_keyring,omitempty"`
+
+	// ProgressBarStyle is the filled, in progress and empty glyphs the progress bar is drawn with.
+	ProgressBarStyle string `json:"progress_bar_style,omitempty"`
+
+	// Profile holds the settings of the default profile, which are written at the top level
+	// so that configuration files from before profiles existed keep working.
+	Profile
+
+	// Profiles are named alternatives to the default profile, selected with --profile.
+	Profiles map[string]Profile `json:"profiles,omitempty"`
+}
+
+// Profile holds the settings that differ between accounts or hosts.
+type Profile struct {
+	// GitHubTokenPath is a file holding the token, used if no other source provides one.
+	GitHubTokenPath string `json:"github_token_path,omitempty"`
+
+	// Host is the GitHub Enterprise Server host to talk to instead of github.com.
+	Host string `json:"host,omitempty"`
+
+	// Concurrency replaces the default of --concurrency when it is not given.
+	Concurrency int `json:"concurrency,omitempty"`
+}
+
+// WithProfile returns the configuration with the settings of the named profile in place of the
+// default ones. Settings the profile leaves unset keep their default. An empty name selects the default profile.
+func (c Config) WithProfile(name string) (Config, error) {
+	if name == "" {
+		return c, nil
+	}
+	profile, ok := c.Profiles[name]
+	if !ok {
+		return c, fmt.Errorf("no profile named %q in the configuration file", name)
+	}
+	if profile.GitHubTokenPath != "" {
+		c.GitHubTokenPath = profile.GitHubTokenPath
+	}
+	if profile.Host != "" {
+		c.Host = profile.Host
+	}
+	if profile.Concurrency != 0 {
+		c.Concurrency = profile.Concurrency
+	}
+	return c, nil
+}
+
+// Dir returns the repo-pack configuration directory, repo-pack under the user configuration directory.
+func Dir() (string, error) {
+	configDir, err := os.UserConfigDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(configDir, "repo-pack"), nil
+}
+
+// Path returns the location of the configuration file.
+func Path() (string, error) {
+	dir, err := Dir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "config.json"), nil
+}
+
+// Load reads the configuration file, a missing file gives the zero Config.
+func Load() (Config, error) {
+	var config Config
+	path, err := Path()
+	if err != nil {
+		return config, err
+	}
+
+	data, err := os.ReadFile(path)
+	if errors.Is(err, os.ErrNotExist) {
+		return config, nil
+	}
+	if err != nil {
+		return config, err
+	}
+	if err := json.Unmarshal(data, &config); err != nil {
+		return config, fmt.Errorf("error decoding %s: %w", path, err)
+	}
+	return config, nil
+}
+
+// Save writes the configuration file, creating the configuration directory if needed.
+func (c Config) Save() error {
+	path, err := Path()
+	if err != nil {
+		return err
+	}
+	data, err := json.MarshalIndent(c, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0o755); err != nil {
+		return err
+	}
+	return os.WriteFile(path, append(data, '\n'), 0o644)
+}
+
+// Keys are the settings accepted by Get and Set. ProfileKeys can also be set per profile as profiles.<name>.<key>.
+var (
+	Keys        = []string{"default_output_dir", "use_keyring", "progress_bar_style", "github_token_path", "host", "concurrency"}
+	ProfileKeys = []string{"github_token_path", "host", "concurrency"}
+)
+
+// Get returns the value of the setting key as text, "" if it is unset.
+func (c Config) Get(key string) (string, error) {
+	if name, field, ok := profileKey(key); ok {
+		profile, exists := c.Profiles[name]
+		if !exists {
+			return "", fmt.Errorf("no profile named %q in the configuration file", name)
+		}
+		return profile.get(field)
+	}
+
+	switch key {
+	case "default_output_dir":
+		return c.DefaultOutputDir, nil
+	case "use_keyring":
+		return strconv.FormatBool(c.UseKeyring), nil
+	case "progress_bar_style":
+		return c.ProgressBarStyle, nil
+	}
+	return c.Profile.get(key)
+}
+
+// Set validates value and assigns it to the setting key. Setting a profile key of a profile
+// that does not exist yet creates the profile.
+func (c *Config) Set(key, value string) error {
+	if name, field, ok := profileKey(key); ok {
+		profile := c.Profiles[name]
+		if err := profile.set(field, value); err != nil {
+			return err
+		}
+		if c.Profiles == nil {
+			c.Profiles = map[string]Profile{}
+		}
+		c.Profiles[name] = profile
+		return nil
+	}
+
+	switch key {
+	case "default_output_dir":
+		c.DefaultOutputDir = value
+		return nil
+	case "use_keyring":
+		useKeyring, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("use_keyring must be true or false: %s", value)
+		}
+		c.UseKeyring = useKeyring
+		return nil
+	case "progress_bar_style":
+		if utf8.RuneCountInString(value) != 3 {
+			return fmt.Errorf("progress_bar_style must be three characters, filled, in progress and empty, e.g. \"█▌░\": %s", value)
+		}
+		c.ProgressBarStyle = value
+		return nil
+	}
+	return c.Profile.set(key, value)
+}
+
+// profileKey splits a profiles.<name>.<key> setting into the profile name and its key.
+func profileKey(key string) (name, field string, ok bool) {
+	rest, found := strings.CutPrefix(key, "profiles.")
+	if !found {
+		return "", "", false
+	}
+	dot := strings.LastIndex(rest, ".")
+	if dot <= 0 {
+		return "", "", false
+	}
+	return rest[:dot], rest[dot+1:], true
+}
+
+func (p Profile) get(key string) (string, error) {
+	switch key {
+	case "github_token_path":
+		return p.GitHubTokenPath, nil
+	case "host":
+		return p.Host, nil
+	case "concurrency":
+		if p.Concurrency == 0 {
+			return "", nil
+		}
+		return strconv.Itoa(p.Concurrency), nil
+	}
+	return "", unknownKeyError(key)
+}
+
+func (p *Profile) set(key, value string) error {
+	switch key {
+	case "github_token_path":
+		p.GitHubTokenPath = value
+	case "host":
+		if strings.Contains(value, "/") {
+			return fmt.Errorf("host must be a host name such as github.example.com, not a URL: %s", value)
+		}
+		p.Host = value
+	case "concurrency":
+		concurrency, err := strconv.Atoi(value)
+		if err != nil || concurrency < 1 {
+			return fmt.Errorf("concurrency must be a whole number of at least 1: %s", value)
+		}
+		p.Concurrency = concurrency
+	default:
+		return unknownKeyError(key)
+	}
+	return nil
+}
+
+func unknownKeyError(key string) error {
+	return fmt.Errorf(
+		"unknown key %q, expected one of %s, or profiles.<name>.<key> with one of %s",
+		key,
+		strings.Join(Keys, ", "),
+		strings.Join(ProfileKeys, ", "),
+	)
+}
+
+// Token returns the token to use when none is given with --token: GITHUB_TOKEN, then the keyring
+// if UseKeyring is set and the platform has one, then the content of GitHubTokenPath. It returns "" if none of them has one.
+func (c Config) Token() (string, error) {
+	if token := os.Getenv("GITHUB_TOKEN"); token != "" {
+		return token, nil
+	}
+	if c.UseKeyring {
+		token, err := KeyringToken()
+		if errors.Is(err, ErrKeyringUnavailable) {
+			log.Printf("warning: use_keyring is set but %v, falling back to github_token_path", err)
+		} else if err != nil {
+			return "", err
+		}
+		if token != "" {
+			return token, nil
+		}
+	}
+	if c.GitHubTokenPath == "" {
+		return "", nil
+	}
+	return readToken(c.GitHubTokenPath)
+}
+
+// ReadTokenFile returns the token held in the file at path, as given with --token-file, without
+// surrounding whitespace. A leading ~ in path is expanded, and a file holding only whitespace is an error.
+func ReadTokenFile(path string) (string, error) {
+	token, err := readToken(path)
+	if err == nil && token == "" {
+		return "", fmt.Errorf("token file %s is empty", path)
+	}
+	return token, err
+}
+
+// readToken returns the content of the file at path without surrounding whitespace, expanding a leading ~.
+func readToken(path string) (string, error) {
+	path, err := ExpandHome(path)
+	if err != nil {
+		return "", err
+	}
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return "", fmt.Errorf("error reading token file: %w", err)
+	}
+	return strings.TrimSpace(string(data)), nil
+}
+
+// ExpandHome replaces a leading ~ in path with the home directory of the user.
+func ExpandHome(path string) (string, error) {
+	if path != "~" && !strings.HasPrefix(path, "~/") {
+		return path, nil
+	}
+	home, err := os.UserHomeDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(home, strings.TrimPrefix(path, "~")), nil
+}
@@ -0,0 +1,171 @@
+package config_test
+
+import (
+	"encoding/json"
+	"os"
+	"path/filepath"
+	"testing"
+
+	"repo-pack/config"
+)
+
+func TestExpandHome(t *testing.T) {
+	home, err := os.UserHomeDir()
+	if err != nil {
+		t.Skip("no home directory")
+	}
+
+	tests := map[string]string{
+		"~":                     home,
+		"~/Downloads/repo-pack": filepath.Join(home, "Downloads", "repo-pack"),
+		"/tmp/out":              "/tmp/out",
+		"~other/out":            "~other/out",
+	}
+	for input, expected := range tests {
+		got, err := config.ExpandHome(input)
+		if err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		if got != expected {
+			t.Errorf("%s: expected %s, got %s", input, expected, got)
+		}
+	}
+}
+
+func TestConfigTokenPrecedence(t *testing.T) {
+	tokenFile := filepath.Join(t.TempDir(), "token")
+	if err := os.WriteFile(tokenFile, []byte("from-file\n"), 0o600); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	cfg := config.Config{GitHubTokenPath: tokenFile}
+
+	t.Setenv("GITHUB_TOKEN", "")
+	if token, err := cfg.Token(); err != nil || token != "from-file" {
+		t.Errorf("expected the token file to be read, got: %q, %v", token, err)
+	}
+
+	t.Setenv("GITHUB_TOKEN", "from-env")
+	if token, err := cfg.Token(); err != nil || token != "from-env" {
+		t.Errorf("expected GITHUB_TOKEN to take precedence, got: %q, %v", token, err)
+	}
+}
+
+func TestConfigTokenWithEmptyTokenFile(t *testing.T) {
+	tokenFile := filepath.Join(t.TempDir(), "token")
+	if err := os.WriteFile(tokenFile, []byte("\n"), 0o600); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	cfg := config.Config{GitHubTokenPath: tokenFile}
+
+	t.Setenv("GITHUB_TOKEN", "")
+	if token, err := cfg.Token(); err != nil || token != "" {
+		t.Errorf("expected no token and no error, got: %q, %v", token, err)
+	}
+}
+
+func TestConfigTokenWithoutKeyring(t *testing.T) {
+	tokenFile := filepath.Join(t.TempDir(), "token")
+	if err := os.WriteFile(tokenFile, []byte("from-file\n"), 0o600); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	cfg := config.Config{UseKeyring: true, GitHubTokenPath: tokenFile}
+
+	// Without the keyring tools on PATH the keyring is unavailable on every platform.
+	t.Setenv("PATH", t.TempDir())
+	t.Setenv("GITHUB_TOKEN", "")
+	if token, err := cfg.Token(); err != nil || token != "from-file" {
+		t.Errorf("expected the token file to be read, got: %q, %v", token, err)
+	}
+}
+
+func TestReadTokenFile(t *testing.T) {
+	dir := t.TempDir()
+	tokenFile := filepath.Join(dir, "token")
+	if err := os.WriteFile(tokenFile, []byte("  secret\n"), 0o600); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if token, err := config.ReadTokenFile(tokenFile); err != nil || token != "secret" {
+		t.Errorf("expected the trimmed token, got: %q, %v", token, err)
+	}
+
+	emptyFile := filepath.Join(dir, "empty")
+	if err := os.WriteFile(emptyFile, []byte("\n"), 0o600); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	for _, path := range []string{emptyFile, filepath.Join(dir, "missing")} {
+		if _, err := config.ReadTokenFile(path); err == nil {
+			t.Errorf("%s: expected an error", path)
+		}
+	}
+}
+
+func TestConfigWithProfile(t *testing.T) {
+	var cfg config.Config
+	data := `{
+		"github_token_path": "~/.github-token",
+		"concurrency": 4,
+		"profiles": {"work": {"host": "github.example.com", "github_token_path": "~/.work-token"}}
+	}`
+	if err := json.Unmarshal([]byte(data), &cfg); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if cfg.GitHubTokenPath != "~/.github-token" || cfg.Concurrency != 4 {
+		t.Errorf("expected top-level fields to form the default profile, got: %+v", cfg.Profile)
+	}
+
+	work, err := cfg.WithProfile("work")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected := config.Profile{GitHubTokenPath: "~/.work-token", Host: "github.example.com", Concurrency: 4}
+	if work.Profile != expected {
+		t.Errorf("expected: %+v, got: %+v", expected, work.Profile)
+	}
+
+	if _, err := cfg.WithProfile("missing"); err == nil {
+		t.Errorf("expected an error for an unknown profile")
+	}
+}
+
+func TestConfigGetSet(t *testing.T) {
+	var cfg config.Config
+	settings := map[string]string{
+		"default_output_dir":        "~/Downloads",
+		"use_keyring":               "true",
+		"progress_bar_style":        "=> ",
+		"concurrency":               "4",
+		"profiles.work.host":        "github.example.com",
+		"profiles.work.concurrency": "2",
+	}
+	for key, value := range settings {
+		if err := cfg.Set(key, value); err != nil {
+			t.Fatalf("%s: unexpected error: %v", key, err)
+		}
+	}
+	for key, value := range settings {
+		if got, err := cfg.Get(key); err != nil || got != value {
+			t.Errorf("%s: expected %q, got %q, %v", key, value, got, err)
+		}
+	}
+	if cfg.Profiles["work"].Host != "github.example.com" || cfg.Host != "" {
+		t.Errorf("expected the host to be set on the work profile only, got %+v", cfg)
+	}
+
+	invalid := map[string]string{
+		"concurrency":           "0",
+		"use_keyring":           "maybe",
+		"progress_bar_style":    "##",
+		"host":                  "https://github.example.com",
+		"output_dir":            "out",
+		"profiles.work.use_key": "true",
+	}
+	for key, value := range invalid {
+		if err := cfg.Set(key, value); err == nil {
+			t.Errorf("%s=%s: expected an error", key, value)
+		}
+	}
+	if _, err := cfg.Get("profiles.home.host"); err == nil {
+		t.Errorf("expected an error for a missing profile")
+	}
+}
@@ -0,0 +1,104 @@
+package config
+
+import (
+	"errors"
+	"fmt"
+	"os/exec"
+	"runtime"
+	"strings"
+)
+
+// The keyring entry holding the token, as service and account.
+const (
+	keyringService = "repo-pack"
+	keyringAccount = "github"
+)
+
+// ErrKeyringUnavailable is returned when the keyring tool of the platform is not installed:
+// security on macOS and secret-tool (libsecret) elsewhere. Windows is not supported.
+var ErrKeyringUnavailable = errors.New("no supported keyring found")
+
+// keyringCommand returns the tool and arguments that perform op, one of lookup, store or clear,
+// on the token entry.
+func keyringCommand(op string) (string, []string, error) {
+	var name string
+	var args []string
+	switch runtime.GOOS {
+	case "windows":
+		return "", nil, ErrKeyringUnavailable
+	case "darwin":
+		name = "security"
+		entry := []string{"-s", keyringService, "-a", keyringAccount}
+		switch op {
+		case "lookup":
+			args = append([]string{"find-generic-password", "-w"}, entry...)
+		case "store":
+			// security only takes the password as an argument; -w last makes it prompt for it instead.
+			args = append(append([]string{"add-generic-password", "-U"}, entry...), "-w")
+		case "clear":
+			args = append([]string{"delete-generic-password"}, entry...)
+		}
+	default:
+		name = "secret-tool"
+		entry := []string{"service", keyringService, "account", keyringAccount}
+		switch op {
+		case "store":
+			args = append([]string{"store", "--label=repo-pack GitHub token"}, entry...)
+		default:
+			args = append([]string{op}, entry...)
+		}
+	}
+	if _, err := exec.LookPath(name); err != nil {
+		return "", nil, ErrKeyringUnavailable
+	}
+	return name, args, nil
+}
+
+// KeyringToken returns the token stored in the keyring, or "" if there is none.
+func KeyringToken() (string, error) {
+	name, args, err := keyringCommand("lookup")
+	if err != nil {
+		return "", err
+	}
+	output, err := exec.Command(name, args...).Output()
+	var exitErr *exec.ExitError
+	if errors.As(err, &exitErr) {
+		// Both tools exit with an error when there is no such entry.
+		return "", nil
+	}
+	if err != nil {
+		return "", fmt.Errorf("error reading the keyring: %v", err)
+	}
+	return strings.TrimSpace(string(output)), nil
+}
+
+// StoreKeyringToken saves token in the keyring, replacing any stored before.
+func StoreKeyringToken(token string) error {
+	name, args, err := keyringCommand("store")
+	if err != nil {
+		return err
+	}
+	cmd := exec.Command(name, args...)
+	input := token
+	if runtime.GOOS == "darwin" {
+		// security asks for the password twice.
+		input = token + "\n" + token + "\n"
+	}
+	cmd.Stdin = strings.NewReader(input)
+	if output, err := cmd.CombinedOutput(); err != nil {
+		return fmt.Errorf("error writing to the keyring: %v: %s", err, strings.TrimSpace(string(output)))
+	}
+	return nil
+}
+
+// DeleteKeyringToken removes the token from the keyring.
+func DeleteKeyringToken() error {
+	name, args, err := keyringCommand("clear")
+	if err != nil {
+		return err
+	}
+	if output, err := exec.Command(name, args...).CombinedOutput(); err != nil {
+		return fmt.Errorf("error removing the token from the keyring: %v: %s", err, strings.TrimSpace(string(output)))
+	}
+	return nil
+}
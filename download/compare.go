@@ -0,0 +1,110 @@
+package download
+
+import (
+	"errors"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"sort"
+
+	"repo-pack/gh"
+	"repo-pack/helpers"
+)
+
+// Change is how a local file differs from the remote one.
+type Change string
+
+const (
+	ChangeAdded   Change = "added"
+	ChangeRemoved Change = "removed"
+	ChangeChanged Change = "changed"
+)
+
+// FileChange is one difference found by Compare, Path is the local path.
+type FileChange struct {
+	Path   string `json:"path"`
+	Change Change `json:"change"`
+}
+
+// LfsPointerFunc returns the Git LFS pointer the repository file path holds, if it holds one.
+type LfsPointerFunc func(path string) (helpers.LfsPointer, bool, error)
+
+// Compare reports how the files under localRoot differ from the remote items: remote files missing
+// locally are added, local files with no remote counterpart are removed, and files present on both
+// sides are changed if their sizes differ or, with byContent, their git blob SHAs do. Items listed
+// without a SHA are compared by size. Files that look changed but are listed small enough to be
+// Git LFS pointers are checked against the object the pointer describes, if lfsPointer is set.
+// localPath maps a repository path to where it is saved. Nothing is written.
+func Compare(items []gh.Item, localPath func(path string) string, localRoot string, byContent bool, lfsPointer LfsPointerFunc) ([]FileChange, error) {
+	var changes []FileChange
+	remote := map[string]bool{}
+	for _, item := range items {
+		local := localPath(item.Path)
+		remote[local] = true
+
+		info, err := os.Stat(filepath.FromSlash(local))
+		if errors.Is(err, os.ErrNotExist) {
+			changes = append(changes, FileChange{Path: local, Change: ChangeAdded})
+			continue
+		}
+		if err != nil {
+			return nil, err
+		}
+
+		changed := info.Size() != item.Size
+		if byContent && item.SHA != "" {
+			sha, err := helpers.GitBlobSHA(filepath.FromSlash(local))
+			if err != nil {
+				return nil, err
+			}
+			changed = sha != item.SHA
+		}
+		if changed && lfsPointer != nil && item.Size > 0 && item.Size <= helpers.MaxLfsPointerSize {
+			if changed, err = changedFromLfsObject(item.Path, local, info, byContent, lfsPointer); err != nil {
+				return nil, err
+			}
+		}
+		if changed {
+			changes = append(changes, FileChange{Path: local, Change: ChangeChanged})
+		}
+	}
+
+	err := filepath.WalkDir(filepath.FromSlash(localRoot), func(file string, entry fs.DirEntry, err error) error {
+		if errors.Is(err, os.ErrNotExist) && file == filepath.FromSlash(localRoot) {
+			return filepath.SkipDir
+		}
+		// The sidecars repo-pack keeps in the output directory have no remote counterpart.
+		if err != nil || entry.IsDir() || entry.Name() == helpers.VerificationCacheFile || entry.Name() == LockFileName {
+			return err
+		}
+		if local := filepath.ToSlash(filepath.Clean(file)); !remote[local] {
+			changes = append(changes, FileChange{Path: local, Change: ChangeRemoved})
+		}
+		return nil
+	})
+	if err != nil {
+		return nil, err
+	}
+
+	sort.Slice(changes, func(i, j int) bool {
+		return changes[i].Path < changes[j].Path
+	})
+	return changes, nil
+}
+
+// changedFromLfsObject reports whether the local file differs from the Git LFS object the repository file
+// path points at, by size or with byContent by SHA-256. Files that are no pointer count as changed.
+func changedFromLfsObject(path, local string, info os.FileInfo, byContent bool, lfsPointer LfsPointerFunc) (bool, error) {
+	pointer, ok, err := lfsPointer(path)
+	if err != nil || !ok {
+		return true, err
+	}
+	if !byContent {
+		return info.Size() != pointer.Size, nil
+	}
+	sum, err := helpers.FileSHA256(filepath.FromSlash(local))
+	if err != nil {
+		return false, err
+	}
+	return sum != pointer.Oid, nil
+}
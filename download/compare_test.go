@@ -0,0 +1,101 @@
+package download_test
+
+import (
+	"os"
+	"path"
+	"path/filepath"
+	"reflect"
+	"testing"
+
+	"repo-pack/download"
+	"repo-pack/gh"
+	"repo-pack/helpers"
+)
+
+func TestCompare(t *testing.T) {
+	root := t.TempDir()
+	write := func(name, content string) {
+		t.Helper()
+		file := filepath.Join(root, "lua", name)
+		if err := os.MkdirAll(filepath.Dir(file), 0o755); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		if err := os.WriteFile(file, []byte(content), 0o644); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	write("same.lua", "hello\n")
+	write("resized.lua", "hello, world\n")
+	write("edited.lua", "HELLO\n")
+	write("local-only.lua", "extra\n")
+	write(download.LockFileName, "{}\n")
+
+	// same.lua matches the remote blob, edited.lua only matches its size.
+	helloSHA := "ce013625030ba8dba906f756967f9e9ca394464a"
+	items := []gh.Item{
+		{Path: "nvim/lua/same.lua", SHA: helloSHA, Size: 6},
+		{Path: "nvim/lua/resized.lua", SHA: helloSHA, Size: 6},
+		{Path: "nvim/lua/edited.lua", SHA: helloSHA, Size: 6},
+		{Path: "nvim/lua/remote-only.lua", SHA: helloSHA, Size: 6},
+	}
+	localPath := func(file string) string {
+		return path.Join(filepath.ToSlash(root), "lua", path.Base(file))
+	}
+	local := func(name string) string { return path.Join(filepath.ToSlash(root), "lua", name) }
+
+	bySize, err := download.Compare(items, localPath, filepath.ToSlash(root)+"/lua", false, nil)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected := []download.FileChange{
+		{Path: local("local-only.lua"), Change: download.ChangeRemoved},
+		{Path: local("remote-only.lua"), Change: download.ChangeAdded},
+		{Path: local("resized.lua"), Change: download.ChangeChanged},
+	}
+	if !reflect.DeepEqual(bySize, expected) {
+		t.Errorf("expected: %+v, got: %+v", expected, bySize)
+	}
+
+	byContent, err := download.Compare(items, localPath, filepath.ToSlash(root)+"/lua", true, nil)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected = []download.FileChange{
+		{Path: local("edited.lua"), Change: download.ChangeChanged},
+		{Path: local("local-only.lua"), Change: download.ChangeRemoved},
+		{Path: local("remote-only.lua"), Change: download.ChangeAdded},
+		{Path: local("resized.lua"), Change: download.ChangeChanged},
+	}
+	if !reflect.DeepEqual(byContent, expected) {
+		t.Errorf("expected: %+v, got: %+v", expected, byContent)
+	}
+}
+
+func TestCompareLfsFilesAgainstTheirObjects(t *testing.T) {
+	root := t.TempDir()
+	for name, content := range map[string]string{"same.bin": "content", "resized.bin": "other content"} {
+		if err := os.WriteFile(filepath.Join(root, name), []byte(content), 0o644); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+
+	// Both files are listed at the size of their pointer, describing a 7 byte object with the SHA-256 of "content".
+	pointer := helpers.LfsPointer{Oid: "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73", Size: 7}
+	items := []gh.Item{
+		{Path: "same.bin", SHA: "pointer-blob", Size: 130},
+		{Path: "resized.bin", SHA: "pointer-blob", Size: 130},
+	}
+	lfsPointer := func(file string) (helpers.LfsPointer, bool, error) { return pointer, true, nil }
+	localPath := func(file string) string { return path.Join(filepath.ToSlash(root), file) }
+	expected := []download.FileChange{{Path: localPath("resized.bin"), Change: download.ChangeChanged}}
+
+	for _, byContent := range []bool{false, true} {
+		changes, err := download.Compare(items, localPath, filepath.ToSlash(root), byContent, lfsPointer)
+		if err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+		if !reflect.DeepEqual(changes, expected) {
+			t.Errorf("byContent %v: expected: %+v, got: %+v", byContent, expected, changes)
+		}
+	}
+}
@@ -0,0 +1,43 @@
+package download
+
+import (
+	"os"
+	"path/filepath"
+)
+
+// ExistingFiles is what a run does with files whose local copy already exists.
+type ExistingFiles string
+
+const (
+	// ExistingAsk asks before overwriting existing files, and fails where it cannot ask.
+	ExistingAsk ExistingFiles = "ask"
+
+	// ExistingOverwrite downloads every file again, replacing the local copies.
+	ExistingOverwrite ExistingFiles = "overwrite"
+
+	// ExistingSkip keeps the local copies, counting their files as skipped.
+	ExistingSkip ExistingFiles = "skip"
+
+	// ExistingResume keeps the local copies unless the lockfile shows they changed upstream, see SkipUnchanged.
+	ExistingResume ExistingFiles = "resume"
+
+	// ExistingRename keeps the local copies and saves the downloads next to them with a .remote suffix.
+	ExistingRename ExistingFiles = "rename"
+
+	// ExistingNewer downloads files again only if they changed upstream after their local copy was modified.
+	ExistingNewer ExistingFiles = "newer"
+)
+
+// Existing returns the pending files whose local copy, at the path returned by localPath, exists.
+func (s *Session) Existing(localPath func(path string) string) []string {
+	var existing []string
+	for _, file := range s.Files {
+		if file.State != StatePending {
+			continue
+		}
+		if _, err := os.Stat(filepath.FromSlash(localPath(file.Path))); err == nil {
+			existing = append(existing, file.Path)
+		}
+	}
+	return existing
+}
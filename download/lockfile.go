@@ -0,0 +1,103 @@
+package download
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"time"
+
+	"repo-pack/gh"
+)
+
+// LockFileName is the file written to the output directory to record what each local file was downloaded from.
+const LockFileName = ".repo-pack.lock"
+
+// LockFile records the blob SHA each local file was downloaded at, keyed by local path,
+// so that a later run can tell which files are still current.
+type LockFile struct {
+	Files map[string]LockEntry `json:"files"`
+}
+
+// LockEntry is one file of a LockFile.
+type LockEntry struct {
+	SHA          string    `json:"sha"`
+	DownloadedAt time.Time `json:"downloaded_at"`
+}
+
+// NewLockFile creates a lockfile without any files.
+func NewLockFile() *LockFile {
+	return &LockFile{Files: map[string]LockEntry{}}
+}
+
+// LoadLockFile reads a lockfile previously written with Save.
+func LoadLockFile(path string) (*LockFile, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return nil, err
+	}
+
+	lock := NewLockFile()
+	if err := json.Unmarshal(data, lock); err != nil {
+		return nil, fmt.Errorf("error decoding lockfile %s: %w", path, err)
+	}
+	if lock.Files == nil {
+		lock.Files = map[string]LockEntry{}
+	}
+	return lock, nil
+}
+
+// Record notes that the file at localPath was downloaded at blob sha. Files without a SHA are forgotten,
+// as there is nothing to compare them against later.
+func (l *LockFile) Record(localPath, sha string, at time.Time) {
+	if sha == "" {
+		delete(l.Files, localPath)
+		return
+	}
+	l.Files[localPath] = LockEntry{SHA: sha, DownloadedAt: at.UTC()}
+}
+
+// Save writes the lockfile to path.
+func (l *LockFile) Save(path string) error {
+	data, err := json.MarshalIndent(l, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := os.WriteFile(path, data, 0o644); err != nil {
+		return fmt.Errorf("error writing lockfile %s: %w", path, err)
+	}
+	return nil
+}
+
+// SkipUnchanged marks pending files skipped if their local copy, at the path returned by localPath, exists
+// and is still current: lock records the blob SHA the listing reports for it. Files lock doesn't know, or
+// the listing reported no SHA for, are taken to be current if they exist, which is also how every file is
+// treated with a nil lock. Truncated copies, smaller than the listed size or empty, are never current.
+// Only files checked against lock are reported unchanged since the last run, the others as already existing.
+// It returns how many files were marked skipped.
+func (s *Session) SkipUnchanged(lock *LockFile, localPath func(path string) string) int {
+	count := 0
+	for i := range s.Files {
+		file := &s.Files[i]
+		if file.State != StatePending {
+			continue
+		}
+		local := localPath(file.Path)
+		if info, err := os.Stat(filepath.FromSlash(local)); err != nil || file.truncated(info.Size()) {
+			continue
+		}
+		reason := "already exists"
+		if lock != nil && file.SHA != "" {
+			if entry, ok := lock.Files[local]; ok {
+				if entry.SHA != file.SHA {
+					continue
+				}
+				reason = "unchanged since the last run"
+			}
+		}
+		file.State = StateSkipped
+		file.Error = fmt.Errorf("%w: %s", gh.ErrSkipped, reason).Error()
+		count++
+	}
+	return count
+}
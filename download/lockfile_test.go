@@ -0,0 +1,83 @@
+package download_test
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+	"time"
+
+	"repo-pack/download"
+	"repo-pack/model"
+)
+
+func TestSessionSkipUnchanged(t *testing.T) {
+	dir := t.TempDir()
+	for _, name := range []string{"same.txt", "changed.txt", "unknown.txt"} {
+		if err := os.WriteFile(filepath.Join(dir, name), []byte("x"), 0o644); err != nil {
+			t.Fatal(err)
+		}
+	}
+	localPath := func(path string) string { return filepath.ToSlash(filepath.Join(dir, path)) }
+
+	lock := download.NewLockFile()
+	lock.Record(localPath("same.txt"), "aaa", time.Now())
+	lock.Record(localPath("changed.txt"), "bbb", time.Now())
+
+	newSession := func() *download.Session {
+		session := download.NewSession(model.RepoURLComponents{}, []string{"same.txt", "changed.txt", "unknown.txt", "missing.txt"})
+		for i := range session.Files {
+			session.Files[i].SHA = "aaa"
+		}
+		return session
+	}
+
+	session := newSession()
+	if existing := session.Existing(localPath); len(existing) != 3 {
+		t.Errorf("expected 3 existing files, got %v", existing)
+	}
+	if skipped := session.SkipUnchanged(lock, localPath); skipped != 2 {
+		t.Errorf("expected 2 files to be skipped, got %d", skipped)
+	}
+	expected := []download.FileState{download.StateSkipped, download.StatePending, download.StateSkipped, download.StatePending}
+	for i, state := range expected {
+		if session.Files[i].State != state {
+			t.Errorf("%s: expected %s, got %s", session.Files[i].Path, state, session.Files[i].State)
+		}
+	}
+
+	session = newSession()
+	if skipped := session.SkipUnchanged(nil, localPath); skipped != 3 {
+		t.Errorf("expected every existing file to be skipped without a lockfile, got %d", skipped)
+	}
+
+	session = newSession()
+	session.Files[2].Size = 5
+	if skipped := session.SkipUnchanged(nil, localPath); skipped != 2 || session.Files[2].State != download.StatePending {
+		t.Errorf("expected the copy shorter than its listed size to be downloaded again, got %d skipped", skipped)
+	}
+}
+
+func TestLoadLockFileRoundTrip(t *testing.T) {
+	path := filepath.Join(t.TempDir(), download.LockFileName)
+	lock := download.NewLockFile()
+	lock.Record("a.txt", "aaa", time.Date(2024, 3, 1, 8, 0, 0, 0, time.UTC))
+	lock.Record("b.txt", "", time.Now())
+	if err := lock.Save(path); err != nil {
+		t.Fatal(err)
+	}
+
+	loaded, err := download.LoadLockFile(path)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if len(loaded.Files) != 1 || loaded.Files["a.txt"].SHA != "aaa" {
+		t.Errorf("unexpected files: %+v", loaded.Files)
+	}
+
+	if err := os.WriteFile(path, []byte("{not json"), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	if _, err := download.LoadLockFile(path); err == nil {
+		t.Errorf("expected an error for a corrupt lockfile")
+	}
+}
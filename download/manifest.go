@@ -0,0 +1,55 @@
+package download
+
+import (
+	"time"
+)
+
+// Manifest is a machine-readable report of a run: where the files came from, how each one fared and the totals.
+type Manifest struct {
+	Owner      string `json:"owner"`
+	Repository string `json:"repository"`
+	Ref        string `json:"ref"`
+	CommitSHA  string `json:"commit_sha,omitempty"`
+	Dir        string `json:"dir"`
+
+	// ElapsedSeconds is the wall time of the run.
+	ElapsedSeconds float64 `json:"elapsed_seconds"`
+
+	Total      int `json:"total"`
+	Downloaded int `json:"downloaded"`
+	Skipped    int `json:"skipped"`
+	Failed     int `json:"failed"`
+
+	Files []ManifestFile `json:"files"`
+}
+
+// ManifestFile is the outcome of one file in a Manifest.
+type ManifestFile struct {
+	Path   string    `json:"path"`
+	Status FileState `json:"status"`
+
+	// Size is the size reported by the listing, zero if it didn't report one.
+	Size  int64  `json:"size"`
+	Error string `json:"error,omitempty"`
+}
+
+// Manifest reports the current state of every file of the session, for a run that took elapsed.
+func (s *Session) Manifest(elapsed time.Duration) Manifest {
+	files := make([]ManifestFile, len(s.Files))
+	for i, file := range s.Files {
+		files[i] = ManifestFile{Path: file.Path, Status: file.State, Size: file.Size, Error: file.Error}
+	}
+	return Manifest{
+		Owner:          s.Components.Owner,
+		Repository:     s.Components.Repository,
+		Ref:            s.Components.Ref,
+		CommitSHA:      s.Components.CommitSHA,
+		Dir:            s.Components.Dir,
+		ElapsedSeconds: elapsed.Seconds(),
+		Total:          len(s.Files),
+		Downloaded:     s.Count(StateDownloaded),
+		Skipped:        s.Count(StateSkipped),
+		Failed:         s.Count(StateFailed),
+		Files:          files,
+	}
+}
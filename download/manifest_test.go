@@ -0,0 +1,29 @@
+package download_test
+
+import (
+	"testing"
+	"time"
+
+	"repo-pack/download"
+	"repo-pack/model"
+)
+
+func TestSessionManifest(t *testing.T) {
+	session := download.NewSession(model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main"}, []string{"a", "b"})
+	session.Files[0].State = download.StateDownloaded
+	session.Files[0].Size = 42
+	session.Files[1].State = download.StateFailed
+	session.Files[1].Error = "HTTP 500"
+
+	manifest := session.Manifest(2 * time.Second)
+
+	if manifest.Owner != "owner" || manifest.Ref != "main" || manifest.ElapsedSeconds != 2 {
+		t.Errorf("unexpected run details: %+v", manifest)
+	}
+	if manifest.Total != 2 || manifest.Downloaded != 1 || manifest.Failed != 1 {
+		t.Errorf("unexpected totals: %+v", manifest)
+	}
+	if manifest.Files[0].Size != 42 || manifest.Files[1].Status != download.StateFailed || manifest.Files[1].Error != "HTTP 500" {
+		t.Errorf("unexpected files: %+v", manifest.Files)
+	}
+}
@@ -0,0 +1,521 @@
+package download
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"math/rand"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+	"sync"
+	"time"
+
+	"repo-pack/gh"
+	"repo-pack/helpers"
+	"repo-pack/model"
+)
+
+// FileState is the progress of a single file within a Session.
+type FileState string
+
+const (
+	StatePending    FileState = "pending"
+	StateDownloaded FileState = "downloaded"
+	StateFailed     FileState = "failed"
+	StateSkipped    FileState = "skipped"
+)
+
+// FileStatus records the state of one file of a Session.
+type FileStatus struct {
+	Path  string    `json:"path"`
+	State FileState `json:"state"`
+	Error string    `json:"error,omitempty"`
+
+	// SHA, Size and Mode are the blob SHA, size and git file mode reported by the listing, if it reported them.
+	SHA  string `json:"sha,omitempty"`
+	Size int64  `json:"size,omitempty"`
+	Mode string `json:"mode,omitempty"`
+
+	// URL is where the listing said the file is downloaded from, for files such as release assets
+	// whose URL does not follow from their path.
+	URL string `json:"url,omitempty"`
+}
+
+// Provider lists and downloads the files of a repository.
+// Listing returns the entries with their metadata; gh.ItemPaths reduces them to paths.
+// FetchFile saves one file as opts says, handling anything particular to its host itself, such as
+// Git LFS on GitHub, so that every provider shares the concurrency, retries and bookkeeping of Session.
+type Provider interface {
+	ListItems(ctx context.Context, components *model.RepoURLComponents) ([]gh.Item, error)
+	FetchFile(ctx context.Context, path string, components *model.RepoURLComponents, opts gh.FetchOptions) error
+}
+
+// Session holds the resolved file list of a run together with the state of each file,
+// so that downloading can be driven in batches and checkpointed to disk between them.
+type Session struct {
+	Components model.RepoURLComponents `json:"components"`
+	Files      []FileStatus            `json:"files"`
+
+	// Provider downloads the files, GitHub is used if it is nil.
+	Provider Provider `json:"-"`
+
+	// Concurrency caps how many files are downloaded at once; zero means no limit.
+	Concurrency int `json:"-"`
+
+	// Stagger, if positive, delays the start of each download by a random duration below it.
+	Stagger time.Duration `json:"-"`
+
+	// Retries is how many more times a file is tried after a transient failure, see gh.IsRetryable.
+	Retries int `json:"-"`
+
+	// RetryBackoff is the delay before the first retry, it doubles for every further one and
+	// gets up to as much again of random jitter. DefaultRetryBackoff is used if it is zero.
+	RetryBackoff time.Duration `json:"-"`
+
+	// OnRetry, if set, is called before a file is retried with the attempt about to be made and the error.
+	OnRetry func(path string, attempt int, err error) `json:"-"`
+
+	// FailFast stops the batch at the first failed file.
+	FailFast bool `json:"-"`
+
+	// Interrupted, if set, stops the batch from starting further downloads once it is closed.
+	// Downloads already running carry on until they finish or the context of the batch is done.
+	Interrupted <-chan struct{} `json:"-"`
+
+	// FetchOptions controls how files are downloaded and written.
+	FetchOptions gh.FetchOptions `json:"-"`
+
+	// VerifyBlobs checks every downloaded file against the git blob SHA recorded for it, if any.
+	VerifyBlobs bool `json:"-"`
+
+	// OnFileDone, if set, is called once per attempted file from the goroutine running the batch.
+	// err wraps gh.ErrSkipped for skipped files.
+	OnFileDone func(path string, err error) `json:"-"`
+}
+
+// ErrInterrupted is returned by RunNextBatch when Interrupted closed before every file was started.
+var ErrInterrupted = errors.New("interrupted, files not yet started are left pending")
+
+// DefaultRetryBackoff is the delay before the first retry when Session.RetryBackoff is not set.
+const DefaultRetryBackoff = 500 * time.Millisecond
+
+type fileResult struct {
+	index int
+	err   error
+}
+
+// NewSession creates a session where every file is still pending.
+func NewSession(components model.RepoURLComponents, files []string) *Session {
+	statuses := make([]FileStatus, len(files))
+	for i, file := range files {
+		statuses[i] = FileStatus{Path: file, State: StatePending}
+	}
+	return &Session{Components: components, Files: statuses}
+}
+
+// LoadSession reads a session previously written with Save.
+// Files that failed are marked pending again so that they are retried.
+func LoadSession(path string) (*Session, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return nil, err
+	}
+
+	var session Session
+	if err := json.Unmarshal(data, &session); err != nil {
+		return nil, fmt.Errorf("error decoding session %s: %w", path, err)
+	}
+
+	session.RetryFailed()
+	return &session, nil
+}
+
+// RetryFailed marks every failed file pending again and returns how many there were.
+func (s *Session) RetryFailed() int {
+	count := 0
+	for i := range s.Files {
+		if s.Files[i].State == StateFailed {
+			s.Files[i].State = StatePending
+			s.Files[i].Error = ""
+			count++
+		}
+	}
+	return count
+}
+
+// Save writes the session to path as JSON.
+func (s *Session) Save(path string) error {
+	data, err := json.MarshalIndent(s, "", "  ")
+	if err != nil {
+		return fmt.Errorf("error encoding session: %w", err)
+	}
+	if err := os.WriteFile(path, data, 0o644); err != nil {
+		return fmt.Errorf("error writing session %s: %w", path, err)
+	}
+	return nil
+}
+
+// truncated reports whether a local copy of size bytes falls short of the file: it is smaller than the
+// listed size, or empty if no size was listed. A larger copy is fine, as Git LFS files are listed with the
+// size of their pointer and --ensure-final-newline adds a byte.
+func (f FileStatus) truncated(size int64) bool {
+	if f.Size > 0 {
+		return size < f.Size
+	}
+	return size == 0
+}
+
+// RecheckDownloaded marks downloaded files pending again if their local copy, at the path returned by
+// localPath, is missing or truncated, as left behind by a run interrupted while writing. Without remote sizes
+// only empty files count as truncated, and genuinely empty files are downloaded again too, which costs one
+// small request each. With checksums in FetchOptions the local copies are also verified, so that they count
+// as seen, and those that fail are downloaded again. It returns how many files were marked pending.
+func (s *Session) RecheckDownloaded(localPath func(path string) string) int {
+	count := 0
+	for i := range s.Files {
+		if s.Files[i].State != StateDownloaded {
+			continue
+		}
+		local := localPath(s.Files[i].Path)
+		info, err := os.Stat(filepath.FromSlash(local))
+		if err == nil && !s.Files[i].truncated(info.Size()) && s.verifyLocal(local) {
+			continue
+		}
+		s.Files[i].State = StatePending
+		count++
+	}
+	return count
+}
+
+// verifyLocal checks the file at local against the checksums of FetchOptions, if there are any.
+func (s *Session) verifyLocal(local string) bool {
+	checksums := s.FetchOptions.Save.Checksums
+	if checksums == nil {
+		return true
+	}
+	sum, err := helpers.FileSHA256(filepath.FromSlash(local))
+	return err == nil && checksums.Verify(local, sum) == nil
+}
+
+// Remaining returns the paths of the files that are still pending.
+func (s *Session) Remaining() []string {
+	var remaining []string
+	for _, file := range s.Files {
+		if file.State == StatePending {
+			remaining = append(remaining, file.Path)
+		}
+	}
+	return remaining
+}
+
+// RemainingSize returns the combined size of the pending files, as reported by the listing.
+// It is zero when the listing reported no sizes.
+func (s *Session) RemainingSize() int64 {
+	var size int64
+	for _, file := range s.Files {
+		if file.State == StatePending {
+			size += file.Size
+		}
+	}
+	return size
+}
+
+// RemainingSizeKnown reports whether the listing reported a size for every pending file. Some listings,
+// such as those of wikis, report none, and as a size of zero can't be told from a missing one, empty files
+// count as unknown too. Git LFS files are listed at the size of their pointer all the same.
+func (s *Session) RemainingSizeKnown() bool {
+	for _, file := range s.Files {
+		if file.State == StatePending && file.Size <= 0 {
+			return false
+		}
+	}
+	return true
+}
+
+// Count returns how many files are in the given state.
+func (s *Session) Count(state FileState) int {
+	count := 0
+	for _, file := range s.Files {
+		if file.State == state {
+			count++
+		}
+	}
+	return count
+}
+
+// RunNextBatch concurrently downloads up to size pending files (all of them if size <= 0)
+// and records their outcome. It returns the number of files attempted, zero once nothing is left.
+// With FailFast set, the first failure cancels the rest of the batch and is returned as an error;
+// files interrupted by the cancellation stay pending.
+func (s *Session) RunNextBatch(ctx context.Context, size int) (int, error) {
+	var batch []int
+	for i, file := range s.Files {
+		if file.State != StatePending {
+			continue
+		}
+		batch = append(batch, i)
+		if size > 0 && len(batch) == size {
+			break
+		}
+	}
+
+	files := make([]FileStatus, len(batch))
+	for i, index := range batch {
+		files[i] = s.Files[index]
+	}
+
+	var provider Provider = gh.Provider{}
+	if s.Provider != nil {
+		provider = s.Provider
+	}
+
+	ctx, cancel := context.WithCancel(ctx)
+	defer cancel()
+
+	results := make(chan fileResult, len(batch))
+	go func() {
+		var wg sync.WaitGroup
+		defer func() {
+			wg.Wait()
+			close(results)
+		}()
+
+		var sem chan struct{}
+		if s.Concurrency > 0 {
+			sem = make(chan struct{}, s.Concurrency)
+		}
+
+		// Acquiring the semaphore before spawning keeps downloads starting in file order.
+		for i, index := range batch {
+			if sem != nil {
+				select {
+				case sem <- struct{}{}:
+				case <-ctx.Done():
+					return
+				case <-s.Interrupted:
+					return
+				}
+			}
+			if ctx.Err() != nil || s.interrupted() {
+				if sem != nil {
+					<-sem
+				}
+				return
+			}
+
+			wg.Add(1)
+			go func(index int, file FileStatus) {
+				defer wg.Done()
+				err := staggerStart(ctx, s.Stagger)
+				if err == nil {
+					err = s.fetchWithRetries(ctx, provider, file)
+				}
+				if sem != nil {
+					<-sem
+				}
+				results <- fileResult{index: index, err: err}
+			}(index, files[i])
+		}
+	}()
+
+	var abortErr error
+	for result := range results {
+		if abortErr != nil && result.err != nil {
+			continue
+		}
+
+		file := &s.Files[result.index]
+		switch {
+		case result.err == nil:
+			file.State = StateDownloaded
+			file.Error = ""
+		case errors.Is(result.err, gh.ErrSkipped):
+			file.State = StateSkipped
+			file.Error = result.err.Error()
+		default:
+			file.State = StateFailed
+			file.Error = result.err.Error()
+		}
+
+		if s.OnFileDone != nil {
+			s.OnFileDone(file.Path, result.err)
+		}
+
+		// Files cut off once the grace period of an interrupt runs out are not failures to abort on.
+		if file.State == StateFailed && s.FailFast && !s.interrupted() {
+			abortErr = fmt.Errorf("aborting after %s failed: %w", file.Path, result.err)
+			cancel()
+		}
+	}
+
+	if abortErr == nil && s.interrupted() && len(s.Remaining()) > 0 {
+		abortErr = ErrInterrupted
+	}
+	return len(batch), abortErr
+}
+
+// interrupted reports whether Interrupted has been closed.
+func (s *Session) interrupted() bool {
+	select {
+	case <-s.Interrupted:
+		return true
+	default:
+		return false
+	}
+}
+
+// fetchWithRetries downloads file, retrying transient failures with exponential backoff and jitter.
+// Executable files are saved as such, and with VerifyBlobs the content is checked against the SHA of file.
+func (s *Session) fetchWithRetries(ctx context.Context, provider Provider, file FileStatus) error {
+	backoff := s.RetryBackoff
+	if backoff <= 0 {
+		backoff = DefaultRetryBackoff
+	}
+	path := file.Path
+	opts := s.FetchOptions
+	opts.Save.Executable = file.Mode == gh.ModeExecutable
+	opts.URL = file.URL
+	opts.Save.Size = file.Size
+	if s.VerifyBlobs {
+		opts.Save.BlobSHA = file.SHA
+	}
+
+	for attempt := 1; ; attempt++ {
+		err := provider.FetchFile(ctx, path, &s.Components, opts)
+		if err == nil || attempt > s.Retries || !gh.IsRetryable(err) {
+			return err
+		}
+
+		if s.OnRetry != nil {
+			s.OnRetry(path, attempt+1, err)
+		}
+		delay := backoff<<(attempt-1) + time.Duration(rand.Int63n(int64(backoff)))
+		select {
+		case <-time.After(delay):
+		case <-ctx.Done():
+			return ctx.Err()
+		}
+	}
+}
+
+// staggerStart sleeps for a random duration below stagger, spreading out the initial burst of requests.
+func staggerStart(ctx context.Context, stagger time.Duration) error {
+	if stagger <= 0 {
+		return nil
+	}
+	select {
+	case <-time.After(time.Duration(rand.Int63n(int64(stagger)))):
+		return nil
+	case <-ctx.Done():
+		return ctx.Err()
+	}
+}
+
+// Filter returns the files matching any of include, or every file if include is empty, leaving out
+// those matching any of exclude. Patterns are matched against paths relative to dir, and exclude
+// takes precedence over include.
+func Filter(files []string, include, exclude []string, dir string) []string {
+	if len(include) == 0 && len(exclude) == 0 {
+		return files
+	}
+
+	var filtered []string
+	for _, file := range files {
+		relative := helpers.RelativePath(file, dir)
+		if len(include) > 0 && !helpers.MatchAnyGlob(include, relative) {
+			continue
+		}
+		if helpers.MatchAnyGlob(exclude, relative) {
+			continue
+		}
+		filtered = append(filtered, file)
+	}
+	return filtered
+}
+
+// FilterDepth returns the files at most maxDepth levels below dir, where files directly in dir are at
+// level 1. A maxDepth of zero or less keeps every file.
+func FilterDepth(files []string, maxDepth int, dir string) []string {
+	if maxDepth <= 0 {
+		return files
+	}
+
+	var filtered []string
+	for _, file := range files {
+		depth := strings.Count(helpers.RelativePath(file, dir), "/") + 1
+		if file == strings.Trim(dir, "/") {
+			// A single file URL names the file itself.
+			depth = 1
+		}
+		if depth <= maxDepth {
+			filtered = append(filtered, file)
+		}
+	}
+	return filtered
+}
+
+// SortUnique returns files sorted with duplicates removed, so that a file listed twice is fetched once
+// and the order of a run does not depend on the order of the listing.
+func SortUnique(files []string) []string {
+	sorted := append([]string(nil), files...)
+	sort.Strings(sorted)
+
+	unique := sorted[:0]
+	for i, file := range sorted {
+		if i == 0 || file != sorted[i-1] {
+			unique = append(unique, file)
+		}
+	}
+	return unique
+}
+
+// Prioritize returns files with those matching any of patterns moved to the front.
+// Patterns are matched against paths relative to dir, and the original order is otherwise kept.
+func Prioritize(files []string, patterns []string, dir string) []string {
+	if len(patterns) == 0 {
+		return files
+	}
+
+	prioritized := make([]string, 0, len(files))
+	var rest []string
+	for _, file := range files {
+		if helpers.MatchAnyGlob(patterns, helpers.RelativePath(file, dir)) {
+			prioritized = append(prioritized, file)
+		} else {
+			rest = append(rest, file)
+		}
+	}
+	return append(prioritized, rest...)
+}
+
+// Interleave returns files reordered to round-robin across the top-level subdirectories of dir,
+// so that an interrupted run leaves every subtree partially downloaded rather than a few complete.
+// Files directly in dir form their own group, and groups keep the order they first appear in.
+func Interleave(files []string, dir string) []string {
+	var order []string
+	groups := map[string][]string{}
+	for _, file := range files {
+		group, _, found := strings.Cut(helpers.RelativePath(file, dir), "/")
+		if !found {
+			group = ""
+		}
+		if _, ok := groups[group]; !ok {
+			order = append(order, group)
+		}
+		groups[group] = append(groups[group], file)
+	}
+
+	interleaved := make([]string, 0, len(files))
+	for len(interleaved) < len(files) {
+		for _, group := range order {
+			if queue := groups[group]; len(queue) > 0 {
+				interleaved = append(interleaved, queue[0])
+				groups[group] = queue[1:]
+			}
+		}
+	}
+	return interleaved
+}
@@ -0,0 +1,420 @@
+package download_test
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"reflect"
+	"strings"
+	"sync"
+	"testing"
+	"time"
+
+	"repo-pack/download"
+	"repo-pack/gh"
+	"repo-pack/helpers"
+	"repo-pack/model"
+)
+
+func TestSessionSaveLoadRetriesFailedFiles(t *testing.T) {
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "dir"}
+	session := download.NewSession(components, []string{"dir/a", "dir/b", "dir/c"})
+	session.Files[0].State = download.StateDownloaded
+	session.Files[1].State = download.StateFailed
+	session.Files[1].Error = "HTTP 500"
+
+	path := filepath.Join(t.TempDir(), "session.json")
+	if err := session.Save(path); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	loaded, err := download.LoadSession(path)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if loaded.Components != components {
+		t.Errorf("expected components: %+v, got: %+v", components, loaded.Components)
+	}
+
+	expected := []string{"dir/b", "dir/c"}
+	if remaining := loaded.Remaining(); !reflect.DeepEqual(remaining, expected) {
+		t.Errorf("expected remaining: %v, got: %v", expected, remaining)
+	}
+}
+
+func TestSessionRemainingSize(t *testing.T) {
+	session := download.NewSession(model.RepoURLComponents{}, []string{"a", "b", "c"})
+	session.Files[0].Size = 100
+	session.Files[0].State = download.StateDownloaded
+	session.Files[1].Size = 20
+	session.Files[2].Size = 3
+
+	if size := session.RemainingSize(); size != 23 {
+		t.Errorf("expected the pending files to add up to 23 bytes, got %d", size)
+	}
+}
+
+func TestRecheckDownloadedRetriesEmptyFiles(t *testing.T) {
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "full"), []byte("content"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(dir, "truncated"), nil, 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(dir, "short"), []byte("cont"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	session := download.NewSession(model.RepoURLComponents{}, []string{"full", "truncated", "short", "missing", "pending"})
+	for i := 0; i < 4; i++ {
+		session.Files[i].State = download.StateDownloaded
+	}
+	session.Files[0].Size = 7
+	session.Files[2].Size = 7
+
+	count := session.RecheckDownloaded(func(path string) string { return filepath.Join(dir, path) })
+
+	if count != 3 {
+		t.Errorf("expected 3 files to be rechecked, got: %d", count)
+	}
+	expected := []string{"truncated", "short", "missing", "pending"}
+	if remaining := session.Remaining(); !reflect.DeepEqual(remaining, expected) {
+		t.Errorf("expected remaining: %v, got: %v", expected, remaining)
+	}
+}
+
+func TestRemainingSizeKnown(t *testing.T) {
+	session := download.NewSession(model.RepoURLComponents{}, []string{"a", "b", "c"})
+	session.Files[0].Size = 10
+	session.Files[1].Size = 20
+	session.Files[2].State = download.StateSkipped
+
+	if !session.RemainingSizeKnown() || session.RemainingSize() != 30 {
+		t.Errorf("expected the 30 bytes of a and b to be known, got %d, %v", session.RemainingSize(), session.RemainingSizeKnown())
+	}
+
+	session.Files[2].State = download.StatePending
+	if session.RemainingSizeKnown() {
+		t.Errorf("expected the size of c to be unknown")
+	}
+}
+
+func TestRecheckDownloadedVerifiesChecksums(t *testing.T) {
+	dir := t.TempDir()
+	for name, content := range map[string]string{"good": "content", "bad": "changed"} {
+		if err := os.WriteFile(filepath.Join(dir, name), []byte(content), 0o644); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	// Both files are listed with the SHA-256 of "content".
+	sum := "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73"
+	checksums, err := helpers.ParseChecksums(strings.NewReader(
+		sum + "  " + filepath.ToSlash(filepath.Join(dir, "good")) + "\n" + sum + "  " + filepath.ToSlash(filepath.Join(dir, "bad")) + "\n",
+	))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	session := download.NewSession(model.RepoURLComponents{}, []string{"good", "bad"})
+	session.Files[0].State = download.StateDownloaded
+	session.Files[1].State = download.StateDownloaded
+	session.FetchOptions.Save.Checksums = checksums
+
+	count := session.RecheckDownloaded(func(path string) string { return filepath.ToSlash(filepath.Join(dir, path)) })
+
+	if count != 1 || !reflect.DeepEqual(session.Remaining(), []string{"bad"}) {
+		t.Errorf("expected only bad to be downloaded again, got %d: %v", count, session.Remaining())
+	}
+	if unseen := checksums.Unseen(); len(unseen) != 0 {
+		t.Errorf("expected both files to count as seen, got unseen: %v", unseen)
+	}
+}
+
+func TestFilterExcludeTakesPrecedence(t *testing.T) {
+	files := []string{"dir/README.md", "dir/docs/guide.md", "dir/src/main.go", "dir/tests/fixtures/input.md"}
+
+	got := download.Filter(files, []string{"**/*.md"}, []string{"tests/**"}, "dir")
+
+	expected := []string{"dir/README.md", "dir/docs/guide.md"}
+	if !reflect.DeepEqual(got, expected) {
+		t.Errorf("expected: %v, got: %v", expected, got)
+	}
+}
+
+func TestFilterDepth(t *testing.T) {
+	files := []string{"docs/a.md", "docs/guide/b.md", "docs/guide/deep/c.md"}
+
+	expected := []string{"docs/a.md", "docs/guide/b.md"}
+	if filtered := download.FilterDepth(files, 2, "docs"); !reflect.DeepEqual(filtered, expected) {
+		t.Errorf("expected: %v, got: %v", expected, filtered)
+	}
+	if filtered := download.FilterDepth(files, 1, ""); filtered != nil {
+		t.Errorf("expected no files directly in the repository root, got: %v", filtered)
+	}
+	if filtered := download.FilterDepth([]string{"docs/guide/b.md"}, 1, "docs/guide/b.md"); len(filtered) != 1 {
+		t.Errorf("expected a single file URL to keep its file, got: %v", filtered)
+	}
+	if filtered := download.FilterDepth(files, 0, "docs"); !reflect.DeepEqual(filtered, files) {
+		t.Errorf("expected every file without a limit, got: %v", filtered)
+	}
+}
+
+func TestSortUnique(t *testing.T) {
+	files := []string{"dir/b.md", "dir/sub/c.md", "dir/a.md", "dir/b.md", "dir/sub/c.md"}
+	expected := []string{"dir/a.md", "dir/b.md", "dir/sub/c.md"}
+	if got := download.SortUnique(files); !reflect.DeepEqual(got, expected) {
+		t.Errorf("expected: %v, got: %v", expected, got)
+	}
+	if files[0] != "dir/b.md" {
+		t.Errorf("expected the input to be left untouched, got: %v", files)
+	}
+}
+
+func TestPrioritizeKeepsOrder(t *testing.T) {
+	files := []string{"dir/src/a.go", "dir/README.md", "dir/src/b.go", "dir/config/app.toml"}
+
+	got := download.Prioritize(files, []string{"README.md", "config/**"}, "dir")
+
+	expected := []string{"dir/README.md", "dir/config/app.toml", "dir/src/a.go", "dir/src/b.go"}
+	if !reflect.DeepEqual(got, expected) {
+		t.Errorf("expected: %v, got: %v", expected, got)
+	}
+}
+
+func TestInterleaveRoundRobinsSubdirectories(t *testing.T) {
+	files := []string{"dir/a/1", "dir/a/2", "dir/a/3", "dir/b/1", "dir/top", "dir/b/2"}
+
+	got := download.Interleave(files, "dir")
+
+	expected := []string{"dir/a/1", "dir/b/1", "dir/top", "dir/a/2", "dir/b/2", "dir/a/3"}
+	if !reflect.DeepEqual(got, expected) {
+		t.Errorf("expected: %v, got: %v", expected, got)
+	}
+}
+
+// mockProvider serves canned results instead of talking to GitHub.
+type mockProvider struct {
+	errors map[string]error
+	delay  time.Duration
+
+	// transient is how many times a file fails with a 503 before the error in errors applies.
+	transient map[string]int
+
+	mu          sync.Mutex
+	inFlight    int
+	maxInFlight int
+	fetched     []string
+	urls        map[string]string
+}
+
+func (p *mockProvider) ListItems(ctx context.Context, components *model.RepoURLComponents) ([]gh.Item, error) {
+	return nil, nil
+}
+
+func (p *mockProvider) FetchFile(ctx context.Context, path string, components *model.RepoURLComponents, opts gh.FetchOptions) error {
+	p.mu.Lock()
+	p.inFlight++
+	if p.inFlight > p.maxInFlight {
+		p.maxInFlight = p.inFlight
+	}
+	p.fetched = append(p.fetched, path)
+	if opts.URL != "" {
+		if p.urls == nil {
+			p.urls = map[string]string{}
+		}
+		p.urls[path] = opts.URL
+	}
+	p.mu.Unlock()
+
+	defer func() {
+		p.mu.Lock()
+		p.inFlight--
+		p.mu.Unlock()
+	}()
+
+	select {
+	case <-time.After(p.delay):
+	case <-ctx.Done():
+		return ctx.Err()
+	}
+
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	if p.transient[path] > 0 {
+		p.transient[path]--
+		return &gh.StatusError{StatusCode: 503, Status: "503 Service Unavailable", Path: path}
+	}
+	return p.errors[path]
+}
+
+func newMockSession(provider *mockProvider, count int) *download.Session {
+	files := make([]string, count)
+	for i := range files {
+		files[i] = fmt.Sprintf("dir/file-%d", i)
+	}
+
+	session := download.NewSession(model.RepoURLComponents{Dir: "dir"}, files)
+	session.Provider = provider
+	return session
+}
+
+func TestRunNextBatchRespectsConcurrency(t *testing.T) {
+	provider := &mockProvider{delay: 10 * time.Millisecond}
+	session := newMockSession(provider, 8)
+	session.Concurrency = 2
+
+	attempted, err := session.RunNextBatch(context.Background(), 0)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if attempted != 8 || session.Count(download.StateDownloaded) != 8 {
+		t.Errorf("expected 8 downloaded files, got: %d attempted, %d downloaded", attempted, session.Count(download.StateDownloaded))
+	}
+	if provider.maxInFlight > 2 {
+		t.Errorf("expected at most 2 concurrent downloads, got: %d", provider.maxInFlight)
+	}
+}
+
+func TestRunNextBatchDownloadsFromListedURL(t *testing.T) {
+	provider := &mockProvider{}
+	session := newMockSession(provider, 2)
+	session.Files[1].URL = "https://objects.example.com/asset/1"
+
+	if _, err := session.RunNextBatch(context.Background(), 0); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if len(provider.urls) != 1 || provider.urls["dir/file-1"] != session.Files[1].URL {
+		t.Errorf("expected only dir/file-1 to be fetched from its listed URL, got: %v", provider.urls)
+	}
+}
+
+func TestRunNextBatchRecordsFailuresAndSkips(t *testing.T) {
+	provider := &mockProvider{errors: map[string]error{
+		"dir/file-1": errors.New("HTTP 500"),
+		"dir/file-2": fmt.Errorf("%w: too large", gh.ErrSkipped),
+	}}
+	session := newMockSession(provider, 3)
+
+	if _, err := session.RunNextBatch(context.Background(), 0); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	expected := []download.FileState{download.StateDownloaded, download.StateFailed, download.StateSkipped}
+	for i, state := range expected {
+		if session.Files[i].State != state {
+			t.Errorf("expected %s to be %s, got: %s", session.Files[i].Path, state, session.Files[i].State)
+		}
+	}
+}
+
+func TestRunNextBatchFailFast(t *testing.T) {
+	provider := &mockProvider{
+		errors: map[string]error{"dir/file-0": errors.New("HTTP 500")},
+		delay:  10 * time.Millisecond,
+	}
+	session := newMockSession(provider, 10)
+	session.Concurrency = 1
+	session.FailFast = true
+
+	_, err := session.RunNextBatch(context.Background(), 0)
+	if err == nil {
+		t.Fatalf("expected fail-fast error, got: nil")
+	}
+
+	if session.Count(download.StateFailed) != 1 {
+		t.Errorf("expected 1 failed file, got: %d", session.Count(download.StateFailed))
+	}
+	if len(session.Remaining()) == 0 {
+		t.Errorf("expected remaining files to stay pending after fail-fast")
+	}
+}
+
+func TestRunNextBatchRetriesTransientFailures(t *testing.T) {
+	provider := &mockProvider{
+		errors:    map[string]error{"dir/file-2": &gh.StatusError{StatusCode: 404, Status: "404 Not Found", Path: "dir/file-2"}},
+		transient: map[string]int{"dir/file-0": 2, "dir/file-1": 5},
+	}
+	session := newMockSession(provider, 3)
+	session.Retries = 2
+	session.RetryBackoff = time.Millisecond
+
+	var mu sync.Mutex
+	retries := map[string]int{}
+	session.OnRetry = func(path string, attempt int, err error) {
+		mu.Lock()
+		retries[path]++
+		mu.Unlock()
+	}
+
+	if _, err := session.RunNextBatch(context.Background(), 0); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	expected := []download.FileState{download.StateDownloaded, download.StateFailed, download.StateFailed}
+	for i, state := range expected {
+		if session.Files[i].State != state {
+			t.Errorf("expected %s to be %s, got: %s", session.Files[i].Path, state, session.Files[i].State)
+		}
+	}
+	expectedRetries := map[string]int{"dir/file-0": 2, "dir/file-1": 2}
+	if !reflect.DeepEqual(retries, expectedRetries) {
+		t.Errorf("expected retries: %v, got: %v", expectedRetries, retries)
+	}
+}
+
+func TestRunNextBatchInterruptedFinishesStartedFiles(t *testing.T) {
+	provider := &mockProvider{delay: 20 * time.Millisecond}
+	session := newMockSession(provider, 5)
+	session.Concurrency = 2
+	interrupted := make(chan struct{})
+	session.Interrupted = interrupted
+	session.OnFileDone = func(path string, err error) {
+		if path == "dir/file-0" {
+			close(interrupted)
+		}
+	}
+
+	_, err := session.RunNextBatch(context.Background(), 0)
+	if !errors.Is(err, download.ErrInterrupted) {
+		t.Fatalf("expected ErrInterrupted, got: %v", err)
+	}
+
+	if failed := session.Count(download.StateFailed); failed != 0 {
+		t.Errorf("expected files in flight to finish, got %d failed", failed)
+	}
+	if downloaded := session.Count(download.StateDownloaded); downloaded < 2 || downloaded == 5 {
+		t.Errorf("expected the started files to finish and the rest to stay pending, got %d downloaded", downloaded)
+	}
+	if len(session.Remaining())+session.Count(download.StateDownloaded) != 5 {
+		t.Errorf("expected every file to be downloaded or pending, got: %+v", session.Files)
+	}
+}
+
+func TestRunNextBatchFailFastReportsInterrupt(t *testing.T) {
+	provider := &mockProvider{delay: time.Second}
+	session := newMockSession(provider, 5)
+	session.Concurrency = 2
+	session.FailFast = true
+	interrupted := make(chan struct{})
+	session.Interrupted = interrupted
+
+	// The files in flight outlast the grace period and are cancelled, as on an interrupt.
+	ctx, cancel := context.WithCancel(context.Background())
+	go func() {
+		time.Sleep(10 * time.Millisecond)
+		close(interrupted)
+		time.Sleep(20 * time.Millisecond)
+		cancel()
+	}()
+
+	_, err := session.RunNextBatch(ctx, 0)
+	if !errors.Is(err, download.ErrInterrupted) {
+		t.Fatalf("expected ErrInterrupted rather than a fail-fast abort, got: %v", err)
+	}
+}
@@ -0,0 +1,185 @@
+package gh
+
+import (
+	"context"
+	"errors"
+	"io"
+	"net"
+	"net/http"
+	"net/url"
+	"strings"
+	"sync"
+	"time"
+)
+
+// ClientOptions controls how the shared HTTP client talks to GitHub.
+type ClientOptions struct {
+	// ConnectTimeout bounds dialing, the TLS handshake and the wait for response headers.
+	ConnectTimeout time.Duration
+
+	// TransferTimeout bounds a whole request including its body; zero disables it. Rate limited requests
+	// retried by WaitRateLimit are timed per attempt, without the wait for the reset.
+	TransferTimeout time.Duration
+
+	// IdleTimeout fails a response body that receives no data for this long; zero disables it.
+	IdleTimeout time.Duration
+
+	// Proxy is used for every request in place of the HTTP_PROXY, HTTPS_PROXY and NO_PROXY
+	// environment variables. Credentials in its userinfo are sent as Proxy-Authorization.
+	Proxy *url.URL
+
+	// NoProxy connects directly, ignoring the proxy environment variables.
+	NoProxy bool
+
+	// Headers are added to every request to the GitHub API, raw content, LFS media and git hosts and to
+	// HeaderHosts, replacing headers of the same name. Requests to other hosts, such as redirects to
+	// release asset storage, go without them.
+	Headers http.Header
+
+	// HeaderHosts are further hosts Headers are sent to, e.g. gitlab.com.
+	HeaderHosts []string
+
+	// Resolve pins host names to IP addresses, bypassing DNS for those hosts only.
+	Resolve map[string]string
+
+	// HostConcurrency caps the requests in flight to each host, with "*" for hosts not listed.
+	HostConcurrency map[string]int
+
+	// WaitRateLimit retries rate limited requests once the limit resets instead of failing them.
+	WaitRateLimit bool
+
+	// MaxWait is the longest WaitRateLimit waits for a reset; zero means no limit.
+	MaxWait time.Duration
+
+	// OnRateLimitWait, if set, is called before each wait for a rate limit reset.
+	OnRateLimitWait func(time.Duration)
+}
+
+// httpClient is used for every request made by this package.
+var httpClient = &http.Client{Transport: &rateLimitTransport{base: http.DefaultTransport}}
+
+// Client returns the client configured by ConfigureClient, so that other providers share its settings.
+func Client() *http.Client {
+	return httpClient
+}
+
+// warmupURLs point at the hosts that every run talks to.
+var warmupURLs = []string{"https://api.github.com", "https://raw.githubusercontent.com"}
+
+// ConfigureClient replaces the shared HTTP client with one built from opts.
+// It should be called before any request is made.
+func ConfigureClient(opts ClientOptions) {
+	transport := http.DefaultTransport.(*http.Transport).Clone()
+	dialer := &net.Dialer{
+		Timeout:   30 * time.Second,
+		KeepAlive: 30 * time.Second,
+	}
+	if opts.ConnectTimeout > 0 {
+		dialer.Timeout = opts.ConnectTimeout
+		transport.TLSHandshakeTimeout = opts.ConnectTimeout
+		transport.ResponseHeaderTimeout = opts.ConnectTimeout
+	}
+	if opts.NoProxy {
+		transport.Proxy = nil
+	} else if opts.Proxy != nil {
+		transport.Proxy = http.ProxyURL(opts.Proxy)
+	}
+	transport.DialContext = func(ctx context.Context, network, addr string) (net.Conn, error) {
+		if host, port, err := net.SplitHostPort(addr); err == nil {
+			if ip, ok := opts.Resolve[host]; ok {
+				addr = net.JoinHostPort(ip, port)
+			}
+		}
+		return dialer.DialContext(ctx, network, addr)
+	}
+
+	var roundTripper http.RoundTripper = transport
+	if opts.IdleTimeout > 0 {
+		roundTripper = &idleTimeoutTransport{base: roundTripper, timeout: opts.IdleTimeout}
+	}
+	if len(opts.Headers) > 0 {
+		roundTripper = &headerTransport{base: roundTripper, headers: opts.Headers, hosts: opts.HeaderHosts}
+	}
+	if len(opts.HostConcurrency) > 0 {
+		roundTripper = &hostLimitTransport{base: roundTripper, limits: opts.HostConcurrency}
+	}
+	if opts.TransferTimeout > 0 {
+		roundTripper = &transferTimeoutTransport{base: roundTripper, timeout: opts.TransferTimeout}
+	}
+	roundTripper = &rateLimitTransport{
+		base:    roundTripper,
+		wait:    opts.WaitRateLimit,
+		maxWait: opts.MaxWait,
+		onWait:  opts.OnRateLimitWait,
+	}
+
+	httpClient = &http.Client{Transport: roundTripper}
+}
+
+// headerTransport adds a fixed set of headers to every request to the GitHub hosts and to hosts before sending it.
+type headerTransport struct {
+	base    http.RoundTripper
+	headers http.Header
+	hosts   []string
+}
+
+func (t *headerTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	if !headerHost(req.URL.Host, t.hosts) {
+		return t.base.RoundTrip(req)
+	}
+	req = req.Clone(req.Context())
+	for name, values := range t.headers {
+		req.Header.Del(name)
+		for _, value := range values {
+			req.Header.Add(name, value)
+		}
+	}
+	return t.base.RoundTrip(req)
+}
+
+// headerHost reports whether host serves the API, raw content, LFS media or git repositories, or is one of
+// extra, the hosts extra headers are meant for. The base URLs are read on every request, as UseHost may
+// change them after the client is configured.
+func headerHost(host string, extra []string) bool {
+	for _, allowed := range extra {
+		if strings.EqualFold(allowed, host) {
+			return true
+		}
+	}
+	for _, base := range []string{apiBaseURL, rawBaseURL, mediaBaseURL, gitBaseURL} {
+		if parsed, err := url.Parse(base); err == nil && parsed.Host == host {
+			return true
+		}
+	}
+	return false
+}
+
+// Warmup opens pooled connections to the GitHub hosts ahead of the real requests,
+// so that the first downloads don't pay for the TLS handshakes.
+func Warmup(ctx context.Context) error {
+	var wg sync.WaitGroup
+	errs := make([]error, len(warmupURLs))
+	for i, target := range warmupURLs {
+		wg.Add(1)
+		go func(i int, target string) {
+			defer wg.Done()
+
+			req, err := http.NewRequestWithContext(ctx, http.MethodHead, target, nil)
+			if err != nil {
+				errs[i] = err
+				return
+			}
+
+			resp, err := httpClient.Do(req)
+			if err != nil {
+				errs[i] = err
+				return
+			}
+			io.Copy(io.Discard, resp.Body)
+			resp.Body.Close()
+		}(i, target)
+	}
+	wg.Wait()
+
+	return errors.Join(errs...)
+}
@@ -0,0 +1,96 @@
+package gh
+
+import (
+	"encoding/base64"
+	"net/http"
+	"net/http/httptest"
+	"net/url"
+	"strings"
+	"testing"
+)
+
+func TestConfigureClientSendsRequestsThroughProxy(t *testing.T) {
+	var target, authorization string
+	proxy := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		target = r.URL.String()
+		authorization = r.Header.Get("Proxy-Authorization")
+	}))
+	defer proxy.Close()
+
+	previous := httpClient
+	defer func() { httpClient = previous }()
+
+	proxyURL, _ := url.Parse(proxy.URL)
+	proxyURL.User = url.UserPassword("user", "secret")
+	ConfigureClient(ClientOptions{Proxy: proxyURL})
+
+	resp, err := Client().Get("http://github.invalid/owner/repo")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	resp.Body.Close()
+
+	if target != "http://github.invalid/owner/repo" {
+		t.Errorf("expected the proxy to receive the request, got: %q", target)
+	}
+	expected := "Basic " + base64.StdEncoding.EncodeToString([]byte("user:secret"))
+	if authorization != expected {
+		t.Errorf("expected proxy credentials %q, got: %q", expected, authorization)
+	}
+}
+
+func TestConfigureClientSendsHeadersToGitHubHostsOnly(t *testing.T) {
+	var redirected string
+	other := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		redirected = r.Header.Get("X-Proxy-Token")
+	}))
+	defer other.Close()
+
+	var sent string
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/", func(w http.ResponseWriter, r *http.Request) {
+		sent = r.Header.Get("X-Proxy-Token")
+		http.Redirect(w, r, other.URL+"/asset", http.StatusFound)
+	})
+	serveGitHub(t, mux)
+
+	previous := httpClient
+	defer func() { httpClient = previous }()
+	ConfigureClient(ClientOptions{Headers: http.Header{"X-Proxy-Token": {"secret"}}})
+
+	resp, err := Client().Get(apiBaseURL + "/repos/owner/repo")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	resp.Body.Close()
+
+	if sent != "secret" {
+		t.Errorf("expected the header on the API request, got: %q", sent)
+	}
+	if redirected != "" {
+		t.Errorf("expected no header on the redirect to another host, got: %q", redirected)
+	}
+}
+
+func TestConfigureClientSendsHeadersToHeaderHosts(t *testing.T) {
+	var sent string
+	gitlab := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		sent = r.Header.Get("X-Proxy-Token")
+	}))
+	defer gitlab.Close()
+	host := strings.TrimPrefix(gitlab.URL, "http://")
+
+	previous := httpClient
+	defer func() { httpClient = previous }()
+	ConfigureClient(ClientOptions{Headers: http.Header{"X-Proxy-Token": {"secret"}}, HeaderHosts: []string{host}})
+
+	resp, err := Client().Get(gitlab.URL + "/api/v4/projects")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	resp.Body.Close()
+
+	if sent != "secret" {
+		t.Errorf("expected the header on the request to %s, got: %q", host, sent)
+	}
+}
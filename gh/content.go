@@ -9,17 +9,63 @@ import (
 	"net/http"
 	"net/url"
 	"path"
+	"sort"
 	"strings"
+	"sync"
 
 	"repo-pack/model"
 )
 
+// Item is an entry of a repository listing: a file with the blob SHA, size and git mode
+// the listing reported for it, where it reported them.
 type Item struct {
 	Type string `json:"type"`
 	Path string `json:"path"`
 	URL  string `json:"url,omitempty"`
 	SHA  string `json:"sha,omitempty"`
 	Size int64  `json:"size,omitempty"`
+	Mode string `json:"mode,omitempty"`
+}
+
+// Git file modes reported by the Trees API.
+const (
+	ModeExecutable = "100755"
+	ModeSymlink    = "120000"
+	ModeSubmodule  = "160000"
+)
+
+// SpecialKind returns "symlink" or "submodule" for listing entries that are not regular files, and "" otherwise.
+// Symlinks are listed as blobs by the Trees API, so their mode is checked as well as their type.
+func SpecialKind(item Item) string {
+	switch {
+	case item.Type == "commit" || item.Type == "submodule" || item.Mode == ModeSubmodule:
+		return "submodule"
+	case item.Type == "symlink" || item.Mode == ModeSymlink:
+		return "symlink"
+	}
+	return ""
+}
+
+// LogSkipped reports the special entries a listing left out through Logf, e.g. "skipped 2 submodules, 1 symlink".
+func LogSkipped(skipped []Item) {
+	counts := map[string]int{}
+	for _, item := range skipped {
+		counts[SpecialKind(item)]++
+	}
+
+	var parts []string
+	for _, kind := range []string{"submodule", "symlink"} {
+		switch n := counts[kind]; n {
+		case 0:
+		case 1:
+			parts = append(parts, "1 "+kind)
+		default:
+			parts = append(parts, fmt.Sprintf("%d %ss", n, kind))
+		}
+	}
+	if len(parts) > 0 {
+		Logf("skipped %s", strings.Join(parts, ", "))
+	}
 }
 
 type TreeResponse struct {
@@ -30,10 +76,31 @@ type TreeResponse struct {
 
 var ErrNotFound = errors.New("not found")
 
+// DirectoryNotFoundError is returned when the ref of a URL exists but has no files under its directory.
+type DirectoryNotFoundError struct {
+	Dir string
+	Ref string
+}
+
+func (e *DirectoryNotFoundError) Error() string {
+	return fmt.Sprintf("directory %s not found at %s", e.Dir, e.Ref)
+}
+
+func (e *DirectoryNotFoundError) Unwrap() error {
+	return ErrNotFound
+}
+
+// Logf receives verbose diagnostics, it discards them unless replaced, e.g. under --verbose.
+var Logf = func(format string, args ...any) {}
+
+// OnListDirectory, if set, is called with each directory the contents API listed, one call at a time,
+// so that callers can show progress while a large directory is walked.
+var OnListDirectory func(dir string)
+
 // API makes a GET request to the GitHub API with the given endpoint and optional authentication token.
 // It returns the response body as a byte slice or an error if the request fails.
 func API(ctx context.Context, endpoint, token string) ([]byte, error) {
-	url := fmt.Sprintf("https://api.github.com/repos/%s", endpoint)
+	url := fmt.Sprintf("%s/repos/%s", apiBaseURL, endpoint)
 	req, err := http.NewRequestWithContext(ctx, "GET", url, nil)
 	if err != nil {
 		return nil, err
@@ -43,13 +110,18 @@ func API(ctx context.Context, endpoint, token string) ([]byte, error) {
 		req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", token))
 	}
 
-	client := http.DefaultClient
-	resp, err := client.Do(req)
+	resp, err := httpClient.Do(req)
 	if err != nil {
 		return nil, err
 	}
 	defer resp.Body.Close()
 
+	if isRateLimited(resp) {
+		return nil, ErrRateLimitExceeded
+	}
+	if resp.StatusCode == http.StatusNotFound {
+		return nil, fmt.Errorf("%w: %s", ErrNotFound, endpoint)
+	}
 	if resp.StatusCode < 200 || resp.StatusCode >= 300 {
 		return nil, fmt.Errorf("HTTP request failed with status code: %d", resp.StatusCode)
 	}
@@ -62,10 +134,101 @@ func API(ctx context.Context, endpoint, token string) ([]byte, error) {
 	return body, nil
 }
 
-// ViaContentsAPI retrieves a list of files in a GitHub repository directory using the Contents API.
+// ViaContentsAPI retrieves a list of files in a GitHub repository directory using the Contents API,
+// with up to concurrency directory requests at once, any number if it is 0.
 // It handles both files and subdirectories recursively.
-func ViaContentsAPI(ctx context.Context, urlComponents model.RepoURLComponents, token string) ([]string, error) {
-	files := []string{}
+func ViaContentsAPI(ctx context.Context, urlComponents model.RepoURLComponents, token string, concurrency int) ([]string, error) {
+	items, err := contentsAPIItems(ctx, urlComponents, token, concurrency)
+	if err != nil {
+		return nil, err
+	}
+	return ItemPaths(items), nil
+}
+
+// contentsAPIItems lists the file items of a directory recursively using the Contents API,
+// sorted by path. Subdirectories are listed in parallel, up to concurrency at a time, shared with
+// --concurrency, or without a limit if it is 0.
+// Symlinks and submodules are left out and reported through LogSkipped.
+func contentsAPIItems(ctx context.Context, urlComponents model.RepoURLComponents, token string, concurrency int) ([]Item, error) {
+	var (
+		mu      sync.Mutex
+		wg      sync.WaitGroup
+		files   = []Item{}
+		skipped []Item
+		walkErr error
+	)
+	walkCtx, cancel := context.WithCancel(ctx)
+	defer cancel()
+	var sem chan struct{}
+	if concurrency > 0 {
+		sem = make(chan struct{}, concurrency)
+	}
+
+	// A directory request holds the semaphore only while it is in flight, so that waiting
+	// subdirectories never block the directories that spawned them.
+	var list func(dir string)
+	list = func(dir string) {
+		defer wg.Done()
+		if sem != nil {
+			select {
+			case sem <- struct{}{}:
+			case <-walkCtx.Done():
+				return
+			}
+		}
+		subComponents := urlComponents
+		subComponents.Dir = dir
+		items, err := listContents(walkCtx, subComponents, token)
+		if sem != nil {
+			<-sem
+		}
+
+		mu.Lock()
+		defer mu.Unlock()
+		if err == nil && OnListDirectory != nil {
+			OnListDirectory(dir)
+		}
+		if err == nil {
+			for _, item := range items {
+				switch item.Type {
+				case "file":
+					files = append(files, item)
+				case "dir":
+					wg.Add(1)
+					go list(item.Path)
+				case "symlink", "submodule":
+					skipped = append(skipped, item)
+				default:
+					err = fmt.Errorf("ignoring item with unknown type: %s", item.Type)
+				}
+			}
+		}
+		if err != nil && walkErr == nil {
+			walkErr = err
+			cancel()
+		}
+	}
+	wg.Add(1)
+	list(urlComponents.Dir)
+	wg.Wait()
+
+	if walkErr != nil {
+		return nil, walkErr
+	}
+	if err := ctx.Err(); err != nil {
+		return nil, err
+	}
+	sort.Slice(files, func(i, j int) bool { return files[i].Path < files[j].Path })
+	LogSkipped(skipped)
+	return files, nil
+}
+
+// listContents returns the entries of the directory of urlComponents, without descending into subdirectories.
+func listContents(ctx context.Context, urlComponents model.RepoURLComponents, token string) ([]Item, error) {
+	if err := waitForRateLimit(ctx); err != nil {
+		return nil, err
+	}
+
 	contents, err := API(
 		ctx,
 		fmt.Sprintf(
@@ -82,27 +245,10 @@ func ViaContentsAPI(ctx context.Context, urlComponents model.RepoURLComponents,
 	}
 
 	var items []Item
-	err = json.Unmarshal(contents, &items)
-	if err != nil {
+	if err := json.Unmarshal(contents, &items); err != nil {
 		return nil, err
 	}
-
-	for _, item := range items {
-		switch item.Type {
-		case "file":
-			files = append(files, item.Path)
-		case "dir":
-			subFiles, err := ViaContentsAPI(ctx, urlComponents, token)
-			if err != nil {
-				return nil, err
-			}
-			files = append(files, subFiles...)
-		default:
-			return nil, fmt.Errorf("ignoring item with unknown type: %s", item.Type)
-		}
-	}
-
-	return files, nil
+	return items, nil
 }
 
 // ViaTreesAPI retrieves a list of files in a GitHub repository directory using the Git Trees API.
@@ -112,11 +258,21 @@ func ViaTreesAPI(
 	urlComponents model.RepoURLComponents,
 	token string,
 ) (files []string, truncated bool, err error) {
-	if !strings.HasSuffix(urlComponents.Dir, "/") {
+	items, truncated, err := treesAPIItems(ctx, urlComponents, token)
+	if err != nil {
+		return nil, false, err
+	}
+	return ItemPaths(items), truncated, nil
+}
+
+// treesAPIItems lists the blob items under the directory of urlComponents using the Git Trees API.
+// Symlinks and submodules are left out and reported through LogSkipped.
+func treesAPIItems(ctx context.Context, urlComponents model.RepoURLComponents, token string) (files []Item, truncated bool, err error) {
+	if urlComponents.Dir != "" && !strings.HasSuffix(urlComponents.Dir, "/") {
 		urlComponents.Dir += "/"
 	}
 
-	files = []string{}
+	files = []Item{}
 	contents, err := API(
 		ctx,
 		fmt.Sprintf(
@@ -137,56 +293,199 @@ func ViaTreesAPI(
 		return nil, false, err
 	}
 
+	var skipped []Item
 	for _, item := range treeResponse.Tree {
-		if item.Type == "blob" && strings.HasPrefix(item.Path, urlComponents.Dir) {
-			files = append(files, item.Path)
+		// A directory that turns out to be a file, as Contents API URLs may name either, lists just that file.
+		if !strings.HasPrefix(item.Path, urlComponents.Dir) && item.Path+"/" != urlComponents.Dir {
+			continue
+		}
+		if SpecialKind(item) != "" {
+			skipped = append(skipped, item)
+		} else if item.Type == "blob" {
+			files = append(files, item)
 		}
 	}
+	LogSkipped(skipped)
 
 	truncated = treeResponse.Truncated
+	if truncated {
+		Logf("trees API truncated at %d entries; falling back to contents API", len(treeResponse.Tree))
+	}
 
 	return files, truncated, nil
 }
 
+// ItemPaths returns the paths of items, for callers that only need those.
+func ItemPaths(items []Item) []string {
+	paths := make([]string, len(items))
+	for i, item := range items {
+		paths[i] = item.Path
+	}
+	return paths
+}
+
+// ViaTreeSHA lists the files of the tree object treeSHA using the Git Trees API, bypassing ref resolution.
+// The tree is taken to be the directory of urlComponents, so the returned paths are prefixed with it.
+func ViaTreeSHA(ctx context.Context, urlComponents model.RepoURLComponents, treeSHA, token string) (files []string, truncated bool, err error) {
+	items, truncated, err := TreeSHAItems(ctx, urlComponents, treeSHA, token)
+	if err != nil {
+		return nil, false, err
+	}
+	return ItemPaths(items), truncated, nil
+}
+
+// TreeSHAItems is ViaTreeSHA returning the file items, with their blob SHA and size.
+func TreeSHAItems(ctx context.Context, urlComponents model.RepoURLComponents, treeSHA, token string) (files []Item, truncated bool, err error) {
+	contents, err := API(
+		ctx,
+		fmt.Sprintf(
+			"%s/%s/git/trees/%s?recursive=1",
+			urlComponents.Owner,
+			urlComponents.Repository,
+			treeSHA,
+		),
+		token,
+	)
+	if err != nil {
+		return nil, false, err
+	}
+
+	var treeResponse TreeResponse
+	err = json.Unmarshal(contents, &treeResponse)
+	if err != nil {
+		return nil, false, err
+	}
+
+	files = []Item{}
+	var skipped []Item
+	for _, item := range treeResponse.Tree {
+		if SpecialKind(item) != "" {
+			skipped = append(skipped, item)
+		} else if item.Type == "blob" {
+			item.Path = path.Join(urlComponents.Dir, item.Path)
+			files = append(files, item)
+		}
+	}
+	LogSkipped(skipped)
+
+	return files, treeResponse.Truncated, nil
+}
+
 // RepoListingSlashBranchSupport fetches repository listing recursively.
 // It uses the provided context, repository components, and token for authentication.
 // It returns the list of files, the final reference, and an error (if any).
-func RepoListingSlashBranchSupport(ctx context.Context, components *model.RepoURLComponents, token string) ([]string, string, error) {
-	var files []string
-	var isTruncated bool
-
-	ref := components.Ref
-	dir := components.Dir
+func RepoListingSlashBranchSupport(ctx context.Context, components *model.RepoURLComponents, token string, concurrency int) ([]string, string, error) {
+	items, ref, err := RepoListingItems(ctx, components, token, concurrency)
+	if err != nil {
+		return nil, "", err
+	}
+	return ItemPaths(items), ref, nil
+}
 
-	decodedDir, err := url.QueryUnescape(dir)
+// RepoListingItems is RepoListingSlashBranchSupport returning the file items, with their blob SHA and size.
+// While the ref is not found, leading directory parts are moved into it, and components is updated to the
+// ref and directory that were found. Once a ref is found, a directory without files under it is reported
+// as a *DirectoryNotFoundError rather than by moving more parts into the ref. A truncated tree is listed
+// through the Contents API with up to concurrency directory requests at once, any number if it is 0.
+func RepoListingItems(ctx context.Context, components *model.RepoURLComponents, token string, concurrency int) ([]Item, string, error) {
+	decodedDir, err := url.QueryUnescape(components.Dir)
 	if err != nil {
-		return nil, "", fmt.Errorf("error decoding: %s", dir)
+		return nil, "", fmt.Errorf("error decoding: %s", components.Dir)
 	}
 
-	dirParts := strings.Split(decodedDir, "/")
+	ref := components.Ref
+	dirParts := strings.Split(strings.Trim(decodedDir, "/"), "/")
+	if dirParts[0] == "" {
+		dirParts = nil
+	}
 
-	for len(dirParts) > 0 {
-		content, truncated, err := ViaTreesAPI(ctx, *components, token)
-		if err == nil {
-			files = content
-			isTruncated = truncated
-			break
-		} else if errors.Is(err, ErrNotFound) {
+	for {
+		files, truncated, err := treesAPIItems(ctx, *components, token)
+		if errors.Is(err, ErrNotFound) {
+			if len(dirParts) == 0 {
+				return nil, "", fmt.Errorf("%w: no ref %s in %s/%s", ErrNotFound, ref, components.Owner, components.Repository)
+			}
 			ref = path.Join(ref, dirParts[0])
 			dirParts = dirParts[1:]
+			components.Ref = ref
 			components.Dir = strings.Join(dirParts, "/")
-		} else {
+			continue
+		}
+		if err != nil {
 			return nil, "", err
 		}
+
+		// A truncated tree still lists its first entries, so they are dropped rather than completed: the
+		// contents API lists the whole directory, at the cost of one request per subdirectory.
+		if truncated {
+			files, err = contentsAPIItems(ctx, *components, token, concurrency)
+			if err != nil {
+				return nil, "", err
+			}
+		}
+		if len(files) == 0 && components.Dir != "" {
+			return nil, "", &DirectoryNotFoundError{Dir: components.Dir, Ref: ref}
+		}
+		return files, ref, nil
 	}
+}
 
-	if len(files) == 0 && isTruncated {
-		files, err := ViaContentsAPI(ctx, *components, token)
+// ResolveRef moves leading directory parts of components into its ref while no commit is found for the ref,
+// as RepoListingItems does, without listing any files. components is updated to the ref and directory found,
+// and to the commit the ref points to.
+func ResolveRef(ctx context.Context, components *model.RepoURLComponents, token string) error {
+	decodedDir, err := url.QueryUnescape(components.Dir)
+	if err != nil {
+		return fmt.Errorf("error decoding: %s", components.Dir)
+	}
+	dirParts := strings.Split(strings.Trim(decodedDir, "/"), "/")
+	if dirParts[0] == "" {
+		dirParts = nil
+	}
+
+	resolved := *components
+	for {
+		sha, err := ResolveCommitSHA(ctx, resolved, token)
+		if errors.Is(err, ErrNotFound) && len(dirParts) > 0 {
+			resolved.Ref = path.Join(resolved.Ref, dirParts[0])
+			dirParts = dirParts[1:]
+			resolved.Dir = strings.Join(dirParts, "/")
+			continue
+		}
 		if err != nil {
-			return nil, "", err
+			return err
 		}
-		return files, ref, nil
+		resolved.CommitSHA = sha
+		*components = resolved
+		return nil
+	}
+}
+
+// ResolveCommitSHA returns the full SHA of the commit that ref (a branch, tag or abbreviated SHA) points to.
+func ResolveCommitSHA(ctx context.Context, components model.RepoURLComponents, token string) (string, error) {
+	contents, err := API(
+		ctx,
+		fmt.Sprintf(
+			"%s/%s/commits/%s",
+			components.Owner,
+			components.Repository,
+			url.PathEscape(components.Ref),
+		),
+		token,
+	)
+	if err != nil {
+		return "", err
+	}
+
+	var commit struct {
+		SHA string `json:"sha"`
+	}
+	if err := json.Unmarshal(contents, &commit); err != nil {
+		return "", err
+	}
+	if commit.SHA == "" {
+		return "", fmt.Errorf("no commit found for ref %s", components.Ref)
 	}
 
-	return files, ref, nil
+	return commit.SHA, nil
 }
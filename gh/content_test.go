@@ -0,0 +1,192 @@
+package gh
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net/http"
+	"reflect"
+	"strings"
+	"sync/atomic"
+	"testing"
+	"time"
+
+	"repo-pack/model"
+)
+
+func TestRepoListingItemsSeparatesRefAndDirectory(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/git/trees/feature/x", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `{"tree": [{"type": "blob", "path": "docs/a.md"}, {"type": "blob", "path": "src/main.go"}]}`)
+	})
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "feature", Dir: "x/docs"}
+	items, ref, err := RepoListingItems(context.Background(), &components, "", 0)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if ref != "feature/x" || components.Ref != "feature/x" || components.Dir != "docs" || len(items) != 1 {
+		t.Errorf("expected docs/a.md at ref feature/x, got %v at ref %s, dir %s", items, components.Ref, components.Dir)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "feature", Dir: "x/missing"}
+	_, _, err = RepoListingItems(context.Background(), &components, "", 0)
+	var dirErr *DirectoryNotFoundError
+	if !errors.As(err, &dirErr) || dirErr.Dir != "missing" || dirErr.Ref != "feature/x" {
+		t.Errorf("expected directory missing not found at feature/x, got: %v", err)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "nope", Dir: "docs"}
+	_, _, err = RepoListingItems(context.Background(), &components, "", 0)
+	if !errors.Is(err, ErrNotFound) || errors.As(err, &dirErr) {
+		t.Errorf("expected the ref not to be found, got: %v", err)
+	}
+}
+
+func TestResolveRefMovesDirectoryPartsIntoRef(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/commits/feature/x", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `{"sha": "abc123"}`)
+	})
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "feature", Dir: "x/docs"}
+	if err := ResolveRef(context.Background(), &components, ""); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if components.Ref != "feature/x" || components.Dir != "docs" || components.CommitSHA != "abc123" {
+		t.Errorf("expected docs at ref feature/x and commit abc123, got %+v", components)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "nope", Dir: "docs"}
+	if err := ResolveRef(context.Background(), &components, ""); !errors.Is(err, ErrNotFound) {
+		t.Errorf("expected the ref not to be found, got: %v", err)
+	}
+}
+
+func TestTreesAPISkipsSymlinksAndSubmodules(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `{"tree": [
+			{"type": "blob", "path": "a.md", "mode": "100644"},
+			{"type": "blob", "path": "link", "mode": "120000"},
+			{"type": "commit", "path": "vendor/x", "mode": "160000"},
+			{"type": "commit", "path": "vendor/y", "mode": "160000"}
+		]}`)
+	})
+	serveGitHub(t, mux)
+
+	var logged []string
+	Logf = func(format string, args ...any) { logged = append(logged, fmt.Sprintf(format, args...)) }
+	defer func() { Logf = func(format string, args ...any) {} }()
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main"}
+	files, _, err := ViaTreesAPI(context.Background(), components, "")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if len(files) != 1 || files[0] != "a.md" {
+		t.Errorf("expected only a.md, got %v", files)
+	}
+	if len(logged) != 1 || logged[0] != "skipped 2 submodules, 1 symlink" {
+		t.Errorf("expected a note about the skipped entries, got %v", logged)
+	}
+}
+
+func TestRepoListingItemsReplacesTruncatedTree(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/git/trees/main", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `{"tree": [{"type": "blob", "path": "docs/a.md"}], "truncated": true}`)
+	})
+	mux.HandleFunc("/api/repos/owner/repo/contents/docs", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `[{"type": "file", "path": "docs/a.md"}, {"type": "dir", "path": "docs/guide"}]`)
+	})
+	mux.HandleFunc("/api/repos/owner/repo/contents/docs/guide", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `[{"type": "file", "path": "docs/guide/b.md"}]`)
+	})
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "docs"}
+	items, _, err := RepoListingItems(context.Background(), &components, "", 0)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	paths := ItemPaths(items)
+	if len(paths) != 2 || paths[0] != "docs/a.md" || paths[1] != "docs/guide/b.md" {
+		t.Errorf("expected the complete contents listing, got %v", paths)
+	}
+}
+
+func TestContentsAPIItemsListsNestedDirectoriesSorted(t *testing.T) {
+	listings := map[string]string{
+		"docs":         `[{"type": "dir", "path": "docs/z"}, {"type": "file", "path": "docs/b.md"}, {"type": "dir", "path": "docs/a"}]`,
+		"docs/a":       `[{"type": "file", "path": "docs/a/one.md"}, {"type": "dir", "path": "docs/a/deep"}]`,
+		"docs/a/deep":  `[{"type": "file", "path": "docs/a/deep/two.md"}, {"type": "symlink", "path": "docs/a/deep/link"}]`,
+		"docs/z":       `[{"type": "dir", "path": "docs/z/empty"}, {"type": "file", "path": "docs/z/three.md"}]`,
+		"docs/z/empty": `[]`,
+	}
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/contents/", func(w http.ResponseWriter, r *http.Request) {
+		listing, ok := listings[strings.TrimPrefix(r.URL.Path, "/api/repos/owner/repo/contents/")]
+		if !ok {
+			http.NotFound(w, r)
+			return
+		}
+		fmt.Fprint(w, listing)
+	})
+	serveGitHub(t, mux)
+
+	listed := 0
+	OnListDirectory = func(string) { listed++ }
+	defer func() { OnListDirectory = nil }()
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "docs"}
+	items, err := contentsAPIItems(context.Background(), components, "", 2)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected := []string{"docs/a/deep/two.md", "docs/a/one.md", "docs/b.md", "docs/z/three.md"}
+	if paths := ItemPaths(items); !reflect.DeepEqual(paths, expected) {
+		t.Errorf("expected %v, got %v", expected, paths)
+	}
+	if listed != len(listings) {
+		t.Errorf("expected %d directories reported, got %d", len(listings), listed)
+	}
+
+	listings["docs/z/empty"] = `[{"type": "unknown", "path": "docs/z/empty/x"}]`
+	if _, err := contentsAPIItems(context.Background(), components, "", 2); err == nil {
+		t.Errorf("expected an error for an entry of unknown type")
+	}
+}
+
+func TestContentsAPIItemsSharesConcurrency(t *testing.T) {
+	var inFlight, peak atomic.Int32
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/contents/", func(w http.ResponseWriter, r *http.Request) {
+		n := inFlight.Add(1)
+		defer inFlight.Add(-1)
+		for {
+			p := peak.Load()
+			if n <= p || peak.CompareAndSwap(p, n) {
+				break
+			}
+		}
+		time.Sleep(20 * time.Millisecond)
+
+		if strings.TrimPrefix(r.URL.Path, "/api/repos/owner/repo/contents/") == "docs" {
+			fmt.Fprint(w, `[{"type": "dir", "path": "docs/a"}, {"type": "dir", "path": "docs/b"}, {"type": "dir", "path": "docs/c"}, {"type": "dir", "path": "docs/d"}]`)
+			return
+		}
+		fmt.Fprint(w, `[]`)
+	})
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "docs"}
+	if _, err := contentsAPIItems(context.Background(), components, "", 2); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if got := peak.Load(); got > 2 {
+		t.Errorf("expected at most 2 directory requests at once, got: %d", got)
+	}
+}
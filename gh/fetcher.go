@@ -9,9 +9,14 @@ import (
 	"io"
 	"net/http"
 	"net/url"
+	"os"
+	"path"
 	"path/filepath"
 	"strconv"
 	"strings"
+	"sync"
+	"sync/atomic"
+	"time"
 
 	"repo-pack/helpers"
 	"repo-pack/model"
@@ -22,109 +27,491 @@ var (
 	ErrRateLimitExceeded  = errors.New("rate limit exceeded")
 	ErrRepositoryNotFound = errors.New("repository not found")
 	ErrInvalidToken       = errors.New("invalid token")
+	ErrInsufficientScope  = errors.New("token lacks access to the repository")
 	ErrFetchError         = errors.New("could not obtain repository data from the GitHub API")
 )
 
 // RepoInfo represents information about a repository
 type RepoInfo struct {
 	Private bool `json:"private"`
+
+	// Size is the size of the repository in kilobytes
+	Size int64 `json:"size"`
+
+	DefaultBranch string `json:"default_branch"`
 }
 
-// FetchRepoIsPrivate checks if a repository is private or not on GitHub.
-func FetchRepoIsPrivate(ctx context.Context, components *model.RepoURLComponents, token string) (bool, error) {
-	url := fmt.Sprintf("https://api.github.com/repos/%s/%s", components.Owner, components.Repository)
+// FetchRepoInfo fetches information about a repository from GitHub.
+func FetchRepoInfo(ctx context.Context, components *model.RepoURLComponents, token string) (RepoInfo, error) {
+	url := fmt.Sprintf("%s/repos/%s/%s", apiBaseURL, components.Owner, components.Repository)
 	req, err := http.NewRequestWithContext(ctx, http.MethodGet, url, nil)
 	if err != nil {
-		return false, err
+		return RepoInfo{}, err
 	}
 
-	req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", token))
+	if token != "" {
+		req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", token))
+	}
 
-	resp, err := http.DefaultClient.Do(req)
+	resp, err := httpClient.Do(req)
 	if err != nil {
-		return false, err
+		return RepoInfo{}, err
 	}
 	defer resp.Body.Close()
 
 	switch resp.StatusCode {
 	case http.StatusNotFound:
-		return false, fmt.Errorf("repo not found: %s/%s", components.Owner, components.Repository)
+		return RepoInfo{}, fmt.Errorf("%w: %s/%s", ErrRepositoryNotFound, components.Owner, components.Repository)
 	case http.StatusUnauthorized:
-		return false, ErrInvalidToken
+		return RepoInfo{}, ErrInvalidToken
 	case http.StatusForbidden:
 		if resp.Header.Get("X-RateLimit-Remaining") == "0" {
-			return false, ErrRateLimitExceeded
+			return RepoInfo{}, ErrRateLimitExceeded
 		}
+		return RepoInfo{}, ErrFetchError
 	case http.StatusOK:
 		var repoInfo RepoInfo
 		if err := json.NewDecoder(resp.Body).Decode(&repoInfo); err != nil {
-			return false, err
+			return RepoInfo{}, err
 		}
-		return repoInfo.Private, nil
+		return repoInfo, nil
 	default:
-		return false, ErrFetchError
+		return RepoInfo{}, ErrFetchError
 	}
+}
 
-	return false, nil
+// ResolveDefaultBranch sets the ref of components to the default branch of the repository if no ref is set yet.
+func ResolveDefaultBranch(ctx context.Context, components *model.RepoURLComponents, token string) error {
+	if components.Ref != "" {
+		return nil
+	}
+	repoInfo, err := FetchRepoInfo(ctx, components, token)
+	if err != nil {
+		return err
+	}
+	components.Ref = repoInfo.DefaultBranch
+	return nil
 }
 
-// isLfsResponse checks if the HTTP response potentially contains a Git LFS response.
-func isLfsResponse(res *http.Response) bool {
-	if contentLength, err := strconv.Atoi(res.Header.Get("Content-Length")); err == nil && 128 < contentLength &&
-		contentLength < 140 {
-		bufr := make([]byte, 40)
-		_, err := io.ReadFull(res.Body, bufr)
-		if err != nil {
+// FetchRepoIsPrivate checks if a repository is private or not on GitHub.
+func FetchRepoIsPrivate(ctx context.Context, components *model.RepoURLComponents, token string) (bool, error) {
+	repoInfo, err := FetchRepoInfo(ctx, components, token)
+	if err != nil {
+		return false, err
+	}
+	return repoInfo.Private, nil
+}
+
+// Base URLs of the hosts serving raw file content, variables so tests can point them elsewhere.
+var (
+	rawBaseURL   = "https://raw.githubusercontent.com"
+	mediaBaseURL = "https://media.githubusercontent.com"
+)
+
+// StatusError is an unexpected HTTP status received for a file.
+type StatusError struct {
+	StatusCode int
+	Status     string
+	Path       string
+}
+
+func (e *StatusError) Error() string {
+	return fmt.Sprintf("HTTP %s for %s", e.Status, e.Path)
+}
+
+// IsRetryable reports whether err is likely transient: a network error, a stalled download, a 5xx
+// or 429 status, or a rate limit. Cancellation, missing files and local errors are not retried.
+func IsRetryable(err error) bool {
+	if errors.Is(err, context.Canceled) || errors.Is(err, context.DeadlineExceeded) {
+		return false
+	}
+	if errors.Is(err, ErrRateLimitExceeded) || errors.Is(err, ErrIdleTimeout) || errors.Is(err, ErrTransferTimeout) {
+		return true
+	}
+	var statusErr *StatusError
+	if errors.As(err, &statusErr) {
+		return statusErr.StatusCode >= 500 || statusErr.StatusCode == http.StatusTooManyRequests
+	}
+	var urlErr *url.Error
+	return errors.As(err, &urlErr)
+}
+
+// ErrorKind returns a short, stable name for the kind of err, for machine-readable reports such as
+// --errors-json: file_gone, not_found, rate_limited, invalid_token, insufficient_scope, checksum_mismatch,
+// checksum_missing, idle_timeout, http_status, canceled, timeout, network or other.
+func ErrorKind(err error) string {
+	var statusErr *StatusError
+	var urlErr *url.Error
+	switch {
+	case errors.Is(err, ErrFileGone):
+		return "file_gone"
+	case errors.Is(err, ErrNotFound) || errors.Is(err, ErrRepositoryNotFound):
+		return "not_found"
+	case errors.Is(err, ErrRateLimitExceeded):
+		return "rate_limited"
+	case errors.Is(err, ErrInvalidToken):
+		return "invalid_token"
+	case errors.Is(err, ErrInsufficientScope):
+		return "insufficient_scope"
+	case errors.Is(err, helpers.ErrChecksumMismatch):
+		return "checksum_mismatch"
+	case errors.Is(err, helpers.ErrChecksumMissing):
+		return "checksum_missing"
+	case errors.Is(err, ErrIdleTimeout):
+		return "idle_timeout"
+	case errors.As(err, &statusErr):
+		return "http_status"
+	case errors.Is(err, context.Canceled):
+		return "canceled"
+	case errors.Is(err, context.DeadlineExceeded) || errors.Is(err, ErrTransferTimeout):
+		return "timeout"
+	case errors.As(err, &urlErr):
+		return "network"
+	default:
+		return "other"
+	}
+}
+
+// ErrSkipped marks files that were deliberately not downloaded.
+var ErrSkipped = errors.New("skipped")
+
+// ErrAlreadyVerified marks files skipped because the verification cache shows their local copy verified and
+// unchanged, the expected outcome of a --verify-cache run rather than something to warn about.
+var ErrAlreadyVerified = fmt.Errorf("%w: already verified", ErrSkipped)
+
+// ErrFileGone marks files that were listed but not found when downloading them, e.g. because the ref
+// was force-pushed during the run or an LFS object was never uploaded.
+var ErrFileGone = errors.New("file listed but no longer available")
+
+// LfsStats counts the files downloaded through Git LFS and their combined size. It is safe for concurrent use.
+type LfsStats struct {
+	files atomic.Int64
+	bytes atomic.Int64
+	sizes sync.Map
+}
+
+// Files returns how many files were saved from Git LFS objects.
+func (s *LfsStats) Files() int64 {
+	return s.files.Load()
+}
+
+// Bytes returns the combined size of the Git LFS objects saved, as their pointers reported it.
+func (s *LfsStats) Bytes() int64 {
+	return s.bytes.Load()
+}
+
+// Record counts the repository file path as saved from a Git LFS object of size bytes.
+func (s *LfsStats) Record(path string, size int64) {
+	s.files.Add(1)
+	s.bytes.Add(size)
+	s.sizes.Store(path, size)
+}
+
+// ObjectSize returns the size the pointer of the repository file path reported, if path was saved from a
+// Git LFS object. Listings report the size of the pointer instead.
+func (s *LfsStats) ObjectSize(path string) (int64, bool) {
+	size, ok := s.sizes.Load(path)
+	if !ok {
+		return 0, false
+	}
+	return size.(int64), true
+}
+
+// SizeBudget caps the combined size of the Git LFS objects of a run, which listings only report the pointers of.
+// It is safe for concurrent use.
+type SizeBudget struct {
+	left atomic.Int64
+}
+
+// NewSizeBudget creates a budget of size bytes.
+func NewSizeBudget(size int64) *SizeBudget {
+	budget := &SizeBudget{}
+	budget.left.Store(size)
+	return budget
+}
+
+// take reserves size bytes, or reports false and reserves nothing if fewer are left.
+func (b *SizeBudget) take(size int64) bool {
+	for {
+		left := b.left.Load()
+		if size > left {
 			return false
 		}
+		if b.left.CompareAndSwap(left, left-size) {
+			return true
+		}
+	}
+}
 
-		restOfBody, err := io.ReadAll(res.Body)
-		if err != nil {
-			return false
+// FetchOptions controls how files are downloaded and saved.
+type FetchOptions struct {
+	// MaxLfsSize skips Git LFS files whose pointer reports a larger size; zero means no limit.
+	MaxLfsSize int64
+
+	// NoLfs saves Git LFS pointers as they are instead of downloading the objects they point at.
+	NoLfs bool
+
+	// LfsBudget, if set, skips Git LFS objects once their sizes would add up to more than it holds.
+	LfsBudget *SizeBudget
+
+	// LfsStats, if set, counts the files saved from Git LFS objects and their size.
+	LfsStats *LfsStats
+
+	// MemoryBudget, if set, bounds the bytes of in-flight downloads, each taking its listed size, Save.Size,
+	// before its request is sent, or its Content-Length if it was listed without one.
+	MemoryBudget *helpers.MemoryBudget
+
+	// NoAnchor saves files at their full path in the repository instead of under the last segment of the URL directory.
+	NoAnchor bool
+
+	// Flattened, if set, maps repository paths to the names they are saved under in a single directory.
+	Flattened map[string]string
+
+	// StripComponents drops this many leading directories from local paths, like tar --strip-components.
+	StripComponents int
+
+	// Since, if set, skips files whose last commit is older, at the cost of one API request per file.
+	Since time.Time
+
+	// IfNewer skips files whose local copy was modified after their last commit upstream,
+	// at the cost of one API request per file that exists locally.
+	IfNewer bool
+
+	// URL, if set, is where the file is downloaded from in place of the URL RawURL derives from its path.
+	URL string
+
+	// RemoteCopies holds repository paths whose local copy is kept, with the download saved
+	// next to it under a .remote suffix instead.
+	RemoteCopies map[string]bool
+
+	Save helpers.SaveOptions
+}
+
+// ReserveMemory waits for n bytes of MemoryBudget, the whole budget if n is negative, and returns the
+// function giving them back. Without a budget it returns at once. Files reserve their size before their
+// request is sent, so that the wait does not count against the transfer timeout.
+func (o FetchOptions) ReserveMemory(ctx context.Context, n int64) (func(), error) {
+	if o.MemoryBudget == nil {
+		return func() {}, nil
+	}
+	acquired, err := o.MemoryBudget.Acquire(ctx, n)
+	if err != nil {
+		return nil, err
+	}
+	return func() { o.MemoryBudget.Release(acquired) }, nil
+}
+
+// CheckLfsObject returns an error wrapping ErrSkipped if the Git LFS object pointer describes is not to be
+// downloaded because of MaxLfsSize or LfsBudget, and reserves its size in LfsBudget otherwise.
+func (o FetchOptions) CheckLfsObject(pointer helpers.LfsPointer) error {
+	if o.MaxLfsSize > 0 && pointer.Size > o.MaxLfsSize {
+		return fmt.Errorf("%w: LFS object of %d bytes is over the %d byte limit", ErrSkipped, pointer.Size, o.MaxLfsSize)
+	}
+	if o.LfsBudget != nil && !o.LfsBudget.take(pointer.Size) {
+		return fmt.Errorf("%w: LFS object of %d bytes is over what is left of the total size limit", ErrSkipped, pointer.Size)
+	}
+	return nil
+}
+
+// LocalPath returns where the repository file path is saved, relative to the working directory.
+// It returns "" for files that StripComponents strips away entirely.
+func (o FetchOptions) LocalPath(path string, components *model.RepoURLComponents) string {
+	local, flattened := o.Flattened[path]
+	if !flattened {
+		local = path
+		if !o.NoAnchor {
+			local = helpers.LocalPath(path, components.Dir)
 		}
+		if o.StripComponents > 0 {
+			parts := strings.Split(local, "/")
+			if len(parts) <= o.StripComponents {
+				return ""
+			}
+			local = strings.Join(parts[o.StripComponents:], "/")
+		}
+	}
+	if o.RemoteCopies[path] {
+		local += ".remote"
+	}
+	return local
+}
+
+// LocalRoot returns the local directory the files under the directory of components are saved in, or the
+// local path of the file for file URLs, following the same layout as LocalPath. "." is the working directory.
+func (o FetchOptions) LocalRoot(components *model.RepoURLComponents) string {
+	dir := strings.Trim(components.Dir, "/")
+	if components.File {
+		if root := o.LocalPath(dir, components); root != "" {
+			return root
+		}
+		return "."
+	}
+	// Any file directly in the directory is saved in the root.
+	return path.Dir(o.LocalPath(path.Join(dir, "_"), components))
+}
+
+// SinceFor returns the time files last changed upstream before are skipped: Since, or with IfNewer
+// the modification time of the local copy at localPath if that exists and is later. Zero means no check.
+func (o FetchOptions) SinceFor(localPath string) time.Time {
+	since := o.Since
+	if !o.IfNewer {
+		return since
+	}
+	if info, err := os.Stat(filepath.FromSlash(localPath)); err == nil && info.ModTime().After(since) {
+		since = info.ModTime()
+	}
+	return since
+}
 
-		res.Body.Close()
-		fullBody := append(bufr, restOfBody...)
-		res.Body = io.NopCloser(bytes.NewReader(fullBody))
+// LfsPointerResponse checks if the HTTP response contains a Git LFS pointer and returns it.
+// Small bodies are read in full to validate the pointer, then restored so they can still be saved.
+func LfsPointerResponse(res *http.Response) (helpers.LfsPointer, bool) {
+	contentLength, err := strconv.Atoi(res.Header.Get("Content-Length"))
+	if err != nil || contentLength <= 0 || contentLength > helpers.MaxLfsPointerSize {
+		return helpers.LfsPointer{}, false
+	}
 
-		return strings.HasPrefix(string(bufr), "version https://git-lfs.github.com/spec/v1")
+	body, err := io.ReadAll(res.Body)
+	res.Body.Close()
+	res.Body = io.NopCloser(bytes.NewReader(body))
+	if err != nil {
+		return helpers.LfsPointer{}, false
 	}
-	return false
+
+	return helpers.ParseLfsPointer(body)
+}
+
+// FetchLfsPointer downloads the repository file path and returns it as a Git LFS pointer, if it is one,
+// without following it to the object. Only files listed as small as helpers.MaxLfsPointerSize can be pointers.
+func FetchLfsPointer(ctx context.Context, path string, components *model.RepoURLComponents, token string) (helpers.LfsPointer, bool, error) {
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, RawURL(path, components), nil)
+	if err != nil {
+		return helpers.LfsPointer{}, false, err
+	}
+	if token != "" {
+		req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", token))
+	}
+
+	resp, err := httpClient.Do(req)
+	if err != nil {
+		return helpers.LfsPointer{}, false, fmt.Errorf("HTTP error for %s: %w", path, err)
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode != http.StatusOK {
+		return helpers.LfsPointer{}, false, &StatusError{StatusCode: resp.StatusCode, Status: resp.Status, Path: path}
+	}
+
+	data, err := io.ReadAll(io.LimitReader(resp.Body, helpers.MaxLfsPointerSize+1))
+	if err != nil {
+		return helpers.LfsPointer{}, false, fmt.Errorf("error reading %s: %w", path, err)
+	}
+	pointer, ok := helpers.ParseLfsPointer(data)
+	return pointer, ok, nil
+}
+
+// RawURL returns the raw.githubusercontent.com URL serving the content of the repository file path,
+// or for release URLs the download URL of the release asset path.
+func RawURL(path string, components *model.RepoURLComponents) string {
+	if components.Release {
+		return ReleaseAssetURL(path, components)
+	}
+	if components.Wiki {
+		return fmt.Sprintf(
+			"%s/wiki/%s/%s/%s",
+			rawBaseURL,
+			components.Owner,
+			strings.TrimSuffix(components.Repository, ".wiki"),
+			url.PathEscape(path),
+		)
+	}
+	return fmt.Sprintf(
+		"%s/%s/%s/%s/%s",
+		rawBaseURL,
+		components.Owner,
+		components.Repository,
+		components.Ref,
+		url.PathEscape(path),
+	)
 }
 
 // FetchPublicFile downloads a file from a public GitHub repository, handling Git LFS if necessary and saves it.
-func FetchPublicFile(ctx context.Context, path string, components *model.RepoURLComponents) error {
+// Files skipped because of opts return an error wrapping ErrSkipped.
+func FetchPublicFile(ctx context.Context, path string, components *model.RepoURLComponents, opts FetchOptions) error {
+	return fetchFile(ctx, path, components, "", opts)
+}
+
+// fetchFile is FetchPublicFile authenticating the raw and LFS requests with token, if set,
+// so that files of private repositories can be downloaded.
+func fetchFile(ctx context.Context, path string, components *model.RepoURLComponents, token string, opts FetchOptions) error {
 	user := components.Owner
 	repository := components.Repository
 	ref := components.Ref
+	localPath := opts.LocalPath(path, components)
 
-	rawURL := fmt.Sprintf(
-		"https://raw.githubusercontent.com/%s/%s/%s/%s",
-		user,
-		repository,
-		ref,
-		url.PathEscape(path),
-	)
+	// A file verified on disk is still needed by an archive or --output -.
+	if opts.Save.Checksums != nil && !opts.Save.InMemory() && opts.Save.Checksums.AlreadyVerified(localPath) {
+		return ErrAlreadyVerified
+	}
+
+	rawURL := opts.URL
+	if rawURL == "" {
+		rawURL = RawURL(path, components)
+	}
+
+	// Files listed without a size reserve their Content-Length once the response arrives.
+	release := func() {}
+	reserved := opts.Save.Size > 0
+	if reserved {
+		var err error
+		if release, err = opts.ReserveMemory(ctx, opts.Save.Size); err != nil {
+			return fmt.Errorf("waiting for memory budget for %s: %w", path, err)
+		}
+	}
+	defer func() { release() }()
 
 	req, err := http.NewRequestWithContext(ctx, http.MethodGet, rawURL, nil)
 	if err != nil {
 		return fmt.Errorf("creating request for %s: %w", path, err)
 	}
+	// Release assets are served from other hosts, which must not see the token.
+	if token != "" && !components.Release {
+		req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", token))
+	}
 
-	resp, err := http.DefaultClient.Do(req)
+	resp, err := httpClient.Do(req)
 	if err != nil {
 		return fmt.Errorf("HTTP error for %s: %w", path, err)
 	}
 	defer resp.Body.Close()
 
+	if resp.StatusCode == http.StatusNotFound {
+		return fmt.Errorf("%w: %s at %s", ErrFileGone, path, ref)
+	}
 	if resp.StatusCode != http.StatusOK {
 		resp.Body.Close()
-		return fmt.Errorf("HTTP %s for %s", resp.Status, path)
+		return &StatusError{StatusCode: resp.StatusCode, Status: resp.Status, Path: path}
 	}
 
-	if isLfsResponse(resp) {
+	lfsSize := int64(-1)
+	if pointer, ok := LfsPointerResponse(resp); ok && !opts.NoLfs {
+		if err := opts.CheckLfsObject(pointer); err != nil {
+			return err
+		}
+		// The blob SHA is that of the pointer, not of the object it points at.
+		opts.Save.BlobSHA = ""
+		lfsSize = pointer.Size
+
+		resp.Body.Close()
+		release()
+		if release, err = opts.ReserveMemory(ctx, pointer.Size); err != nil {
+			release = func() {}
+			return fmt.Errorf("waiting for memory budget for %s: %w", path, err)
+		}
+		reserved = true
+
 		lfsURL := fmt.Sprintf(
-			"https://media.githubusercontent.com/media/%s/%s/%s/%s",
+			"%s/media/%s/%s/%s/%s",
+			mediaBaseURL,
 			user,
 			repository,
 			ref,
@@ -134,19 +521,42 @@ func FetchPublicFile(ctx context.Context, path string, components *model.RepoURL
 		if err != nil {
 			return fmt.Errorf("error creating LFS request for %s: %w", path, err)
 		}
-		resp, err = http.DefaultClient.Do(req)
+		if token != "" {
+			req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", token))
+		}
+		resp, err = httpClient.Do(req)
 		if err != nil {
-			resp.Body.Close()
 			return fmt.Errorf("HTTP error for LFS %s: %w", path, err)
 		}
+		if resp.StatusCode == http.StatusNotFound {
+			resp.Body.Close()
+			return fmt.Errorf("%w: LFS object of %s at %s", ErrFileGone, path, ref)
+		}
+		if resp.StatusCode != http.StatusOK {
+			resp.Body.Close()
+			return &StatusError{StatusCode: resp.StatusCode, Status: resp.Status, Path: "LFS " + path}
+		}
 	}
 
-	err = helpers.SaveFile(filepath.Base(components.Dir), path, resp.Body)
+	if resp.ContentLength >= 0 {
+		opts.Save.Size = resp.ContentLength
+	}
+	if !reserved {
+		if release, err = opts.ReserveMemory(ctx, resp.ContentLength); err != nil {
+			release = func() {}
+			resp.Body.Close()
+			return fmt.Errorf("waiting for memory budget for %s: %w", path, err)
+		}
+	}
+
+	err = helpers.SaveFile(localPath, resp.Body, opts.Save)
 	if err != nil {
 		resp.Body.Close()
-		return fmt.Errorf("error saving file %s %v", path, err)
+		return fmt.Errorf("error saving file %s: %w", path, err)
 	}
 
+	if lfsSize >= 0 && opts.LfsStats != nil {
+		opts.LfsStats.Record(path, lfsSize)
+	}
 	return nil
 }
-
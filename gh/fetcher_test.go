@@ -0,0 +1,341 @@
+package gh
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net/http"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+	"time"
+
+	"repo-pack/helpers"
+	"repo-pack/model"
+)
+
+func TestFetchPublicFileSkipsLargeLfsObject(t *testing.T) {
+	pointer := "version https://git-lfs.github.com/spec/v1\n" +
+		"oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n" +
+		"size 5368709120\n"
+
+	mediaRequests := 0
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/", func(w http.ResponseWriter, r *http.Request) {
+		w.Header().Set("Content-Length", fmt.Sprint(len(pointer)))
+		fmt.Fprint(w, pointer)
+	})
+	mux.HandleFunc("/media/", func(w http.ResponseWriter, r *http.Request) {
+		mediaRequests++
+	})
+	serveGitHub(t, mux)
+
+	dir := t.TempDir()
+	wd, _ := os.Getwd()
+	os.Chdir(dir)
+	defer os.Chdir(wd)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "assets"}
+	err := FetchPublicFile(context.Background(), "assets/video.mp4", &components, FetchOptions{MaxLfsSize: 1 << 30})
+
+	if !errors.Is(err, ErrSkipped) {
+		t.Errorf("expected skipped error, got: %v", err)
+	}
+	if mediaRequests != 0 {
+		t.Errorf("expected no media requests, got: %d", mediaRequests)
+	}
+	if _, err := os.Stat("assets/video.mp4"); !os.IsNotExist(err) {
+		t.Errorf("expected no file to be saved, got: %v", err)
+	}
+}
+
+func TestFetchPublicFileNoLfsSavesPointer(t *testing.T) {
+	pointer := "version https://git-lfs.github.com/spec/v1\n" +
+		"oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n" +
+		"size 5368709120\n"
+
+	mediaRequests := 0
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/", func(w http.ResponseWriter, r *http.Request) {
+		w.Header().Set("Content-Length", fmt.Sprint(len(pointer)))
+		fmt.Fprint(w, pointer)
+	})
+	mux.HandleFunc("/media/", func(w http.ResponseWriter, r *http.Request) {
+		mediaRequests++
+	})
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "assets"}
+	opts := FetchOptions{NoLfs: true}
+	opts.Save.Root = t.TempDir()
+	if err := FetchPublicFile(context.Background(), "assets/video.mp4", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if mediaRequests != 0 {
+		t.Errorf("expected no media requests, got: %d", mediaRequests)
+	}
+	saved, err := os.ReadFile(filepath.Join(opts.Save.Root, "assets", "video.mp4"))
+	if err != nil || string(saved) != pointer {
+		t.Errorf("expected the pointer to be saved as is, got: %q, %v", saved, err)
+	}
+}
+
+func TestFetchOptionsLocalPath(t *testing.T) {
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: ".config/nvim/lua"}
+	file := ".config/nvim/lua/plugins/init.lua"
+
+	if got := (FetchOptions{}).LocalPath(file, &components); got != "lua/plugins/init.lua" {
+		t.Errorf("expected anchored path lua/plugins/init.lua, got: %s", got)
+	}
+	if got := (FetchOptions{NoAnchor: true}).LocalPath(file, &components); got != file {
+		t.Errorf("expected full path %s, got: %s", file, got)
+	}
+	if got := (FetchOptions{StripComponents: 1}).LocalPath(file, &components); got != "plugins/init.lua" {
+		t.Errorf("expected stripped path plugins/init.lua, got: %s", got)
+	}
+	if got := (FetchOptions{StripComponents: 3}).LocalPath(file, &components); got != "" {
+		t.Errorf("expected the whole path to be stripped, got: %s", got)
+	}
+	if got := (FetchOptions{RemoteCopies: map[string]bool{file: true}}).LocalPath(file, &components); got != "lua/plugins/init.lua.remote" {
+		t.Errorf("expected the remote copy lua/plugins/init.lua.remote, got: %s", got)
+	}
+}
+
+func TestFetchOptionsLocalRoot(t *testing.T) {
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: ".config/nvim/lua"}
+
+	tests := map[string]FetchOptions{
+		"lua":              {},
+		".config/nvim/lua": {NoAnchor: true},
+		"nvim/lua":         {NoAnchor: true, StripComponents: 1},
+		".":                {StripComponents: 1},
+	}
+	for expected, opts := range tests {
+		if got := opts.LocalRoot(&components); got != expected {
+			t.Errorf("%+v: expected %s, got: %s", opts, expected, got)
+		}
+	}
+
+	if got := (FetchOptions{}).LocalRoot(&model.RepoURLComponents{}); got != "." {
+		t.Errorf("expected the working directory for the repository root, got: %s", got)
+	}
+	file := model.RepoURLComponents{Dir: "docs/guide.md", File: true}
+	if got := (FetchOptions{}).LocalRoot(&file); got != "guide.md" {
+		t.Errorf("expected the file itself for file URLs, got: %s", got)
+	}
+}
+
+func TestProviderSkipsFilesUnchangedSince(t *testing.T) {
+	rawRequests := 0
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/commits", func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.Query().Get("path") != "docs/old.md" || r.URL.Query().Get("sha") != "main" {
+			t.Errorf("unexpected commits query: %s", r.URL.RawQuery)
+		}
+		fmt.Fprint(w, `[{"commit": {"committer": {"date": "2024-01-15T10:00:00Z"}}}]`)
+	})
+	mux.HandleFunc("/raw/owner/repo/main/", func(w http.ResponseWriter, r *http.Request) {
+		rawRequests++
+	})
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "docs"}
+	opts := FetchOptions{Since: time.Date(2024, 2, 1, 0, 0, 0, 0, time.UTC)}
+	err := Provider{}.FetchFile(context.Background(), "docs/old.md", &components, opts)
+
+	if !errors.Is(err, ErrSkipped) {
+		t.Errorf("expected skipped error, got: %v", err)
+	}
+	if rawRequests != 0 {
+		t.Errorf("expected no raw requests, got: %d", rawRequests)
+	}
+}
+
+func TestFetchPublicFileReportsMissingFile(t *testing.T) {
+	serveGitHub(t, http.NewServeMux())
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "docs"}
+	err := FetchPublicFile(context.Background(), "docs/removed.md", &components, FetchOptions{})
+
+	if !errors.Is(err, ErrFileGone) || IsRetryable(err) {
+		t.Errorf("expected a file gone error that is not retried, got: %v", err)
+	}
+}
+
+func TestFetchOptionsSinceForIfNewer(t *testing.T) {
+	dir := t.TempDir()
+	local := filepath.Join(dir, "edited.md")
+	if err := os.WriteFile(local, []byte("local edit"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	modified := time.Date(2024, 3, 1, 0, 0, 0, 0, time.UTC)
+	if err := os.Chtimes(local, modified, modified); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	opts := FetchOptions{IfNewer: true}
+	if got := opts.SinceFor(local); !got.Equal(modified) {
+		t.Errorf("expected the local modification time %s, got: %s", modified, got)
+	}
+	if got := opts.SinceFor(filepath.Join(dir, "missing.md")); !got.IsZero() {
+		t.Errorf("expected no check for a missing local copy, got: %s", got)
+	}
+	if got := (FetchOptions{Since: modified}).SinceFor(local); !got.Equal(modified) {
+		t.Errorf("expected Since without IfNewer, got: %s", got)
+	}
+}
+
+func TestErrorKind(t *testing.T) {
+	tests := map[string]error{
+		"file_gone":         fmt.Errorf("%w: docs/a.md at main", ErrFileGone),
+		"rate_limited":      fmt.Errorf("HTTP error for docs/a.md: %w", ErrRateLimitExceeded),
+		"checksum_mismatch": &helpers.BlobMismatchError{Path: "a.md", Expected: "abc", Actual: "def"},
+		"http_status":       &StatusError{StatusCode: 502, Status: "502 Bad Gateway", Path: "a.md"},
+		"canceled":          fmt.Errorf("error saving file a.md: %w", context.Canceled),
+		"other":             errors.New("disk full"),
+	}
+	for expected, err := range tests {
+		if kind := ErrorKind(err); kind != expected {
+			t.Errorf("%v: expected kind %s, got %s", err, expected, kind)
+		}
+	}
+}
+
+func TestFetchPublicFileWaitsForMemoryBudgetPastIdleTimeout(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/data/big.bin", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, "0123456789")
+	})
+	serveGitHub(t, mux)
+
+	previous := httpClient
+	httpClient = &http.Client{Transport: &idleTimeoutTransport{base: http.DefaultTransport, timeout: 50 * time.Millisecond}}
+	defer func() { httpClient = previous }()
+
+	budget := helpers.NewMemoryBudget(10)
+	held, err := budget.Acquire(context.Background(), 10)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	go func() {
+		time.Sleep(300 * time.Millisecond)
+		budget.Release(held)
+	}()
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "data"}
+	opts := FetchOptions{MemoryBudget: budget}
+	opts.Save.Root = t.TempDir()
+	if err := FetchPublicFile(context.Background(), "data/big.bin", &components, opts); err != nil {
+		t.Fatalf("expected the download to wait for the budget, got: %v", err)
+	}
+	saved, err := os.ReadFile(filepath.Join(opts.Save.Root, "data", "big.bin"))
+	if err != nil || string(saved) != "0123456789" {
+		t.Errorf("expected the file to be saved, got: %q, %v", saved, err)
+	}
+}
+
+func TestFetchPublicFileWaitsForMemoryBudgetBeforeTransferTimeout(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/data/big.bin", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, "0123456789")
+	})
+	serveGitHub(t, mux)
+
+	previous := httpClient
+	httpClient = &http.Client{Transport: &transferTimeoutTransport{base: http.DefaultTransport, timeout: 100 * time.Millisecond}}
+	defer func() { httpClient = previous }()
+
+	budget := helpers.NewMemoryBudget(10)
+	held, err := budget.Acquire(context.Background(), 10)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	go func() {
+		time.Sleep(300 * time.Millisecond)
+		budget.Release(held)
+	}()
+
+	// With its listed size known, the file waits for the budget before its request starts the timer.
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "data"}
+	opts := FetchOptions{MemoryBudget: budget}
+	opts.Save.Root = t.TempDir()
+	opts.Save.Size = 10
+	if err := FetchPublicFile(context.Background(), "data/big.bin", &components, opts); err != nil {
+		t.Fatalf("expected the download to wait for the budget, got: %v", err)
+	}
+}
+
+func TestFetchPublicFileIgnoresVerifyCacheWhenSavingInMemory(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/data/a.txt", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, "content")
+	})
+	serveGitHub(t, mux)
+
+	dir := t.TempDir()
+	wd, _ := os.Getwd()
+	os.Chdir(dir)
+	defer os.Chdir(wd)
+
+	// data/a.txt is on disk and recorded as verified, so downloads to disk skip it.
+	sum := "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73"
+	os.MkdirAll("data", 0o755)
+	if err := os.WriteFile("data/a.txt", []byte("content"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	checksums, err := helpers.ParseChecksums(strings.NewReader(sum + "  data/a.txt\n"))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	checksums.Cache, _ = helpers.LoadVerificationCache(helpers.VerificationCacheFile)
+	info, _ := os.Stat("data/a.txt")
+	checksums.Cache.Record("data/a.txt", sum, info)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "data"}
+	opts := FetchOptions{}
+	opts.Save.Checksums = checksums
+	if err := FetchPublicFile(context.Background(), "data/a.txt", &components, opts); !errors.Is(err, ErrSkipped) {
+		t.Fatalf("expected the verified file to be skipped, got: %v", err)
+	}
+
+	var out strings.Builder
+	opts.Save.Writer = &out
+	if err := FetchPublicFile(context.Background(), "data/a.txt", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if out.String() != "content" {
+		t.Errorf("expected the content to be written, got: %q", out.String())
+	}
+}
+
+func TestFetchPublicFileSkipsLfsObjectsOverBudget(t *testing.T) {
+	pointer := func(size int) string {
+		return "version https://git-lfs.github.com/spec/v1\n" +
+			"oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n" +
+			fmt.Sprintf("size %d\n", size)
+	}
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/assets/first.bin", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, pointer(6))
+	})
+	mux.HandleFunc("/raw/owner/repo/main/assets/second.bin", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, pointer(6))
+	})
+	mux.HandleFunc("/media/owner/repo/main/", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, "object")
+	})
+	serveGitHub(t, mux)
+
+	// The budget fits the first object only.
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "assets"}
+	opts := FetchOptions{LfsBudget: NewSizeBudget(10)}
+	opts.Save.Root = t.TempDir()
+	if err := FetchPublicFile(context.Background(), "assets/first.bin", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := FetchPublicFile(context.Background(), "assets/second.bin", &components, opts); !errors.Is(err, ErrSkipped) {
+		t.Errorf("expected the second object to be skipped, got: %v", err)
+	}
+}
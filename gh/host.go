@@ -0,0 +1,15 @@
+package gh
+
+// apiBaseURL is the root of the REST API, a variable so it can point at GitHub Enterprise Server.
+var apiBaseURL = "https://api.github.com"
+
+// UseHost points the package at the GitHub Enterprise Server instance at host instead of github.com.
+// It should be called before any request is made.
+func UseHost(host string) {
+	base := "https://" + host
+	apiBaseURL = base + "/api/v3"
+	rawBaseURL = base + "/raw"
+	mediaBaseURL = base
+	gitBaseURL = base
+	warmupURLs = []string{base}
+}
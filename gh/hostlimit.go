@@ -0,0 +1,74 @@
+package gh
+
+import (
+	"io"
+	"net/http"
+	"sync"
+)
+
+// hostLimitTransport caps the requests in flight to each host. A request holds its slot from
+// sending until its response body is closed, so a download counts for as long as it is read.
+type hostLimitTransport struct {
+	base http.RoundTripper
+
+	// limits maps host names to their cap, with "*" for hosts not listed; hosts without one are not limited.
+	limits map[string]int
+
+	mu    sync.Mutex
+	slots map[string]chan struct{}
+}
+
+// slot returns the semaphore of host, or nil if host is not limited.
+func (t *hostLimitTransport) slot(host string) chan struct{} {
+	limit, ok := t.limits[host]
+	if !ok {
+		limit = t.limits["*"]
+	}
+	if limit <= 0 {
+		return nil
+	}
+
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	if t.slots == nil {
+		t.slots = map[string]chan struct{}{}
+	}
+	if _, ok := t.slots[host]; !ok {
+		t.slots[host] = make(chan struct{}, limit)
+	}
+	return t.slots[host]
+}
+
+func (t *hostLimitTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	slot := t.slot(req.URL.Host)
+	if slot == nil {
+		return t.base.RoundTrip(req)
+	}
+
+	select {
+	case slot <- struct{}{}:
+	case <-req.Context().Done():
+		return nil, req.Context().Err()
+	}
+	release := sync.OnceFunc(func() { <-slot })
+
+	resp, err := t.base.RoundTrip(req)
+	if err != nil {
+		release()
+		return nil, err
+	}
+	resp.Body = &releasingBody{ReadCloser: resp.Body, release: release}
+	return resp, nil
+}
+
+// releasingBody calls release once the body is closed.
+type releasingBody struct {
+	io.ReadCloser
+	release func()
+}
+
+func (b *releasingBody) Close() error {
+	err := b.ReadCloser.Close()
+	b.release()
+	return err
+}
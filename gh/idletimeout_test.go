@@ -0,0 +1,58 @@
+package gh
+
+import (
+	"errors"
+	"io"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+	"time"
+)
+
+func TestIdleTimeoutTransportFailsStalledBody(t *testing.T) {
+	server := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		w.Write([]byte("partial"))
+		w.(http.Flusher).Flush()
+		select {
+		case <-r.Context().Done():
+		case <-time.After(5 * time.Second):
+		}
+	}))
+	defer server.Close()
+
+	client := &http.Client{Transport: &idleTimeoutTransport{base: http.DefaultTransport, timeout: 100 * time.Millisecond}}
+	resp, err := client.Get(server.URL)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer resp.Body.Close()
+
+	start := time.Now()
+	_, err = io.ReadAll(resp.Body)
+	if !errors.Is(err, ErrIdleTimeout) || !IsRetryable(err) {
+		t.Errorf("expected a retryable idle timeout, got: %v", err)
+	}
+	if elapsed := time.Since(start); elapsed > 2*time.Second {
+		t.Errorf("expected the stalled body to fail quickly, took %s", elapsed)
+	}
+}
+
+func TestIdleTimeoutTransportIgnoresTimeBetweenReads(t *testing.T) {
+	server := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		w.Write([]byte("complete"))
+	}))
+	defer server.Close()
+
+	client := &http.Client{Transport: &idleTimeoutTransport{base: http.DefaultTransport, timeout: 50 * time.Millisecond}}
+	resp, err := client.Get(server.URL)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer resp.Body.Close()
+
+	time.Sleep(200 * time.Millisecond)
+	body, err := io.ReadAll(resp.Body)
+	if err != nil || string(body) != "complete" {
+		t.Errorf("expected the body after a pause, got: %q, %v", body, err)
+	}
+}
@@ -0,0 +1,174 @@
+package gh
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net/http"
+	"net/url"
+	"strings"
+	"time"
+
+	"repo-pack/model"
+)
+
+// Provider lists and downloads repository files from GitHub.
+type Provider struct {
+	Token string
+
+	// TreeSHA, if set, lists this tree object as the directory of the URL instead of resolving the ref.
+	TreeSHA string
+
+	// Concurrency caps the directory requests of a Contents API listing in flight at once, none if 0.
+	Concurrency int
+}
+
+// ListFiles returns the files under the directory of components, resolving refs that contain slashes.
+func (p Provider) ListFiles(ctx context.Context, components *model.RepoURLComponents) ([]string, error) {
+	items, err := p.ListItems(ctx, components)
+	if err != nil {
+		return nil, err
+	}
+	return ItemPaths(items), nil
+}
+
+// ListItems is ListFiles returning the file items, with their blob SHA and size.
+func (p Provider) ListItems(ctx context.Context, components *model.RepoURLComponents) ([]Item, error) {
+	if components.Release {
+		items, err := ReleaseAssetItems(ctx, components, p.Token)
+		if err != nil {
+			return nil, p.ExplainNotFound(ctx, components, err)
+		}
+		return items, nil
+	}
+	if components.Wiki {
+		return WikiItems(ctx, components, p.Token)
+	}
+	if components.File {
+		// Blob URLs run the ref and the file path together, so refs with slashes are resolved as for directories.
+		if strings.Contains(strings.Trim(components.Dir, "/"), "/") && components.CommitSHA == "" {
+			if err := ResolveRef(ctx, components, p.Token); err != nil {
+				return nil, p.ExplainNotFound(ctx, components, err)
+			}
+		}
+		return []Item{{Type: "blob", Path: strings.Trim(components.Dir, "/")}}, nil
+	}
+	if p.TreeSHA != "" {
+		items, truncated, err := TreeSHAItems(ctx, *components, p.TreeSHA, p.Token)
+		if err == nil && truncated {
+			return nil, fmt.Errorf("tree %s is too large to be listed in one response", p.TreeSHA)
+		}
+		return items, err
+	}
+
+	items, _, err := RepoListingItems(ctx, components, p.Token, p.Concurrency)
+	if err != nil {
+		return nil, p.ExplainNotFound(ctx, components, err)
+	}
+	return items, nil
+}
+
+// CheckToken confirms that GitHub accepts the token of p, with a request to the rate limit endpoint,
+// which any valid token may call and which does not count against the rate limit. Without a token it does nothing.
+func (p Provider) CheckToken(ctx context.Context) error {
+	if p.Token == "" {
+		return nil
+	}
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, apiBaseURL+"/rate_limit", nil)
+	if err != nil {
+		return err
+	}
+	req.Header.Set("Authorization", fmt.Sprintf("Bearer %s", p.Token))
+
+	resp, err := httpClient.Do(req)
+	if err != nil {
+		return err
+	}
+	resp.Body.Close()
+
+	switch resp.StatusCode {
+	case http.StatusOK:
+		return nil
+	case http.StatusUnauthorized:
+		return fmt.Errorf("%w: GitHub rejected it, it may have expired or been revoked", ErrInvalidToken)
+	default:
+		return &StatusError{StatusCode: resp.StatusCode, Status: resp.Status, Path: req.URL.Path}
+	}
+}
+
+// ExplainNotFound tells apart, for a not found err of a request made with the token of p, a repository
+// that the token cannot see from one that does not exist, by asking for the repository without the token.
+// A repository visible only without the token gives ErrInsufficientScope. Other errors, and any error
+// when p has no token or the token can see the repository, are returned unchanged.
+func (p Provider) ExplainNotFound(ctx context.Context, components *model.RepoURLComponents, err error) error {
+	if p.Token == "" || !(errors.Is(err, ErrNotFound) || errors.Is(err, ErrRepositoryNotFound)) {
+		return err
+	}
+	if _, withToken := FetchRepoInfo(ctx, components, p.Token); !errors.Is(withToken, ErrRepositoryNotFound) {
+		return err
+	}
+
+	_, withoutToken := FetchRepoInfo(ctx, components, "")
+	switch {
+	case withoutToken == nil:
+		return fmt.Errorf(
+			"%w: %s/%s is visible without the token but not with it; fine-grained tokens need access to the repository with Contents: read",
+			ErrInsufficientScope,
+			components.Owner,
+			components.Repository,
+		)
+	case errors.Is(withoutToken, ErrRepositoryNotFound):
+		return fmt.Errorf(
+			"%w: %s/%s; if it is private, check that the token has access to it (Contents: read for fine-grained tokens, the repo scope for classic ones)",
+			ErrRepositoryNotFound,
+			components.Owner,
+			components.Repository,
+		)
+	default:
+		return err
+	}
+}
+
+// FetchFile downloads a single file and saves it.
+// Files last changed before opts.SinceFor the local path are skipped without downloading them.
+func (p Provider) FetchFile(ctx context.Context, path string, components *model.RepoURLComponents, opts FetchOptions) error {
+	if since := opts.SinceFor(opts.LocalPath(path, components)); !since.IsZero() {
+		changed, err := LastCommitDate(ctx, path, *components, p.Token)
+		if err != nil {
+			return fmt.Errorf("looking up the last commit of %s: %w", path, err)
+		}
+		if changed.Before(since) {
+			return fmt.Errorf("%w: unchanged since %s", ErrSkipped, changed.Format(time.RFC3339))
+		}
+	}
+	return fetchFile(ctx, path, components, p.Token, opts)
+}
+
+// LastCommitDate returns the committer date of the last commit on the ref of components that changed path.
+func LastCommitDate(ctx context.Context, path string, components model.RepoURLComponents, token string) (time.Time, error) {
+	query := url.Values{"path": {path}, "sha": {components.Ref}, "per_page": {"1"}}
+	contents, err := API(
+		ctx,
+		fmt.Sprintf("%s/%s/commits?%s", components.Owner, components.Repository, query.Encode()),
+		token,
+	)
+	if err != nil {
+		return time.Time{}, err
+	}
+
+	var commits []struct {
+		Commit struct {
+			Committer struct {
+				Date time.Time `json:"date"`
+			} `json:"committer"`
+		} `json:"commit"`
+	}
+	if err := json.Unmarshal(contents, &commits); err != nil {
+		return time.Time{}, err
+	}
+	if len(commits) == 0 {
+		return time.Time{}, fmt.Errorf("no commit found for %s at %s", path, components.Ref)
+	}
+	return commits[0].Commit.Committer.Date, nil
+}
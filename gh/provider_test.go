@@ -0,0 +1,273 @@
+package gh
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"testing"
+
+	"repo-pack/model"
+)
+
+// serveGitHub points the API and raw base URLs at /api and /raw of a test server handling mux, and the
+// media base URL at its root, until the test ends.
+func serveGitHub(t *testing.T, mux *http.ServeMux) {
+	t.Helper()
+	server := httptest.NewServer(mux)
+	api, raw, media := apiBaseURL, rawBaseURL, mediaBaseURL
+	apiBaseURL, rawBaseURL, mediaBaseURL = server.URL+"/api", server.URL+"/raw", server.URL
+	t.Cleanup(func() {
+		apiBaseURL, rawBaseURL, mediaBaseURL = api, raw, media
+		server.Close()
+	})
+}
+
+func TestProviderListItems(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/git/trees/release/v2", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `{"tree": [
+			{"type": "tree", "path": "src"},
+			{"type": "blob", "path": "src/main.go", "sha": "abc", "size": 12, "mode": "100644"},
+			{"type": "blob", "path": "src/run.sh", "sha": "def", "size": 3, "mode": "100755"},
+			{"type": "blob", "path": "README.md", "sha": "123", "size": 5, "mode": "100644"}
+		]}`)
+	})
+	mux.HandleFunc("/api/repos/owner/limited/", func(w http.ResponseWriter, r *http.Request) {
+		w.Header().Set("Retry-After", "60")
+		w.WriteHeader(http.StatusForbidden)
+	})
+	mux.HandleFunc("/api/", http.NotFound)
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "release", Dir: "v2/src"}
+	items, err := Provider{}.ListItems(context.Background(), &components)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if components.Ref != "release/v2" || components.Dir != "src" {
+		t.Errorf("expected ref release/v2 and dir src, got %s and %s", components.Ref, components.Dir)
+	}
+	if len(items) != 2 || items[0].SHA != "abc" || items[0].Size != 12 || items[1].Mode != ModeExecutable {
+		t.Errorf("expected the two files of src with their metadata, got %+v", items)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "limited", Ref: "main"}
+	if _, err := (Provider{}).ListItems(context.Background(), &components); !errors.Is(err, ErrRateLimitExceeded) {
+		t.Errorf("expected the rate limit to be reported, got: %v", err)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "missing", Ref: "main", Dir: "docs"}
+	if _, err := (Provider{}).ListItems(context.Background(), &components); !errors.Is(err, ErrNotFound) {
+		t.Errorf("expected not found, got: %v", err)
+	}
+}
+
+func TestProviderFetchFileFollowsLfsPointer(t *testing.T) {
+	content := "the real video"
+	pointer := "version https://git-lfs.github.com/spec/v1\n" +
+		"oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n" +
+		fmt.Sprintf("size %d\n", len(content))
+
+	// As for a private repository, both hosts answer 404 without the token.
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/owner/repo/main/", func(w http.ResponseWriter, r *http.Request) {
+		if r.Header.Get("Authorization") != "Bearer secret" {
+			http.NotFound(w, r)
+			return
+		}
+		w.Header().Set("Content-Length", fmt.Sprint(len(pointer)))
+		fmt.Fprint(w, pointer)
+	})
+	mux.HandleFunc("/media/owner/repo/main/", func(w http.ResponseWriter, r *http.Request) {
+		if r.Header.Get("Authorization") != "Bearer secret" {
+			http.NotFound(w, r)
+			return
+		}
+		fmt.Fprint(w, content)
+	})
+	serveGitHub(t, mux)
+
+	root := t.TempDir()
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "assets"}
+	opts := FetchOptions{LfsStats: &LfsStats{}}
+	opts.Save.Root = root
+	if err := (Provider{Token: "secret"}).FetchFile(context.Background(), "assets/video.mp4", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	data, err := os.ReadFile(filepath.Join(root, "assets", "video.mp4"))
+	if err != nil || string(data) != content {
+		t.Errorf("expected the LFS object to be saved, got %q, %v", data, err)
+	}
+	if opts.LfsStats.Files() != 1 || opts.LfsStats.Bytes() != int64(len(content)) {
+		t.Errorf("expected 1 LFS file of %d bytes, got %d of %d", len(content), opts.LfsStats.Files(), opts.LfsStats.Bytes())
+	}
+	if size, ok := opts.LfsStats.ObjectSize("assets/video.mp4"); !ok || size != int64(len(content)) {
+		t.Errorf("expected the object size %d to be recorded, got: %d, %v", len(content), size, ok)
+	}
+}
+
+func TestProviderExplainsNotFoundWithToken(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/rate_limit", func(w http.ResponseWriter, r *http.Request) {
+		if r.Header.Get("Authorization") != "Bearer valid" {
+			w.WriteHeader(http.StatusUnauthorized)
+		}
+	})
+	mux.HandleFunc("/api/repos/owner/public", func(w http.ResponseWriter, r *http.Request) {
+		if r.Header.Get("Authorization") != "" {
+			http.NotFound(w, r)
+			return
+		}
+		fmt.Fprint(w, `{"default_branch": "main"}`)
+	})
+	mux.HandleFunc("/api/", http.NotFound)
+	serveGitHub(t, mux)
+
+	ctx := context.Background()
+	if err := (Provider{Token: "valid"}).CheckToken(ctx); err != nil {
+		t.Errorf("expected the token to be accepted, got: %v", err)
+	}
+	if err := (Provider{Token: "revoked"}).CheckToken(ctx); !errors.Is(err, ErrInvalidToken) {
+		t.Errorf("expected an invalid token, got: %v", err)
+	}
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "public", Ref: "main"}
+	if _, err := (Provider{Token: "valid"}).ListItems(ctx, &components); !errors.Is(err, ErrInsufficientScope) {
+		t.Errorf("expected the token to lack access, got: %v", err)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "missing", Ref: "main"}
+	if _, err := (Provider{Token: "valid"}).ListItems(ctx, &components); !errors.Is(err, ErrRepositoryNotFound) {
+		t.Errorf("expected the repository not to exist, got: %v", err)
+	}
+}
+
+func TestProviderListsBlobOnBranchWithSlashes(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/commits/feature/x", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, `{"sha": "abc123"}`)
+	})
+	mux.HandleFunc("/api/", http.NotFound)
+	serveGitHub(t, mux)
+
+	// As parsed from https://github.com/owner/repo/blob/feature/x/src/main.go
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "feature", Dir: "x/src/main.go", File: true}
+	items, err := Provider{}.ListItems(context.Background(), &components)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if len(items) != 1 || items[0].Path != "src/main.go" || components.Ref != "feature/x" || components.Dir != "src/main.go" {
+		t.Errorf("expected src/main.go at ref feature/x, got %+v at %+v", items, components)
+	}
+}
+
+func TestProviderDownloadsReleaseAssets(t *testing.T) {
+	mux := http.NewServeMux()
+	mux.HandleFunc("/api/repos/owner/repo/releases/latest", func(w http.ResponseWriter, r *http.Request) {
+		// The assets are served from storage that does not follow the releases/download layout.
+		fmt.Fprintf(w, `{"tag_name": "v2.1", "assets": [
+			{"name": "tool linux.tar.gz", "size": 6, "browser_download_url": "%[1]s/storage/1/tool%%20linux.tar.gz"},
+			{"name": "checksums.txt", "size": 3, "browser_download_url": "%[1]s/storage/2/checksums.txt"}
+		]}`, mediaBaseURL)
+	})
+	mux.HandleFunc("/storage/1/tool linux.tar.gz", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, "binary")
+	})
+	mux.HandleFunc("/api/", http.NotFound)
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Release: true}
+	items, err := Provider{}.ListItems(context.Background(), &components)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if components.Ref != "v2.1" || len(items) != 2 || items[0].Path != "tool linux.tar.gz" || items[0].Size != 6 {
+		t.Fatalf("expected the two assets of v2.1, got %+v at %s", items, components.Ref)
+	}
+	if expected := mediaBaseURL + "/storage/1/tool%20linux.tar.gz"; items[0].URL != expected {
+		t.Fatalf("expected the listed download URL %s, got: %s", expected, items[0].URL)
+	}
+
+	dir := t.TempDir()
+	opts := FetchOptions{URL: items[0].URL}
+	opts.Save.Root = dir
+	if err := (Provider{}).FetchFile(context.Background(), "tool linux.tar.gz", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if saved, err := os.ReadFile(filepath.Join(dir, "tool linux.tar.gz")); err != nil || string(saved) != "binary" {
+		t.Errorf("expected the asset to be saved, got: %q, %v", saved, err)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "v9", Release: true}
+	if _, err := (Provider{}).ListItems(context.Background(), &components); !errors.Is(err, ErrNotFound) {
+		t.Errorf("expected the release not to be found, got: %v", err)
+	}
+}
+
+func TestProviderDownloadsWiki(t *testing.T) {
+	if _, err := exec.LookPath("git"); err != nil {
+		t.Skip("git is not installed")
+	}
+
+	// The wiki is served as a git repository only, the REST API knows nothing of it.
+	root := t.TempDir()
+	work := filepath.Join(root, "work")
+	if err := os.MkdirAll(filepath.Join(work, "guides"), 0o755); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	for name, content := range map[string]string{"Home.md": "# Home\n", "guides/Setup.md": "# Setup\n"} {
+		if err := os.WriteFile(filepath.Join(work, filepath.FromSlash(name)), []byte(content), 0o644); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	for _, args := range [][]string{
+		{"-C", work, "init", "--quiet"},
+		{"-C", work, "add", "."},
+		{"-C", work, "-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "--quiet", "-m", "pages"},
+		{"clone", "--quiet", "--bare", work, filepath.Join(root, "owner", "repo.wiki.git")},
+	} {
+		if output, err := exec.Command("git", args...).CombinedOutput(); err != nil {
+			t.Fatalf("git %v: %v: %s", args, err, output)
+		}
+	}
+	git := gitBaseURL
+	gitBaseURL = "file://" + filepath.ToSlash(root)
+	defer func() { gitBaseURL = git }()
+
+	mux := http.NewServeMux()
+	mux.HandleFunc("/raw/wiki/owner/repo/Home.md", func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprint(w, "# Home\n")
+	})
+	mux.HandleFunc("/api/", http.NotFound)
+	serveGitHub(t, mux)
+
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo.wiki", Ref: "master", Wiki: true}
+	items, err := Provider{}.ListItems(context.Background(), &components)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if paths := ItemPaths(items); len(paths) != 2 || paths[0] != "Home.md" || paths[1] != "guides/Setup.md" {
+		t.Fatalf("expected the two wiki pages, got %v", paths)
+	}
+
+	dir := t.TempDir()
+	opts := FetchOptions{}
+	opts.Save.Root = dir
+	if err := (Provider{}).FetchFile(context.Background(), "Home.md", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if saved, err := os.ReadFile(filepath.Join(dir, "Home.md")); err != nil || string(saved) != "# Home\n" {
+		t.Errorf("expected the page to be saved, got: %q, %v", saved, err)
+	}
+
+	components = model.RepoURLComponents{Owner: "owner", Repository: "other.wiki", Ref: "master", Wiki: true}
+	if _, err := (Provider{}).ListItems(context.Background(), &components); !errors.Is(err, ErrNotFound) {
+		t.Errorf("expected a missing wiki not to be found, got: %v", err)
+	}
+}
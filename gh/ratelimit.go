@@ -0,0 +1,188 @@
+package gh
+
+import (
+	"context"
+	"io"
+	"net/http"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+)
+
+// RateLimit is the rate limit state reported by the GitHub API.
+type RateLimit struct {
+	Limit     int
+	Remaining int
+	Reset     time.Time
+}
+
+var (
+	rateLimitMu   sync.Mutex
+	lastRateLimit *RateLimit
+)
+
+// LastRateLimit returns the most recent rate limit state seen in any response, if there was one.
+func LastRateLimit() (RateLimit, bool) {
+	rateLimitMu.Lock()
+	defer rateLimitMu.Unlock()
+
+	if lastRateLimit == nil {
+		return RateLimit{}, false
+	}
+	return *lastRateLimit, true
+}
+
+// rateLimitReserve is how many API requests listing leaves unused before pausing until the limit resets.
+const rateLimitReserve = 2
+
+// waitForRateLimit blocks until the rate limit resets if the last seen budget is nearly exhausted,
+// so that a long listing pauses instead of failing halfway through.
+func waitForRateLimit(ctx context.Context) error {
+	rateLimit, ok := LastRateLimit()
+	if !ok || rateLimit.Remaining > rateLimitReserve {
+		return nil
+	}
+	wait := time.Until(rateLimit.Reset)
+	if wait <= 0 {
+		return nil
+	}
+
+	Logf("API rate limit nearly exhausted (%d left), pausing listing for %s", rateLimit.Remaining, wait.Round(time.Second))
+	select {
+	case <-time.After(wait):
+		return nil
+	case <-ctx.Done():
+		return ctx.Err()
+	}
+}
+
+// APIUsage counts the requests made to the GitHub REST API, which are the ones subject to its rate limit.
+// Raw and LFS content downloads are served by other hosts and are not counted.
+type APIUsage struct {
+	Trees    int
+	Contents int
+	Other    int
+}
+
+// Total returns the number of API requests of every kind.
+func (u APIUsage) Total() int {
+	return u.Trees + u.Contents + u.Other
+}
+
+var (
+	apiUsageMu sync.Mutex
+	apiUsage   APIUsage
+)
+
+// CurrentAPIUsage returns the API requests made so far.
+func CurrentAPIUsage() APIUsage {
+	apiUsageMu.Lock()
+	defer apiUsageMu.Unlock()
+	return apiUsage
+}
+
+// recordAPIRequest counts req if it is a GET against the REST API.
+func recordAPIRequest(req *http.Request) {
+	if !strings.HasPrefix(req.URL.String(), apiBaseURL+"/") || req.Method != http.MethodGet {
+		return
+	}
+
+	apiUsageMu.Lock()
+	defer apiUsageMu.Unlock()
+	switch {
+	case strings.Contains(req.URL.Path, "/git/trees/"):
+		apiUsage.Trees++
+	case strings.Contains(req.URL.Path, "/contents/"):
+		apiUsage.Contents++
+	default:
+		apiUsage.Other++
+	}
+}
+
+// parseRateLimit reads the X-RateLimit-* headers of a response.
+func parseRateLimit(header http.Header) (RateLimit, bool) {
+	limit, err := strconv.Atoi(header.Get("X-RateLimit-Limit"))
+	if err != nil {
+		return RateLimit{}, false
+	}
+	remaining, err := strconv.Atoi(header.Get("X-RateLimit-Remaining"))
+	if err != nil {
+		return RateLimit{}, false
+	}
+	reset, err := strconv.ParseInt(header.Get("X-RateLimit-Reset"), 10, 64)
+	if err != nil {
+		return RateLimit{}, false
+	}
+	return RateLimit{Limit: limit, Remaining: remaining, Reset: time.Unix(reset, 0)}, true
+}
+
+// isRateLimited reports whether resp was refused because a rate limit was hit.
+func isRateLimited(resp *http.Response) bool {
+	if resp.StatusCode != http.StatusForbidden && resp.StatusCode != http.StatusTooManyRequests {
+		return false
+	}
+	return resp.Header.Get("Retry-After") != "" || resp.Header.Get("X-RateLimit-Remaining") == "0"
+}
+
+// rateLimitWait returns how long a rate limited response asks to wait before retrying, taken from
+// Retry-After (seconds or an HTTP date) or else the X-RateLimit-Reset epoch.
+func rateLimitWait(header http.Header, now time.Time) (time.Duration, bool) {
+	if retryAfter := header.Get("Retry-After"); retryAfter != "" {
+		if seconds, err := strconv.Atoi(retryAfter); err == nil {
+			return max(time.Duration(seconds)*time.Second, 0), true
+		}
+		if at, err := http.ParseTime(retryAfter); err == nil {
+			return max(at.Sub(now), 0), true
+		}
+	}
+	if reset, err := strconv.ParseInt(header.Get("X-RateLimit-Reset"), 10, 64); err == nil {
+		return max(time.Unix(reset, 0).Sub(now), 0), true
+	}
+	return 0, false
+}
+
+// rateLimitTransport counts API requests and records the rate limit headers of every response.
+// With wait set, rate limited requests are retried once the limit resets, unless that is more
+// than maxWait away.
+type rateLimitTransport struct {
+	base    http.RoundTripper
+	wait    bool
+	maxWait time.Duration
+	onWait  func(time.Duration)
+}
+
+func (t *rateLimitTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	for {
+		recordAPIRequest(req)
+		resp, err := t.base.RoundTrip(req)
+		if err != nil {
+			return resp, err
+		}
+
+		if rateLimit, ok := parseRateLimit(resp.Header); ok {
+			rateLimitMu.Lock()
+			lastRateLimit = &rateLimit
+			rateLimitMu.Unlock()
+		}
+
+		if !t.wait || req.Body != nil || !isRateLimited(resp) {
+			return resp, nil
+		}
+		wait, ok := rateLimitWait(resp.Header, time.Now())
+		if !ok || (t.maxWait > 0 && wait > t.maxWait) {
+			return resp, nil
+		}
+
+		io.Copy(io.Discard, resp.Body)
+		resp.Body.Close()
+		if t.onWait != nil {
+			t.onWait(wait)
+		}
+		select {
+		case <-time.After(wait):
+		case <-req.Context().Done():
+			return nil, req.Context().Err()
+		}
+	}
+}
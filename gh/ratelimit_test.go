@@ -0,0 +1,92 @@
+package gh
+
+import (
+	"context"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+	"time"
+)
+
+func setLastRateLimit(rateLimit *RateLimit) {
+	rateLimitMu.Lock()
+	lastRateLimit = rateLimit
+	rateLimitMu.Unlock()
+}
+
+func TestWaitForRateLimitPausesUntilReset(t *testing.T) {
+	defer setLastRateLimit(nil)
+
+	setLastRateLimit(&RateLimit{Limit: 60, Remaining: 30, Reset: time.Now().Add(time.Hour)})
+	start := time.Now()
+	if err := waitForRateLimit(context.Background()); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if elapsed := time.Since(start); elapsed > 50*time.Millisecond {
+		t.Errorf("expected no pause with budget left, paused %s", elapsed)
+	}
+
+	setLastRateLimit(&RateLimit{Limit: 60, Remaining: 1, Reset: time.Now().Add(200 * time.Millisecond)})
+	start = time.Now()
+	if err := waitForRateLimit(context.Background()); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if elapsed := time.Since(start); elapsed < 150*time.Millisecond {
+		t.Errorf("expected a pause until the reset, paused %s", elapsed)
+	}
+
+	setLastRateLimit(&RateLimit{Limit: 60, Remaining: 0, Reset: time.Now().Add(time.Hour)})
+	ctx, cancel := context.WithCancel(context.Background())
+	cancel()
+	if err := waitForRateLimit(ctx); err != context.Canceled {
+		t.Errorf("expected cancellation, got: %v", err)
+	}
+}
+
+func TestRateLimitTransportWaitsForReset(t *testing.T) {
+	requests := 0
+	server := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		requests++
+		if requests == 1 {
+			w.Header().Set("Retry-After", "0")
+			w.WriteHeader(http.StatusTooManyRequests)
+			return
+		}
+		fmt.Fprint(w, "ok")
+	}))
+	defer server.Close()
+
+	var waits []time.Duration
+	client := &http.Client{Transport: &rateLimitTransport{
+		base:   http.DefaultTransport,
+		wait:   true,
+		onWait: func(wait time.Duration) { waits = append(waits, wait) },
+	}}
+	resp, err := client.Get(server.URL)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	resp.Body.Close()
+	if resp.StatusCode != http.StatusOK || requests != 2 || len(waits) != 1 {
+		t.Errorf("expected one wait and a successful retry, got status %d after %d requests and %d waits", resp.StatusCode, requests, len(waits))
+	}
+
+	requests = 0
+	reset := time.Now().Add(time.Hour).Unix()
+	server.Config.Handler = http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		requests++
+		w.Header().Set("X-RateLimit-Remaining", "0")
+		w.Header().Set("X-RateLimit-Reset", fmt.Sprint(reset))
+		w.WriteHeader(http.StatusForbidden)
+	})
+	client.Transport.(*rateLimitTransport).maxWait = time.Minute
+	resp, err = client.Get(server.URL)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	resp.Body.Close()
+	if resp.StatusCode != http.StatusForbidden || requests != 1 {
+		t.Errorf("expected the response to be returned without waiting past --max-wait, got status %d after %d requests", resp.StatusCode, requests)
+	}
+}
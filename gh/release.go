@@ -0,0 +1,65 @@
+package gh
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net/url"
+
+	"repo-pack/model"
+)
+
+// release is a release as returned by the Releases API, with only the fields used here.
+type release struct {
+	TagName string `json:"tag_name"`
+	Assets  []struct {
+		Name               string `json:"name"`
+		Size               int64  `json:"size"`
+		BrowserDownloadURL string `json:"browser_download_url"`
+	} `json:"assets"`
+}
+
+// ReleaseAssetItems lists the assets of the release of components: the release of the tag Ref, or the
+// latest release if Ref is empty, in which case Ref is set to its tag. Items are named after the assets.
+func ReleaseAssetItems(ctx context.Context, components *model.RepoURLComponents, token string) ([]Item, error) {
+	endpoint := fmt.Sprintf("%s/%s/releases/latest", components.Owner, components.Repository)
+	name := "latest release"
+	if components.Ref != "" {
+		endpoint = fmt.Sprintf("%s/%s/releases/tags/%s", components.Owner, components.Repository, url.PathEscape(components.Ref))
+		name = "release " + components.Ref
+	}
+
+	contents, err := API(ctx, endpoint, token)
+	if errors.Is(err, ErrNotFound) {
+		return nil, fmt.Errorf("%w: no %s in %s/%s", ErrNotFound, name, components.Owner, components.Repository)
+	}
+	if err != nil {
+		return nil, err
+	}
+
+	var rel release
+	if err := json.Unmarshal(contents, &rel); err != nil {
+		return nil, err
+	}
+	components.Ref = rel.TagName
+
+	items := make([]Item, len(rel.Assets))
+	for i, asset := range rel.Assets {
+		items[i] = Item{Type: "blob", Path: asset.Name, Size: asset.Size, URL: asset.BrowserDownloadURL}
+	}
+	return items, nil
+}
+
+// ReleaseAssetURL returns the URL the asset name of the release tagged with the ref of components is
+// usually served at on github.com. Downloads use the URL the release listing gave for the asset instead.
+func ReleaseAssetURL(name string, components *model.RepoURLComponents) string {
+	return fmt.Sprintf(
+		"%s/%s/%s/releases/download/%s/%s",
+		gitBaseURL,
+		components.Owner,
+		components.Repository,
+		url.PathEscape(components.Ref),
+		url.PathEscape(name),
+	)
+}
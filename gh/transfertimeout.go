@@ -0,0 +1,65 @@
+package gh
+
+import (
+	"context"
+	"errors"
+	"io"
+	"net/http"
+	"sync/atomic"
+	"time"
+)
+
+// ErrTransferTimeout is returned for requests, including the reads of their body, that took longer than the transfer timeout.
+var ErrTransferTimeout = errors.New("request did not complete within the transfer timeout")
+
+// transferTimeoutTransport bounds each request, from sending it until its body is closed, by timeout. Unlike
+// Client.Timeout it times every attempt of rateLimitTransport on its own, so that the waits for a rate limit
+// reset between attempts are not counted.
+type transferTimeoutTransport struct {
+	base    http.RoundTripper
+	timeout time.Duration
+}
+
+func (t *transferTimeoutTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	ctx, cancel := context.WithCancel(req.Context())
+	var timedOut atomic.Bool
+	timer := time.AfterFunc(t.timeout, func() {
+		timedOut.Store(true)
+		cancel()
+	})
+
+	resp, err := t.base.RoundTrip(req.WithContext(ctx))
+	if err != nil {
+		timer.Stop()
+		cancel()
+		if timedOut.Load() {
+			return nil, ErrTransferTimeout
+		}
+		return nil, err
+	}
+	resp.Body = &transferBody{ReadCloser: resp.Body, timer: timer, timedOut: &timedOut, cancel: cancel}
+	return resp, nil
+}
+
+// transferBody reports reads cut off by the transfer timeout as such and stops the timer once closed.
+type transferBody struct {
+	io.ReadCloser
+	timer    *time.Timer
+	timedOut *atomic.Bool
+	cancel   context.CancelFunc
+}
+
+func (b *transferBody) Read(p []byte) (int, error) {
+	n, err := b.ReadCloser.Read(p)
+	if err != nil && b.timedOut.Load() {
+		return n, ErrTransferTimeout
+	}
+	return n, err
+}
+
+func (b *transferBody) Close() error {
+	b.timer.Stop()
+	err := b.ReadCloser.Close()
+	b.cancel()
+	return err
+}
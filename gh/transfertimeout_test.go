@@ -0,0 +1,64 @@
+package gh
+
+import (
+	"errors"
+	"io"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+	"time"
+)
+
+func TestTransferTimeoutTransportFailsSlowBody(t *testing.T) {
+	server := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		w.Write([]byte("partial"))
+		w.(http.Flusher).Flush()
+		select {
+		case <-r.Context().Done():
+		case <-time.After(5 * time.Second):
+		}
+	}))
+	defer server.Close()
+
+	client := &http.Client{Transport: &transferTimeoutTransport{base: http.DefaultTransport, timeout: 100 * time.Millisecond}}
+	resp, err := client.Get(server.URL)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer resp.Body.Close()
+
+	_, err = io.ReadAll(resp.Body)
+	if !errors.Is(err, ErrTransferTimeout) || !IsRetryable(err) || ErrorKind(err) != "timeout" {
+		t.Errorf("expected a retryable transfer timeout, got: %v", err)
+	}
+}
+
+func TestTransferTimeoutLeavesOutRateLimitWaits(t *testing.T) {
+	requests := 0
+	server := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		requests++
+		if requests == 1 {
+			w.Header().Set("Retry-After", "1")
+			w.WriteHeader(http.StatusTooManyRequests)
+			return
+		}
+		w.Write([]byte("ok"))
+	}))
+	defer server.Close()
+
+	// The wait of a second for the reset is well over the timeout of each attempt.
+	client := &http.Client{Transport: &rateLimitTransport{
+		base: &transferTimeoutTransport{base: http.DefaultTransport, timeout: 200 * time.Millisecond},
+		wait: true,
+	}}
+	resp, err := client.Get(server.URL)
+	if err != nil {
+		t.Fatalf("expected the request to wait for the reset, got: %v", err)
+	}
+	defer resp.Body.Close()
+
+	body, err := io.ReadAll(resp.Body)
+	if err != nil || string(body) != "ok" || requests != 2 {
+		t.Errorf("expected a successful retry, got: %q, %v after %d requests", body, err, requests)
+	}
+}
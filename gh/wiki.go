@@ -0,0 +1,67 @@
+package gh
+
+import (
+	"bytes"
+	"context"
+	"fmt"
+	"os"
+	"os/exec"
+	"strings"
+
+	"repo-pack/model"
+)
+
+// WikiItems lists the pages of the wiki of components, whose Repository is the <repo>.wiki repository.
+// The REST API does not serve wikis, so they are listed from a shallow, blobless clone of the wiki
+// repository; the pages themselves are downloaded from their raw wiki URLs. Sizes are not reported.
+func WikiItems(ctx context.Context, components *model.RepoURLComponents, token string) ([]Item, error) {
+	if _, err := exec.LookPath("git"); err != nil {
+		return nil, fmt.Errorf("%w: listing a wiki needs git", ErrGitUnavailable)
+	}
+
+	cloneDir, err := os.MkdirTemp("", "repo-pack-wiki-")
+	if err != nil {
+		return nil, err
+	}
+	defer os.RemoveAll(cloneDir)
+
+	env := gitEnv(token)
+	git := func(args ...string) ([]byte, error) {
+		cmd := exec.CommandContext(ctx, "git", args...)
+		cmd.Env = env
+		var stderr bytes.Buffer
+		cmd.Stderr = &stderr
+		output, err := cmd.Output()
+		if err != nil {
+			return nil, fmt.Errorf("git %s: %v: %s", args[0], err, strings.TrimSpace(stderr.String()))
+		}
+		return output, nil
+	}
+
+	wikiURL := fmt.Sprintf("%s/%s/%s.git", gitBaseURL, components.Owner, components.Repository)
+	if _, err := git("clone", "--quiet", "--bare", "--filter=blob:none", "--depth=1", wikiURL, cloneDir); err != nil {
+		return nil, fmt.Errorf("%w: %s/%s has no wiki or it is out of reach: %v", ErrNotFound, components.Owner, strings.TrimSuffix(components.Repository, ".wiki"), err)
+	}
+	output, err := git("-C", cloneDir, "ls-tree", "-r", "-z", "HEAD")
+	if err != nil {
+		return nil, err
+	}
+
+	var items, skipped []Item
+	for _, entry := range strings.Split(string(output), "\x00") {
+		// Entries are "<mode> <type> <sha>\t<path>".
+		meta, filePath, ok := strings.Cut(entry, "\t")
+		fields := strings.Fields(meta)
+		if !ok || len(fields) != 3 {
+			continue
+		}
+		item := Item{Type: fields[1], Mode: fields[0], SHA: fields[2], Path: filePath}
+		if SpecialKind(item) != "" {
+			skipped = append(skipped, item)
+			continue
+		}
+		items = append(items, item)
+	}
+	LogSkipped(skipped)
+	return items, nil
+}
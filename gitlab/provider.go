@@ -0,0 +1,245 @@
+package gitlab
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net/http"
+	"net/url"
+	"strings"
+	"time"
+
+	"repo-pack/gh"
+	"repo-pack/helpers"
+	"repo-pack/model"
+)
+
+// apiBaseURL is the GitLab REST API, a variable so tests can point it elsewhere.
+var apiBaseURL = "https://gitlab.com/api/v4"
+
+// treePageSize is how many entries are requested per page of the repository tree API, its maximum.
+const treePageSize = 100
+
+// treeEntry is one entry of the GitLab repository tree API.
+type treeEntry struct {
+	ID   string `json:"id"`
+	Type string `json:"type"`
+	Path string `json:"path"`
+	Mode string `json:"mode"`
+}
+
+// Provider lists and downloads repository files from GitLab.
+type Provider struct {
+	Token string
+}
+
+// projectID returns the URL-encoded "namespace/project" path identifying the project in API requests.
+func projectID(components *model.RepoURLComponents) string {
+	return url.PathEscape(components.Owner + "/" + components.Repository)
+}
+
+// get makes an API request, authenticating with the PRIVATE-TOKEN header if a token is set.
+// Failures map to the same errors as the GitHub provider.
+func (p Provider) get(ctx context.Context, endpoint string) (*http.Response, error) {
+	req, err := http.NewRequestWithContext(ctx, http.MethodGet, apiBaseURL+endpoint, nil)
+	if err != nil {
+		return nil, err
+	}
+	if p.Token != "" {
+		req.Header.Set("PRIVATE-TOKEN", p.Token)
+	}
+
+	resp, err := gh.Client().Do(req)
+	if err != nil {
+		return nil, err
+	}
+
+	switch resp.StatusCode {
+	case http.StatusOK:
+		return resp, nil
+	case http.StatusNotFound:
+		resp.Body.Close()
+		return nil, gh.ErrNotFound
+	case http.StatusUnauthorized:
+		resp.Body.Close()
+		return nil, gh.ErrInvalidToken
+	case http.StatusTooManyRequests:
+		resp.Body.Close()
+		return nil, gh.ErrRateLimitExceeded
+	default:
+		resp.Body.Close()
+		return nil, &gh.StatusError{StatusCode: resp.StatusCode, Status: resp.Status, Path: req.URL.Path}
+	}
+}
+
+// ListFiles returns the files under the directory of components.
+func (p Provider) ListFiles(ctx context.Context, components *model.RepoURLComponents) ([]string, error) {
+	items, err := p.ListItems(ctx, components)
+	if err != nil {
+		return nil, err
+	}
+	return gh.ItemPaths(items), nil
+}
+
+// ListItems lists the files under the directory of components with the repository tree API,
+// following its pagination. GitLab does not report sizes, so only paths and blob SHAs are set.
+func (p Provider) ListItems(ctx context.Context, components *model.RepoURLComponents) ([]gh.Item, error) {
+	if components.File {
+		return []gh.Item{{Type: "blob", Path: strings.Trim(components.Dir, "/")}}, nil
+	}
+
+	query := url.Values{}
+	query.Set("recursive", "true")
+	query.Set("ref", components.Ref)
+	query.Set("per_page", fmt.Sprint(treePageSize))
+	if dir := strings.Trim(components.Dir, "/"); dir != "" {
+		query.Set("path", dir)
+	}
+
+	items := []gh.Item{}
+	var skipped []gh.Item
+	for page := "1"; page != ""; {
+		query.Set("page", page)
+		resp, err := p.get(ctx, fmt.Sprintf("/projects/%s/repository/tree?%s", projectID(components), query.Encode()))
+		if err != nil {
+			return nil, err
+		}
+
+		var entries []treeEntry
+		err = json.NewDecoder(resp.Body).Decode(&entries)
+		resp.Body.Close()
+		if err != nil {
+			return nil, err
+		}
+		for _, entry := range entries {
+			item := gh.Item{Type: entry.Type, Path: entry.Path, SHA: entry.ID, Mode: entry.Mode}
+			if gh.SpecialKind(item) != "" {
+				skipped = append(skipped, item)
+			} else if entry.Type == "blob" {
+				items = append(items, item)
+			}
+		}
+		page = resp.Header.Get("X-Next-Page")
+	}
+	gh.LogSkipped(skipped)
+	return items, nil
+}
+
+// RawURL returns the API URL serving the raw content of the repository file path.
+func RawURL(path string, components *model.RepoURLComponents) string {
+	return fmt.Sprintf(
+		"%s/projects/%s/repository/files/%s/raw?ref=%s",
+		apiBaseURL,
+		projectID(components),
+		url.PathEscape(path),
+		url.QueryEscape(components.Ref),
+	)
+}
+
+// FetchFile downloads a single file and saves it.
+// Files last changed before opts.SinceFor the local path are skipped without downloading them.
+func (p Provider) FetchFile(ctx context.Context, path string, components *model.RepoURLComponents, opts gh.FetchOptions) error {
+	localPath := opts.LocalPath(path, components)
+	// A file verified on disk is still needed by an archive or --output -.
+	if opts.Save.Checksums != nil && !opts.Save.InMemory() && opts.Save.Checksums.AlreadyVerified(localPath) {
+		return gh.ErrAlreadyVerified
+	}
+	if since := opts.SinceFor(localPath); !since.IsZero() {
+		changed, err := p.lastCommitDate(ctx, path, components)
+		if err != nil {
+			return fmt.Errorf("looking up the last commit of %s: %w", path, err)
+		}
+		if changed.Before(since) {
+			return fmt.Errorf("%w: unchanged since %s", gh.ErrSkipped, changed.Format(time.RFC3339))
+		}
+	}
+
+	// Files listed without a size reserve their Content-Length once the response arrives.
+	release := func() {}
+	reserved := opts.Save.Size > 0
+	if reserved {
+		var err error
+		if release, err = opts.ReserveMemory(ctx, opts.Save.Size); err != nil {
+			return fmt.Errorf("waiting for memory budget for %s: %w", path, err)
+		}
+	}
+	defer func() { release() }()
+
+	endpoint := strings.TrimPrefix(RawURL(path, components), apiBaseURL)
+	resp, err := p.get(ctx, endpoint)
+	if errors.Is(err, gh.ErrNotFound) {
+		return fmt.Errorf("%w: %s at %s", gh.ErrFileGone, path, components.Ref)
+	}
+	if err != nil {
+		return fmt.Errorf("HTTP error for %s: %w", path, err)
+	}
+
+	// The raw endpoint serves Git LFS files as their pointer unless asked for the object.
+	lfsSize := int64(-1)
+	if pointer, ok := gh.LfsPointerResponse(resp); ok && !opts.NoLfs {
+		resp.Body.Close()
+		if err := opts.CheckLfsObject(pointer); err != nil {
+			return err
+		}
+		// The blob SHA is that of the pointer, not of the object it points at.
+		opts.Save.BlobSHA = ""
+		lfsSize = pointer.Size
+
+		release()
+		if release, err = opts.ReserveMemory(ctx, pointer.Size); err != nil {
+			release = func() {}
+			return fmt.Errorf("waiting for memory budget for %s: %w", path, err)
+		}
+		reserved = true
+
+		resp, err = p.get(ctx, endpoint+"&lfs=true")
+		if errors.Is(err, gh.ErrNotFound) {
+			return fmt.Errorf("%w: LFS object of %s at %s", gh.ErrFileGone, path, components.Ref)
+		}
+		if err != nil {
+			return fmt.Errorf("HTTP error for LFS %s: %w", path, err)
+		}
+	}
+
+	if resp.ContentLength >= 0 {
+		opts.Save.Size = resp.ContentLength
+	}
+	if !reserved {
+		if release, err = opts.ReserveMemory(ctx, resp.ContentLength); err != nil {
+			release = func() {}
+			resp.Body.Close()
+			return fmt.Errorf("waiting for memory budget for %s: %w", path, err)
+		}
+	}
+
+	if err := helpers.SaveFile(localPath, resp.Body, opts.Save); err != nil {
+		return fmt.Errorf("error saving file %s: %w", path, err)
+	}
+
+	if lfsSize >= 0 && opts.LfsStats != nil {
+		opts.LfsStats.Record(path, lfsSize)
+	}
+	return nil
+}
+
+// lastCommitDate returns the committed date of the last commit on the ref of components that changed path.
+func (p Provider) lastCommitDate(ctx context.Context, path string, components *model.RepoURLComponents) (time.Time, error) {
+	query := url.Values{"path": {path}, "ref_name": {components.Ref}, "per_page": {"1"}}
+	resp, err := p.get(ctx, fmt.Sprintf("/projects/%s/repository/commits?%s", projectID(components), query.Encode()))
+	if err != nil {
+		return time.Time{}, err
+	}
+	defer resp.Body.Close()
+
+	var commits []struct {
+		CommittedDate time.Time `json:"committed_date"`
+	}
+	if err := json.NewDecoder(resp.Body).Decode(&commits); err != nil {
+		return time.Time{}, err
+	}
+	if len(commits) == 0 {
+		return time.Time{}, fmt.Errorf("no commit found for %s at %s", path, components.Ref)
+	}
+	return commits[0].CommittedDate, nil
+}
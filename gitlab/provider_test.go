@@ -0,0 +1,106 @@
+package gitlab
+
+import (
+	"context"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"os"
+	"path/filepath"
+	"reflect"
+	"testing"
+
+	"repo-pack/gh"
+	"repo-pack/model"
+)
+
+// serveGitLab points the API at a test server running handler for the duration of the test.
+func serveGitLab(t *testing.T, handler http.Handler) {
+	t.Helper()
+	server := httptest.NewServer(handler)
+	api := apiBaseURL
+	apiBaseURL = server.URL
+	t.Cleanup(func() {
+		apiBaseURL = api
+		server.Close()
+	})
+}
+
+func TestListFilesFollowsPagination(t *testing.T) {
+	var tokens []string
+	serveGitLab(t, http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		tokens = append(tokens, r.Header.Get("PRIVATE-TOKEN"))
+		if r.URL.EscapedPath() != "/projects/group%2Fsub%2Fproject/repository/tree" {
+			http.NotFound(w, r)
+			return
+		}
+		if r.URL.Query().Get("path") != "docs" || r.URL.Query().Get("ref") != "main" {
+			t.Errorf("unexpected query: %s", r.URL.RawQuery)
+		}
+
+		switch r.URL.Query().Get("page") {
+		case "1":
+			w.Header().Set("X-Next-Page", "2")
+			fmt.Fprint(w, `[{"id":"a1","type":"tree","path":"docs/guides"},{"id":"b1","type":"blob","path":"docs/index.md"}]`)
+		case "2":
+			fmt.Fprint(w, `[{"id":"c1","type":"blob","path":"docs/guides/setup.md"}]`)
+		}
+	}))
+
+	components := model.RepoURLComponents{Host: "gitlab.com", Owner: "group/sub", Repository: "project", Ref: "main", Dir: "docs"}
+	files, err := Provider{Token: "secret"}.ListFiles(context.Background(), &components)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	expected := []string{"docs/index.md", "docs/guides/setup.md"}
+	if !reflect.DeepEqual(files, expected) {
+		t.Errorf("expected: %v, got: %v", expected, files)
+	}
+	if !reflect.DeepEqual(tokens, []string{"secret", "secret"}) {
+		t.Errorf("expected the token in PRIVATE-TOKEN on every request, got: %v", tokens)
+	}
+}
+
+func TestFetchFileFollowsLfsPointer(t *testing.T) {
+	content := "the real video"
+	pointer := "version https://git-lfs.github.com/spec/v1\n" +
+		"oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n" +
+		fmt.Sprintf("size %d\n", len(content))
+
+	serveGitLab(t, http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.EscapedPath() != "/projects/group%2Fproject/repository/files/assets%2Fvideo.mp4/raw" {
+			http.NotFound(w, r)
+			return
+		}
+		if r.URL.Query().Get("lfs") == "true" {
+			fmt.Fprint(w, content)
+			return
+		}
+		w.Header().Set("Content-Length", fmt.Sprint(len(pointer)))
+		fmt.Fprint(w, pointer)
+	}))
+
+	components := model.RepoURLComponents{Host: "gitlab.com", Owner: "group", Repository: "project", Ref: "main", Dir: "assets"}
+	opts := gh.FetchOptions{LfsStats: &gh.LfsStats{}}
+	opts.Save.Root = t.TempDir()
+	if err := (Provider{}).FetchFile(context.Background(), "assets/video.mp4", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	data, err := os.ReadFile(filepath.Join(opts.Save.Root, "assets", "video.mp4"))
+	if err != nil || string(data) != content {
+		t.Errorf("expected the LFS object to be saved, got %q, %v", data, err)
+	}
+	if opts.LfsStats.Files() != 1 {
+		t.Errorf("expected 1 LFS file, got %d", opts.LfsStats.Files())
+	}
+
+	opts.NoLfs = true
+	if err := (Provider{}).FetchFile(context.Background(), "assets/video.mp4", &components, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if data, _ := os.ReadFile(filepath.Join(opts.Save.Root, "assets", "video.mp4")); string(data) != pointer {
+		t.Errorf("expected --no-lfs to save the pointer, got %q", data)
+	}
+}
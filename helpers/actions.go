@@ -0,0 +1,20 @@
+package helpers
+
+import (
+	"fmt"
+	"os"
+	"strings"
+)
+
+var workflowDataEscaper = strings.NewReplacer("%", "%25", "\r", "%0D", "\n", "%0A")
+
+// GitHubActionsEnabled reports whether workflow commands should be emitted, either because
+// it was forced or because the process runs inside a GitHub Actions runner.
+func GitHubActionsEnabled(force bool) bool {
+	return force || os.Getenv("GITHUB_ACTIONS") == "true"
+}
+
+// WorkflowCommand formats a GitHub Actions workflow command such as ::error:: or ::notice::
+func WorkflowCommand(command, message string) string {
+	return fmt.Sprintf("::%s::%s", command, workflowDataEscaper.Replace(message))
+}
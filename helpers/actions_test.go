@@ -0,0 +1,15 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestWorkflowCommandEscapesData(t *testing.T) {
+	got := helpers.WorkflowCommand("error", "100% failed\nretry")
+	expected := "::error::100%25 failed%0Aretry"
+
+	if got != expected {
+		t.Errorf("expected: %s, got: %s", expected, got)
+	}
+}
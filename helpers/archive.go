@@ -0,0 +1,126 @@
+package helpers
+
+import (
+	"archive/tar"
+	"archive/zip"
+	"compress/flate"
+	"compress/gzip"
+	"fmt"
+	"io"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"strings"
+	"sync"
+	"time"
+)
+
+// Archive collects saved files into a single archive file instead of writing them to disk.
+// It is safe for concurrent use, entries are written one at a time.
+type Archive struct {
+	mu   sync.Mutex
+	file *os.File
+	zip  *zip.Writer
+	gzip *gzip.Writer
+	tar  *tar.Writer
+}
+
+// CreateArchive creates the archive file at name, choosing the format from its extension:
+// .zip, or .tar.gz and .tgz for a gzip-compressed tarball. Level is the compression level, 0 to 9.
+func CreateArchive(name string, level int) (*Archive, error) {
+	if level < flate.NoCompression || level > flate.BestCompression {
+		return nil, fmt.Errorf("invalid compression level %d, expected 0 to 9", level)
+	}
+
+	lower := strings.ToLower(name)
+	isZip := strings.HasSuffix(lower, ".zip")
+	isTarGz := strings.HasSuffix(lower, ".tar.gz") || strings.HasSuffix(lower, ".tgz")
+	if !isZip && !isTarGz {
+		return nil, fmt.Errorf("unsupported archive format %q, expected a .zip, .tar.gz or .tgz file", filepath.Base(name))
+	}
+
+	file, err := os.Create(name)
+	if err != nil {
+		return nil, fmt.Errorf("error creating archive %s: %v", name, err)
+	}
+
+	archive := &Archive{file: file}
+	if isZip {
+		archive.zip = zip.NewWriter(file)
+		archive.zip.RegisterCompressor(zip.Deflate, func(w io.Writer) (io.WriteCloser, error) {
+			return flate.NewWriter(w, level)
+		})
+		return archive, nil
+	}
+
+	archive.gzip, err = gzip.NewWriterLevel(file, level)
+	if err != nil {
+		file.Close()
+		return nil, err
+	}
+	archive.tar = tar.NewWriter(archive.gzip)
+	return archive, nil
+}
+
+// Add writes data as an entry at the slash-separated path name, with mode 0644 or 0755 if executable.
+func (a *Archive) Add(name string, data []byte, executable bool) error {
+	a.mu.Lock()
+	defer a.mu.Unlock()
+
+	var mode fs.FileMode = 0o644
+	if executable {
+		mode = 0o755
+	}
+	if err := a.add(name, data, mode); err != nil {
+		return fmt.Errorf("error adding %s to archive: %v", name, err)
+	}
+	return nil
+}
+
+func (a *Archive) add(name string, data []byte, mode fs.FileMode) error {
+	modified := time.Now()
+	var entry io.Writer
+	if a.zip != nil {
+		header := &zip.FileHeader{Name: name, Method: zip.Deflate, Modified: modified}
+		header.SetMode(mode)
+		var err error
+		entry, err = a.zip.CreateHeader(header)
+		if err != nil {
+			return err
+		}
+	} else {
+		header := &tar.Header{
+			Typeflag: tar.TypeReg,
+			Name:     name,
+			Mode:     int64(mode),
+			Size:     int64(len(data)),
+			ModTime:  modified,
+		}
+		if err := a.tar.WriteHeader(header); err != nil {
+			return err
+		}
+		entry = a.tar
+	}
+	_, err := entry.Write(data)
+	return err
+}
+
+// Close finishes the archive and closes its file.
+func (a *Archive) Close() error {
+	a.mu.Lock()
+	defer a.mu.Unlock()
+
+	var err error
+	if a.zip != nil {
+		err = a.zip.Close()
+	} else {
+		err = a.tar.Close()
+		if gzipErr := a.gzip.Close(); err == nil {
+			err = gzipErr
+		}
+	}
+	if closeErr := a.file.Close(); err == nil {
+		err = closeErr
+	}
+	return err
+}
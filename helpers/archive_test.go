@@ -0,0 +1,92 @@
+package helpers_test
+
+import (
+	"archive/tar"
+	"archive/zip"
+	"compress/gzip"
+	"io"
+	"os"
+	"path/filepath"
+	"repo-pack/helpers"
+	"strings"
+	"testing"
+)
+
+func TestSaveFileToArchive(t *testing.T) {
+	dir := t.TempDir()
+	name := filepath.Join(dir, "out.zip")
+	archive, err := helpers.CreateArchive(name, 6)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	opts := helpers.SaveOptions{Root: dir, Archive: archive}
+	if err := helpers.SaveFile("lua/init.lua", io.NopCloser(strings.NewReader("print(1)")), opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := helpers.SaveFile("../escape.txt", io.NopCloser(strings.NewReader("x")), opts); err == nil {
+		t.Errorf("expected path traversal to be rejected")
+	}
+	if err := archive.Close(); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	reader, err := zip.OpenReader(name)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer reader.Close()
+	if len(reader.File) != 1 || reader.File[0].Name != "lua/init.lua" {
+		t.Fatalf("expected a single lua/init.lua entry, got: %v", reader.File)
+	}
+	entry, err := reader.File[0].Open()
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer entry.Close()
+	if content, _ := io.ReadAll(entry); string(content) != "print(1)" {
+		t.Errorf("expected content print(1), got: %q", content)
+	}
+}
+
+func TestCreateArchiveRejectsUnknownFormat(t *testing.T) {
+	if _, err := helpers.CreateArchive(filepath.Join(t.TempDir(), "out.rar"), 6); err == nil {
+		t.Errorf("expected an error for an unsupported format")
+	}
+}
+
+func TestSaveFileToTarGzArchive(t *testing.T) {
+	name := filepath.Join(t.TempDir(), "out.tar.gz")
+	archive, err := helpers.CreateArchive(name, 9)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	opts := helpers.SaveOptions{Archive: archive}
+	if err := helpers.SaveFile("lua/init.lua", io.NopCloser(strings.NewReader("print(1)")), opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := archive.Close(); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	file, err := os.Open(name)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer file.Close()
+	gz, err := gzip.NewReader(file)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	reader := tar.NewReader(gz)
+	header, err := reader.Next()
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if header.Name != "lua/init.lua" || header.Mode != 0o644 {
+		t.Errorf("expected lua/init.lua with mode 0644, got: %s %o", header.Name, header.Mode)
+	}
+	if content, _ := io.ReadAll(reader); string(content) != "print(1)" {
+		t.Errorf("expected content print(1), got: %q", content)
+	}
+}
@@ -0,0 +1,87 @@
+package helpers
+
+import (
+	"context"
+	"sync"
+)
+
+// MemoryBudget bounds how many bytes are held in memory at once across concurrent downloads.
+// It is a weighted semaphore that serves waiters in order, so large requests are not starved.
+type MemoryBudget struct {
+	size    int64
+	mu      sync.Mutex
+	used    int64
+	waiters []*budgetWaiter
+}
+
+type budgetWaiter struct {
+	n     int64
+	ready chan struct{}
+}
+
+// NewMemoryBudget creates a budget of size bytes.
+func NewMemoryBudget(size int64) *MemoryBudget {
+	return &MemoryBudget{size: size}
+}
+
+// Acquire blocks until n bytes of the budget are free, or ctx is done. Requests for more than the
+// whole budget, or of unknown size (negative n), take the whole budget so they run on their own.
+// It returns the amount acquired, to be passed to Release.
+func (b *MemoryBudget) Acquire(ctx context.Context, n int64) (int64, error) {
+	if n < 0 || n > b.size {
+		n = b.size
+	}
+
+	b.mu.Lock()
+	if len(b.waiters) == 0 && b.used+n <= b.size {
+		b.used += n
+		b.mu.Unlock()
+		return n, nil
+	}
+	waiter := &budgetWaiter{n: n, ready: make(chan struct{})}
+	b.waiters = append(b.waiters, waiter)
+	b.mu.Unlock()
+
+	select {
+	case <-waiter.ready:
+		return n, nil
+	case <-ctx.Done():
+		b.mu.Lock()
+		defer b.mu.Unlock()
+		select {
+		case <-waiter.ready:
+			// Granted while being cancelled, hand the bytes back.
+			b.used -= n
+		default:
+			for i, w := range b.waiters {
+				if w == waiter {
+					b.waiters = append(b.waiters[:i], b.waiters[i+1:]...)
+					break
+				}
+			}
+		}
+		b.grant()
+		return 0, ctx.Err()
+	}
+}
+
+// Release returns n bytes previously acquired to the budget.
+func (b *MemoryBudget) Release(n int64) {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	b.used -= n
+	b.grant()
+}
+
+// grant hands free bytes to waiters in order, stopping at the first that does not fit.
+func (b *MemoryBudget) grant() {
+	for len(b.waiters) > 0 {
+		waiter := b.waiters[0]
+		if b.used+waiter.n > b.size {
+			return
+		}
+		b.used += waiter.n
+		close(waiter.ready)
+		b.waiters = b.waiters[1:]
+	}
+}
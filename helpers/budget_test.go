@@ -0,0 +1,64 @@
+package helpers_test
+
+import (
+	"context"
+	"repo-pack/helpers"
+	"sync"
+	"testing"
+	"time"
+)
+
+func TestMemoryBudgetSerializesLargeRequests(t *testing.T) {
+	budget := helpers.NewMemoryBudget(1024)
+
+	var mu sync.Mutex
+	inFlight, maxInFlight := 0, 0
+	var wg sync.WaitGroup
+	for i := 0; i < 4; i++ {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			acquired, err := budget.Acquire(context.Background(), 1<<20)
+			if err != nil {
+				t.Errorf("unexpected error: %v", err)
+				return
+			}
+			defer budget.Release(acquired)
+
+			mu.Lock()
+			inFlight++
+			if inFlight > maxInFlight {
+				maxInFlight = inFlight
+			}
+			mu.Unlock()
+			time.Sleep(10 * time.Millisecond)
+			mu.Lock()
+			inFlight--
+			mu.Unlock()
+		}()
+	}
+	wg.Wait()
+
+	if maxInFlight != 1 {
+		t.Errorf("expected downloads over the budget to run one at a time, got %d at once", maxInFlight)
+	}
+}
+
+func TestMemoryBudgetAcquireCancelled(t *testing.T) {
+	budget := helpers.NewMemoryBudget(1024)
+	held, err := budget.Acquire(context.Background(), 1000)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	ctx, cancel := context.WithTimeout(context.Background(), 10*time.Millisecond)
+	defer cancel()
+	if _, err := budget.Acquire(ctx, 100); err == nil {
+		t.Fatalf("expected the acquire to time out")
+	}
+
+	budget.Release(held)
+	if _, err := budget.Acquire(context.Background(), 1024); err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+}
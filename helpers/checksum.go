@@ -0,0 +1,235 @@
+package helpers
+
+import (
+	"bufio"
+	"crypto/sha1"
+	"crypto/sha256"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path"
+	"sort"
+	"strings"
+	"sync"
+)
+
+var (
+	ErrChecksumMismatch = errors.New("checksum mismatch")
+	ErrChecksumMissing  = errors.New("no expected checksum")
+)
+
+// ChecksumVerifier checks saved files against the SHA-256 sums of a sha256sum style file.
+// It is safe for concurrent use.
+type ChecksumVerifier struct {
+	// RequireEntries makes files without an expected checksum fail instead of only being recorded.
+	RequireEntries bool
+
+	// Cache, if set, records verified files and lets AlreadyVerified skip them while they are unchanged.
+	Cache *VerificationCache
+
+	expected map[string]string
+	mu       sync.Mutex
+	seen     map[string]bool
+	missing  []string
+}
+
+// LoadChecksums reads a file of "<sha256>  <path>" lines as written by sha256sum.
+func LoadChecksums(filename string) (*ChecksumVerifier, error) {
+	file, err := os.Open(filename)
+	if err != nil {
+		return nil, err
+	}
+	defer file.Close()
+
+	return ParseChecksums(file)
+}
+
+// ParseChecksums reads "<sha256>  <path>" lines, blank lines and # comments are ignored.
+func ParseChecksums(r io.Reader) (*ChecksumVerifier, error) {
+	verifier := &ChecksumVerifier{expected: map[string]string{}, seen: map[string]bool{}}
+
+	scanner := bufio.NewScanner(r)
+	for lineNumber := 1; scanner.Scan(); lineNumber++ {
+		line := strings.TrimSpace(scanner.Text())
+		if line == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+
+		sum, file, found := strings.Cut(line, " ")
+		file = strings.TrimPrefix(strings.TrimLeft(file, " "), "*")
+		if !found || len(sum) != 64 || file == "" {
+			return nil, fmt.Errorf("invalid checksum line %d: %s", lineNumber, line)
+		}
+		verifier.expected[path.Clean(file)] = strings.ToLower(sum)
+	}
+	if err := scanner.Err(); err != nil {
+		return nil, err
+	}
+
+	return verifier, nil
+}
+
+// Verify compares the hex SHA-256 of a saved file with its expected entry.
+func (v *ChecksumVerifier) Verify(localPath, sum string) error {
+	localPath = path.Clean(localPath)
+
+	v.mu.Lock()
+	defer v.mu.Unlock()
+
+	expected, ok := v.expected[localPath]
+	if !ok {
+		if v.RequireEntries {
+			return fmt.Errorf("%w for %s", ErrChecksumMissing, localPath)
+		}
+		v.missing = append(v.missing, localPath)
+		return nil
+	}
+
+	v.seen[localPath] = true
+	if expected != sum {
+		return fmt.Errorf("%w for %s: expected %s, got %s", ErrChecksumMismatch, localPath, expected, sum)
+	}
+	return nil
+}
+
+// Record notes in Cache that the file saved at localPath, as info describes it once in place,
+// matched its expected entry with sum. It does nothing without a Cache or a matching entry.
+func (v *ChecksumVerifier) Record(localPath, sum string, info os.FileInfo) {
+	if v.Cache == nil {
+		return
+	}
+	localPath = path.Clean(localPath)
+
+	v.mu.Lock()
+	expected, ok := v.expected[localPath]
+	v.mu.Unlock()
+	if ok && expected == sum {
+		v.Cache.Record(localPath, sum, info)
+	}
+}
+
+// AlreadyVerified reports whether the file saved at localPath passed verification on an earlier run
+// and is unchanged since, in which case it counts as verified without being downloaded again.
+func (v *ChecksumVerifier) AlreadyVerified(localPath string) bool {
+	if v.Cache == nil {
+		return false
+	}
+	localPath = path.Clean(localPath)
+
+	v.mu.Lock()
+	defer v.mu.Unlock()
+
+	expected, ok := v.expected[localPath]
+	if !ok || !v.Cache.Fresh(localPath, expected) {
+		return false
+	}
+	v.seen[localPath] = true
+	return true
+}
+
+// Missing returns the saved files that had no expected checksum.
+func (v *ChecksumVerifier) Missing() []string {
+	v.mu.Lock()
+	defer v.mu.Unlock()
+
+	missing := append([]string(nil), v.missing...)
+	sort.Strings(missing)
+	return missing
+}
+
+// Unseen returns the files listed with a checksum that were never verified.
+func (v *ChecksumVerifier) Unseen() []string {
+	v.mu.Lock()
+	defer v.mu.Unlock()
+
+	var unseen []string
+	for file := range v.expected {
+		if !v.seen[file] {
+			unseen = append(unseen, file)
+		}
+	}
+	sort.Strings(unseen)
+	return unseen
+}
+
+// BlobMismatchError is returned for content whose git blob SHA differs from the expected one.
+type BlobMismatchError struct {
+	Path     string
+	Expected string
+	Actual   string
+}
+
+func (e *BlobMismatchError) Error() string {
+	return fmt.Sprintf("%v for %s: expected blob %s, got %s", ErrChecksumMismatch, e.Path, e.Expected, e.Actual)
+}
+
+func (e *BlobMismatchError) Unwrap() error {
+	return ErrChecksumMismatch
+}
+
+// verifyBlobSHA checks that data hashes to the git blob SHA expected.
+func verifyBlobSHA(localPath string, data []byte, expected string) error {
+	hasher := sha1.New()
+	fmt.Fprintf(hasher, "blob %d\x00", len(data))
+	hasher.Write(data)
+	if actual := hex.EncodeToString(hasher.Sum(nil)); actual != expected {
+		return &BlobMismatchError{Path: localPath, Expected: expected, Actual: actual}
+	}
+	return nil
+}
+
+// GitBlobSHA returns the SHA-1 git computes for the content of filename as a blob object,
+// the same value the GitHub API reports as the sha of a file.
+func GitBlobSHA(filename string) (string, error) {
+	file, err := os.Open(filename)
+	if err != nil {
+		return "", err
+	}
+	defer file.Close()
+
+	info, err := file.Stat()
+	if err != nil {
+		return "", err
+	}
+
+	hasher := sha1.New()
+	fmt.Fprintf(hasher, "blob %d\x00", info.Size())
+	if _, err := io.Copy(hasher, file); err != nil {
+		return "", err
+	}
+	return hex.EncodeToString(hasher.Sum(nil)), nil
+}
+
+// FileSHA256 returns the hex SHA-256 of the content of filename.
+func FileSHA256(filename string) (string, error) {
+	file, err := os.Open(filename)
+	if err != nil {
+		return "", err
+	}
+	defer file.Close()
+
+	hasher := sha256.New()
+	if _, err := io.Copy(hasher, file); err != nil {
+		return "", err
+	}
+	return hex.EncodeToString(hasher.Sum(nil)), nil
+}
+
+// WriteChecksums writes sums, mapping paths to hex SHA-256 sums, as "<sha256>  <path>" lines sorted
+// by path, the format sha256sum -c and LoadChecksums read.
+func WriteChecksums(w io.Writer, sums map[string]string) error {
+	paths := make([]string, 0, len(sums))
+	for file := range sums {
+		paths = append(paths, file)
+	}
+	sort.Strings(paths)
+
+	for _, file := range paths {
+		if _, err := fmt.Fprintf(w, "%s  %s\n", sums[file], file); err != nil {
+			return err
+		}
+	}
+	return nil
+}
@@ -0,0 +1,152 @@
+package helpers_test
+
+import (
+	"errors"
+	"io"
+	"os"
+	"reflect"
+	"repo-pack/helpers"
+	"strings"
+	"testing"
+)
+
+const (
+	helloSum = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+	worldSum = "486ea46224d1bb4fb680f34f7c9ad96a8f24ec88be73ea8e5a6c65260e9cb8a7"
+)
+
+func parseTestChecksums(t *testing.T) *helpers.ChecksumVerifier {
+	t.Helper()
+
+	sums := helloSum + "  lua/hello.txt\n" + worldSum + " *lua/world.txt\n"
+	verifier, err := helpers.ParseChecksums(strings.NewReader(sums))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	return verifier
+}
+
+func TestChecksumVerifierMatch(t *testing.T) {
+	verifier := parseTestChecksums(t)
+
+	if err := verifier.Verify("lua/hello.txt", helloSum); err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+
+	expected := []string{"lua/world.txt"}
+	if unseen := verifier.Unseen(); !reflect.DeepEqual(unseen, expected) {
+		t.Errorf("expected unseen: %v, got: %v", expected, unseen)
+	}
+}
+
+func TestChecksumVerifierMismatch(t *testing.T) {
+	verifier := parseTestChecksums(t)
+
+	err := verifier.Verify("lua/world.txt", helloSum)
+	if !errors.Is(err, helpers.ErrChecksumMismatch) {
+		t.Errorf("expected checksum mismatch, got: %v", err)
+	}
+}
+
+func TestChecksumVerifierMissingEntry(t *testing.T) {
+	verifier := parseTestChecksums(t)
+
+	if err := verifier.Verify("lua/extra.txt", helloSum); err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+	if missing := verifier.Missing(); !reflect.DeepEqual(missing, []string{"lua/extra.txt"}) {
+		t.Errorf("expected missing: [lua/extra.txt], got: %v", missing)
+	}
+
+	verifier.RequireEntries = true
+	if err := verifier.Verify("lua/extra.txt", helloSum); !errors.Is(err, helpers.ErrChecksumMissing) {
+		t.Errorf("expected missing checksum error, got: %v", err)
+	}
+}
+
+func TestChecksumVerifierCache(t *testing.T) {
+	wd, err := os.Getwd()
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.Chdir(t.TempDir()); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer os.Chdir(wd)
+
+	cache, err := helpers.LoadVerificationCache(helpers.VerificationCacheFile)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	verifier := parseTestChecksums(t)
+	verifier.Cache = cache
+
+	reader := io.NopCloser(strings.NewReader("hello"))
+	if err := helpers.SaveFile("lua/hello.txt", reader, helpers.SaveOptions{Checksums: verifier}); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := cache.Save(); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	cache, err = helpers.LoadVerificationCache(helpers.VerificationCacheFile)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	verifier = parseTestChecksums(t)
+	verifier.Cache = cache
+	if !verifier.AlreadyVerified("lua/hello.txt") {
+		t.Errorf("expected lua/hello.txt to be already verified")
+	}
+	if verifier.AlreadyVerified("lua/world.txt") {
+		t.Errorf("expected lua/world.txt not to be verified")
+	}
+
+	if err := os.WriteFile("lua/hello.txt", []byte("hello, changed"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if verifier.AlreadyVerified("lua/hello.txt") {
+		t.Errorf("expected a changed file not to count as verified")
+	}
+}
+
+func TestGitBlobSHA(t *testing.T) {
+	file := t.TempDir() + "/hello.txt"
+	if err := os.WriteFile(file, []byte("hello\n"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	// git hash-object of "hello\n"
+	expected := "ce013625030ba8dba906f756967f9e9ca394464a"
+	if got, err := helpers.GitBlobSHA(file); err != nil || got != expected {
+		t.Errorf("expected: %s, got: %s (%v)", expected, got, err)
+	}
+}
+
+func TestWriteChecksumsRoundTrip(t *testing.T) {
+	file := t.TempDir() + "/hello.txt"
+	if err := os.WriteFile(file, []byte("hello"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	sum, err := helpers.FileSHA256(file)
+	if err != nil || sum != helloSum {
+		t.Fatalf("expected: %s, got: %s (%v)", helloSum, sum, err)
+	}
+
+	var written strings.Builder
+	if err := helpers.WriteChecksums(&written, map[string]string{"lua/world.txt": worldSum, "lua/hello.txt": sum}); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	expected := helloSum + "  lua/hello.txt\n" + worldSum + "  lua/world.txt\n"
+	if written.String() != expected {
+		t.Errorf("expected:\n%s\ngot:\n%s", expected, written.String())
+	}
+
+	verifier, err := helpers.ParseChecksums(strings.NewReader(written.String()))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := verifier.Verify("lua/hello.txt", helloSum); err != nil {
+		t.Errorf("expected the written sums to verify, got: %v", err)
+	}
+}
@@ -0,0 +1,47 @@
+package helpers
+
+import (
+	"bytes"
+	"unicode/utf8"
+)
+
+// binarySniffLen is how much of a file is inspected when guessing whether it is binary
+const binarySniffLen = 8000
+
+// maxInvalidUTF8Ratio is the share of invalid UTF-8 bytes above which content is considered binary.
+// Text in a legacy single-byte encoding stays well below it, since most of its bytes are ASCII.
+const maxInvalidUTF8Ratio = 0.3
+
+// IsBinary guesses whether data is binary content rather than text, from a prefix of it.
+// UTF-16 text with a byte order mark is text; otherwise NUL bytes or a high ratio of bytes
+// that are not valid UTF-8 mark the content as binary.
+func IsBinary(data []byte) bool {
+	if len(data) > binarySniffLen {
+		data = data[:binarySniffLen]
+	}
+	if IsUTF16(data) {
+		return false
+	}
+	if bytes.IndexByte(data, 0) != -1 {
+		return true
+	}
+
+	invalid := 0
+	for i := 0; i < len(data); {
+		// A rune cut off by the end of the sample is not evidence of binary content.
+		if !utf8.FullRune(data[i:]) {
+			break
+		}
+		r, size := utf8.DecodeRune(data[i:])
+		if r == utf8.RuneError && size == 1 {
+			invalid++
+		}
+		i += size
+	}
+	return len(data) > 0 && float64(invalid)/float64(len(data)) > maxInvalidUTF8Ratio
+}
+
+// IsUTF16 reports whether data starts with a UTF-16 byte order mark.
+func IsUTF16(data []byte) bool {
+	return bytes.HasPrefix(data, []byte{0xFF, 0xFE}) || bytes.HasPrefix(data, []byte{0xFE, 0xFF})
+}
@@ -0,0 +1,32 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"strings"
+	"testing"
+)
+
+func TestIsBinary(t *testing.T) {
+	tests := []struct {
+		name string
+		data []byte
+		want bool
+	}{
+		{"empty", nil, false},
+		{"ascii", []byte("hello world\n"), false},
+		{"utf-8", []byte("naïve café, 日本語\n"), false},
+		{"latin1", []byte("caf\xe9 cr\xe8me br\xfbl\xe9e\n"), false},
+		{"utf-16le with bom", []byte("\xff\xfeh\x00i\x00\n\x00"), false},
+		{"utf-16be with bom", []byte("\xfe\xff\x00h\x00i\x00\n"), false},
+		{"nul byte", []byte("text\x00more"), true},
+		{"png header", []byte("\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR"), true},
+		{"mostly invalid utf-8", []byte("\xc3\x28\xa0\xa1\xe2\x28\xa1\xf0\x28\x8c\xbc"), true},
+		{"utf-8 cut off by the sample", []byte(strings.Repeat("a", 7999) + "é"), false},
+	}
+
+	for _, tt := range tests {
+		if got := helpers.IsBinary(tt.data); got != tt.want {
+			t.Errorf("%s: expected %v, got %v", tt.name, tt.want, got)
+		}
+	}
+}
@@ -0,0 +1,111 @@
+package helpers
+
+import (
+	"fmt"
+	"strings"
+	"unicode/utf8"
+)
+
+// windows1252High maps bytes 0x80-0x9F of Windows-1252, the rest of the range matches Latin-1.
+// Zero marks bytes that are undefined in the encoding.
+var windows1252High = [32]rune{
+	0x20AC, 0, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021,
+	0x02C6, 0x2030, 0x0160, 0x2039, 0x0152, 0, 0x017D, 0,
+	0, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+	0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0, 0x017E, 0x0178,
+}
+
+// Transcoder converts text in a legacy encoding to UTF-8.
+type Transcoder struct {
+	name string
+
+	// decode returns the rune encoded at the start of data and how many bytes it takes,
+	// or false with the number of bytes to skip if they are undefined in the encoding.
+	decode func(data []byte) (rune, int, bool)
+	strict bool
+}
+
+// NewTranscoder returns a transcoder for the named encoding: latin1, windows-1252 or shift_jis.
+// Shift_JIS is decoded with the extensions of Windows (code page 932), which files labelled as such mostly use.
+// policy is either "replace", which substitutes U+FFFD for undefined bytes, or "strict", which rejects them.
+func NewTranscoder(name, policy string) (*Transcoder, error) {
+	t := &Transcoder{name: name}
+
+	switch strings.ToLower(name) {
+	case "latin1", "latin-1", "iso-8859-1", "iso8859-1":
+		t.decode = func(data []byte) (rune, int, bool) { return rune(data[0]), 1, true }
+	case "windows-1252", "cp1252":
+		t.decode = func(data []byte) (rune, int, bool) {
+			if b := data[0]; b >= 0x80 && b <= 0x9F {
+				r := windows1252High[b-0x80]
+				return r, 1, r != 0
+			}
+			return rune(data[0]), 1, true
+		}
+	case "shift_jis", "shift-jis", "sjis", "cp932", "windows-31j":
+		t.decode = decodeShiftJIS
+	default:
+		return nil, fmt.Errorf("unsupported encoding: %s", name)
+	}
+
+	switch policy {
+	case "replace":
+	case "strict":
+		t.strict = true
+	default:
+		return nil, fmt.Errorf("invalid encoding error policy: %s", policy)
+	}
+
+	return t, nil
+}
+
+// ToUTF8 decodes data and returns it encoded as UTF-8.
+func (t *Transcoder) ToUTF8(data []byte) ([]byte, error) {
+	out := make([]byte, 0, len(data))
+	for i := 0; i < len(data); {
+		r, size, ok := t.decode(data[i:])
+		if !ok {
+			if t.strict {
+				return nil, fmt.Errorf("invalid %s byte 0x%02X at offset %d", t.name, data[i], i)
+			}
+			r = utf8.RuneError
+		}
+		out = utf8.AppendRune(out, r)
+		i += size
+	}
+	return out, nil
+}
+
+// decodeShiftJIS decodes the Shift_JIS character at the start of data: ASCII, a half-width katakana,
+// or a two-byte sequence looked up in shiftJISTable. An undefined lead byte is skipped on its own, and
+// so is a lead byte followed by a byte that cannot trail it, which then starts the next character.
+func decodeShiftJIS(data []byte) (rune, int, bool) {
+	b := data[0]
+	switch {
+	case b < 0x80:
+		return rune(b), 1, true
+	case b >= 0xA1 && b <= 0xDF:
+		return 0xFF61 + rune(b-0xA1), 1, true
+	case (b < 0x81 || b > 0x9F) && (b < 0xE0 || b > 0xFC):
+		return 0, 1, false
+	}
+
+	if len(data) < 2 {
+		return 0, 1, false
+	}
+	trail := data[1]
+	if trail < 0x40 || trail == 0x7F || trail > 0xFC {
+		return 0, 1, false
+	}
+
+	row := int(b) - 0x81
+	if b >= 0xE0 {
+		row = int(b) - 0xC1
+	}
+	column := int(trail) - 0x40
+	if trail > 0x7F {
+		column--
+	}
+	r := rune(shiftJISTable[row*188+column])
+	return r, 2, r != 0
+}
@@ -0,0 +1,78 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestTranscoderLatin1(t *testing.T) {
+	transcoder, err := helpers.NewTranscoder("latin1", "strict")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	got, err := transcoder.ToUTF8([]byte("caf\xe9"))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if string(got) != "café" {
+		t.Errorf("expected: café, got: %s", got)
+	}
+}
+
+func TestTranscoderWindows1252Policies(t *testing.T) {
+	input := []byte("\x93quoted\x94 \x81")
+
+	replace, err := helpers.NewTranscoder("windows-1252", "replace")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	got, err := replace.ToUTF8(input)
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if string(got) != "“quoted” �" {
+		t.Errorf("unexpected output: %q", got)
+	}
+
+	strict, err := helpers.NewTranscoder("windows-1252", "strict")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if _, err := strict.ToUTF8(input); err == nil {
+		t.Errorf("expected error for undefined byte, got: nil")
+	}
+}
+
+func TestTranscoderShiftJIS(t *testing.T) {
+	transcoder, err := helpers.NewTranscoder("shift_jis", "strict")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	got, err := transcoder.ToUTF8([]byte("\x93\xfa\x96{\x8c\xea\x83e\x83L\x83X\x83g \xb1"))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if string(got) != "日本語テキスト ｱ" {
+		t.Errorf("expected: 日本語テキスト ｱ, got: %s", got)
+	}
+
+	if _, err := transcoder.ToUTF8([]byte("\x85\x40")); err == nil {
+		t.Errorf("expected error for undefined sequence, got: nil")
+	}
+
+	replace, err := helpers.NewTranscoder("sjis", "replace")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	// A lead byte cut short by the end of the text or by an ASCII byte is replaced on its own.
+	got, err = replace.ToUTF8([]byte("a\x93b\x93"))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if string(got) != "a�b�" {
+		t.Errorf("unexpected output: %q", got)
+	}
+}
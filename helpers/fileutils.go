@@ -1,44 +1,341 @@
 package helpers
 
 import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
 	"fmt"
 	"io"
+	"io/fs"
 	"os"
+	"path"
 	"path/filepath"
+	"runtime"
 	"strings"
+	"unicode/utf8"
 )
 
-// SaveFile saves file to a filepath and base directory
-func SaveFile(baseDir string, filePath string, reader io.ReadCloser) error {
+// SaveOptions controls how downloaded content is written to disk.
+type SaveOptions struct {
+	// Root is the directory paths are saved under, the working directory if empty.
+	Root string
+
+	// Transcoder, if set, converts text files that are not valid UTF-8 to UTF-8.
+	Transcoder *Transcoder
+
+	// EnsureFinalNewline appends a newline to non-empty text files that don't end with one.
+	EnsureFinalNewline bool
+
+	// Checksums, if set, verifies the SHA-256 of every saved file.
+	Checksums *ChecksumVerifier
+
+	// WindowsLongPaths allows paths over MAX_PATH on Windows instead of failing with a clear error.
+	WindowsLongPaths bool
+
+	// Archive, if set, receives saved files as entries instead of them being written under Root.
+	Archive *Archive
+
+	// Writer, if set, receives the content of saved files instead of them being written under Root,
+	// e.g. stdout for --output -.
+	Writer io.Writer
+
+	// BlobSHA, if set, is the git blob SHA the downloaded content must hash to, checked before
+	// any text transforms. Files that don't match fail with a *BlobMismatchError and are removed.
+	BlobSHA string
+
+	// Executable marks the file as executable once saved; it has no effect on Windows.
+	Executable bool
+
+	// Throttle, if set, caps the rate content is read at, shared with every other file using it.
+	Throttle *Throttle
+
+	// Size is the length of the content if known, zero or negative otherwise.
+	Size int64
+
+	// StreamThreshold is the Size above which content is streamed to disk untouched by the text options
+	// rather than read into memory first, DefaultStreamThreshold if zero.
+	StreamThreshold int64
+}
+
+// DefaultStreamThreshold is the size above which SaveFile streams content without applying text options.
+const DefaultStreamThreshold = 16 << 20
+
+// streams reports whether content of opts.Size is written as it is read even though text options are set.
+func (o SaveOptions) streams() bool {
+	threshold := o.StreamThreshold
+	if threshold == 0 {
+		threshold = DefaultStreamThreshold
+	}
+	return o.Size > threshold
+}
+
+// InMemory reports whether saved files go to Archive or Writer rather than to disk.
+func (o SaveOptions) InMemory() bool {
+	return o.Archive != nil || o.Writer != nil
+}
+
+// LocalPath returns where a repository file is saved relative to the working directory:
+// the last segment of dir followed by the path of file inside dir.
+// Files listed from the repository root keep their full path, and a file that is dir itself,
+// as for single file URLs, is saved under its base name.
+func LocalPath(file, dir string) string {
+	dir = strings.Trim(dir, "/")
+	if dir == "" {
+		return file
+	}
+	if file == dir {
+		return path.Base(file)
+	}
+	return path.Join(path.Base(dir), RelativePath(file, dir))
+}
+
+// SaveFile saves file to a slash-separated path relative to opts.Root or the working directory
+func SaveFile(localPath string, reader io.ReadCloser, opts SaveOptions) error {
+	if opts.Throttle != nil {
+		reader = opts.Throttle.Reader(reader)
+	}
 	defer reader.Close()
-	currentDir, err := os.Getwd()
-	if err != nil {
-		return fmt.Errorf("error getting current working directory: %v", err)
+	currentDir := opts.Root
+	if currentDir == "" {
+		var err error
+		currentDir, err = os.Getwd()
+		if err != nil {
+			return fmt.Errorf("error getting current working directory: %v", err)
+		}
 	}
 
-	baseDirIndex := strings.Index(filePath, baseDir+"/")
-	if baseDirIndex == -1 {
-		return fmt.Errorf("base directory %s not found in file path %s", baseDir, filePath)
+	fullPath := filepath.Join(currentDir, filepath.FromSlash(localPath))
+	if rel, err := filepath.Rel(currentDir, fullPath); err != nil || rel == ".." || strings.HasPrefix(rel, ".."+string(filepath.Separator)) {
+		return fmt.Errorf("refusing to write %s outside of %s", localPath, currentDir)
 	}
 
-	adjustedFilePath := filePath[baseDirIndex:]
-	fullPath := filepath.Join(currentDir, adjustedFilePath)
+	if opts.InMemory() {
+		return saveInMemory(localPath, reader, opts)
+	}
+
+	if err := CheckPathLength(fullPath, opts.WindowsLongPaths); err != nil {
+		return err
+	}
 
 	dir := filepath.Dir(fullPath)
 	if makeDirErr := os.MkdirAll(dir, 0o755); makeDirErr != nil && !os.IsExist(makeDirErr) {
 		return fmt.Errorf("error creating output folder for %s: %w", fullPath, makeDirErr)
 	}
 
-	file, err := os.Create(fullPath)
-	if err != nil {
-		return fmt.Errorf("error creating file %s: %v", fullPath, err)
+	var content io.Reader = reader
+	if (opts.Transcoder != nil || opts.EnsureFinalNewline) && !opts.streams() {
+		sniff, err := readSniff(reader)
+		if err != nil {
+			return fmt.Errorf("error reading content for %s: %v", fullPath, err)
+		}
+		if IsBinary(sniff) || IsUTF16(sniff) {
+			// The text options leave such content untouched, so stream it rather than holding it in memory.
+			content = io.MultiReader(bytes.NewReader(sniff), reader)
+		} else {
+			rest, err := io.ReadAll(reader)
+			if err != nil {
+				return fmt.Errorf("error reading content for %s: %v", fullPath, err)
+			}
+			data := append(sniff, rest...)
+			if opts.BlobSHA != "" {
+				if err := verifyBlobSHA(localPath, data, opts.BlobSHA); err != nil {
+					return err
+				}
+				// Checked before the transforms, the saved content no longer hashes to it.
+				opts.BlobSHA = ""
+			}
+			data, err = transformText(data, opts)
+			if err != nil {
+				return fmt.Errorf("error transforming %s: %v", fullPath, err)
+			}
+			content = bytes.NewReader(data)
+		}
 	}
 
-	_, err = io.Copy(file, reader)
+	// The content goes to a uniquely named sibling .part file that is renamed into place once complete, so
+	// that an interrupted or failed save never leaves a truncated file under the final name, and neither
+	// a file named like it nor another download of the same path is overwritten on the way.
+	part, err := os.CreateTemp(dir, "."+filepath.Base(fullPath)+".*.part")
+	if err != nil {
+		return fmt.Errorf("error creating file for %s: %v", fullPath, err)
+	}
+	partPath := part.Name()
+	sum, err := writePart(part, localPath, content, opts)
 	if err != nil {
-		return fmt.Errorf("error copying content to file %s: %v", fullPath, err)
+		os.Remove(partPath)
+		return err
+	}
+	if err := os.Rename(partPath, fullPath); err != nil {
+		os.Remove(partPath)
+		return fmt.Errorf("error moving %s into place: %v", fullPath, err)
 	}
 
-	defer file.Close()
+	// The verification cache describes the file in place, so it is recorded only once renamed.
+	if opts.Checksums != nil {
+		if info, err := os.Stat(fullPath); err == nil {
+			opts.Checksums.Record(localPath, sum, info)
+		}
+	}
 	return nil
 }
+
+// writePart writes content to file, which it closes, and checks it against the BlobSHA and Checksums of opts.
+// It returns the hex SHA-256 of the content.
+func writePart(file *os.File, localPath string, content io.Reader, opts SaveOptions) (string, error) {
+	defer file.Close()
+	partPath := file.Name()
+
+	hasher := sha256.New()
+	if _, err := io.Copy(io.MultiWriter(file, hasher), content); err != nil {
+		return "", fmt.Errorf("error copying content to file %s: %v", partPath, err)
+	}
+	if err := file.Close(); err != nil {
+		return "", fmt.Errorf("error writing file %s: %v", partPath, err)
+	}
+
+	if opts.BlobSHA != "" {
+		actual, err := GitBlobSHA(partPath)
+		if err != nil {
+			return "", fmt.Errorf("error hashing %s: %v", partPath, err)
+		}
+		if actual != opts.BlobSHA {
+			return "", &BlobMismatchError{Path: localPath, Expected: opts.BlobSHA, Actual: actual}
+		}
+	}
+
+	sum := hex.EncodeToString(hasher.Sum(nil))
+	if opts.Checksums != nil {
+		if err := opts.Checksums.Verify(localPath, sum); err != nil {
+			return "", err
+		}
+	}
+
+	// Temporary files are created private, give the file the mode os.Create would have.
+	mode := os.FileMode(0o644)
+	if opts.Executable {
+		mode = 0o755
+	}
+	if runtime.GOOS != "windows" {
+		if err := os.Chmod(partPath, mode); err != nil {
+			return "", fmt.Errorf("error setting the mode of %s: %v", partPath, err)
+		}
+	}
+
+	return sum, nil
+}
+
+// readSniff reads the prefix of reader that IsBinary inspects, or all of it if it is shorter.
+func readSniff(reader io.Reader) ([]byte, error) {
+	sniff := make([]byte, binarySniffLen)
+	n, err := io.ReadFull(reader, sniff)
+	if err == io.EOF || err == io.ErrUnexpectedEOF {
+		err = nil
+	}
+	return sniff[:n], err
+}
+
+// saveInMemory adds the content of reader to opts.Archive at localPath, or writes it to opts.Writer.
+// The content is read in full first so that downloads don't hold the archive while streaming,
+// and so that nothing reaches the writer for content that fails its checks.
+func saveInMemory(localPath string, reader io.Reader, opts SaveOptions) error {
+	data, err := io.ReadAll(reader)
+	if err != nil {
+		return fmt.Errorf("error reading content for %s: %v", localPath, err)
+	}
+	if opts.BlobSHA != "" {
+		if err := verifyBlobSHA(localPath, data, opts.BlobSHA); err != nil {
+			return err
+		}
+	}
+	data, err = transformText(data, opts)
+	if err != nil {
+		return fmt.Errorf("error transforming %s: %v", localPath, err)
+	}
+
+	if opts.Checksums != nil {
+		sum := sha256.Sum256(data)
+		if err := opts.Checksums.Verify(localPath, hex.EncodeToString(sum[:])); err != nil {
+			return err
+		}
+	}
+
+	if opts.Writer != nil {
+		if _, err := opts.Writer.Write(data); err != nil {
+			return fmt.Errorf("error writing %s: %v", localPath, err)
+		}
+		return nil
+	}
+	return opts.Archive.Add(path.Clean(localPath), data, opts.Executable)
+}
+
+// transformText applies the text options of opts to data.
+// Binary content and UTF-16 text, which the byte-oriented transforms would corrupt, are returned untouched.
+func transformText(data []byte, opts SaveOptions) ([]byte, error) {
+	if IsBinary(data) || IsUTF16(data) {
+		return data, nil
+	}
+
+	if opts.Transcoder != nil && !utf8.Valid(data) {
+		transcoded, err := opts.Transcoder.ToUTF8(data)
+		if err != nil {
+			return nil, err
+		}
+		data = transcoded
+	}
+
+	if opts.EnsureFinalNewline && len(data) > 0 && data[len(data)-1] != '\n' {
+		data = append(data, '\n')
+	}
+
+	return data, nil
+}
+
+// MoveTree moves every file under src to the same relative path under dst, replacing existing files,
+// and removes src. Files are renamed when possible and copied otherwise, e.g. across filesystems.
+func MoveTree(src, dst string) error {
+	err := filepath.WalkDir(src, func(file string, entry fs.DirEntry, err error) error {
+		if err != nil || entry.IsDir() {
+			return err
+		}
+
+		rel, err := filepath.Rel(src, file)
+		if err != nil {
+			return err
+		}
+		target := filepath.Join(dst, rel)
+		if err := os.MkdirAll(filepath.Dir(target), 0o755); err != nil {
+			return err
+		}
+		if err := os.Rename(file, target); err == nil {
+			return nil
+		}
+		return copyFile(file, target)
+	})
+	if err != nil {
+		return err
+	}
+	return os.RemoveAll(src)
+}
+
+// copyFile copies the content and permissions of src to dst.
+func copyFile(src, dst string) error {
+	in, err := os.Open(src)
+	if err != nil {
+		return err
+	}
+	defer in.Close()
+
+	info, err := in.Stat()
+	if err != nil {
+		return err
+	}
+	out, err := os.OpenFile(dst, os.O_WRONLY|os.O_CREATE|os.O_TRUNC, info.Mode().Perm())
+	if err != nil {
+		return err
+	}
+	if _, err := io.Copy(out, in); err != nil {
+		out.Close()
+		return err
+	}
+	return out.Close()
+}
@@ -0,0 +1,301 @@
+package helpers_test
+
+import (
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"repo-pack/helpers"
+	"runtime"
+	"strings"
+	"testing"
+)
+
+// saveFileInTempDir runs SaveFile from an empty working directory and returns the saved content.
+func saveFileInTempDir(t *testing.T, content string, opts helpers.SaveOptions) string {
+	t.Helper()
+
+	wd, err := os.Getwd()
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.Chdir(t.TempDir()); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	defer os.Chdir(wd)
+
+	reader := io.NopCloser(strings.NewReader(content))
+	if err := helpers.SaveFile("dir/file.txt", reader, opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	saved, err := os.ReadFile("dir/file.txt")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	return string(saved)
+}
+
+func TestSaveFileEnsureFinalNewline(t *testing.T) {
+	got := saveFileInTempDir(t, "key = value", helpers.SaveOptions{EnsureFinalNewline: true})
+
+	if got != "key = value\n" {
+		t.Errorf("expected final newline to be added, got: %q", got)
+	}
+}
+
+func TestSaveFileEnsureFinalNewlineKeepsBinary(t *testing.T) {
+	got := saveFileInTempDir(t, "\x00\x01\x02", helpers.SaveOptions{EnsureFinalNewline: true})
+
+	if got != "\x00\x01\x02" {
+		t.Errorf("expected binary content to be untouched, got: %q", got)
+	}
+}
+
+func TestSaveFileEnsureFinalNewlineKeepsUTF16(t *testing.T) {
+	got := saveFileInTempDir(t, "\xff\xfeh\x00i\x00", helpers.SaveOptions{EnsureFinalNewline: true})
+
+	if got != "\xff\xfeh\x00i\x00" {
+		t.Errorf("expected UTF-16 content to be untouched, got: %q", got)
+	}
+}
+
+// streamGuard serves content and fails once more than limit bytes are read while no .part file
+// under dir has been written to, catching saves that buffer the whole content first.
+type streamGuard struct {
+	content io.Reader
+	dir     string
+	limit   int
+	read    int
+}
+
+func (g *streamGuard) Read(p []byte) (int, error) {
+	if g.read > g.limit && !g.written() {
+		return 0, fmt.Errorf("read %d bytes before anything was written", g.read)
+	}
+	n, err := g.content.Read(p)
+	g.read += n
+	return n, err
+}
+
+func (g *streamGuard) written() bool {
+	parts, _ := filepath.Glob(filepath.Join(g.dir, ".*.part"))
+	for _, part := range parts {
+		if info, err := os.Stat(part); err == nil && info.Size() > 0 {
+			return true
+		}
+	}
+	return false
+}
+
+func TestSaveFileStreams(t *testing.T) {
+	const limit = 64 << 10
+	tests := []struct {
+		name    string
+		content string
+		opts    helpers.SaveOptions
+	}{
+		{
+			name:    "binary with text options",
+			content: "\x00" + strings.Repeat("x", 1<<20),
+			opts:    helpers.SaveOptions{EnsureFinalNewline: true},
+		},
+		{
+			name:    "text over the threshold",
+			content: strings.Repeat("x", 1<<20),
+			opts:    helpers.SaveOptions{EnsureFinalNewline: true, Size: 1 << 20, StreamThreshold: limit},
+		},
+		{
+			name:    "no text options",
+			content: strings.Repeat("x", 1<<20),
+		},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			dir := t.TempDir()
+			tt.opts.Root = dir
+			guard := &streamGuard{content: strings.NewReader(tt.content), dir: filepath.Join(dir, "dir"), limit: limit}
+			if err := helpers.SaveFile("dir/file.bin", io.NopCloser(guard), tt.opts); err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			saved, err := os.ReadFile(filepath.Join(dir, "dir", "file.bin"))
+			if err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			if string(saved) != tt.content {
+				t.Errorf("expected %d bytes to be saved verbatim, got %d bytes", len(tt.content), len(saved))
+			}
+		})
+	}
+}
+
+func TestSaveFileEnsureFinalNewlineBelowThreshold(t *testing.T) {
+	got := saveFileInTempDir(t, "key = value", helpers.SaveOptions{EnsureFinalNewline: true, Size: 11, StreamThreshold: 11})
+	if got != "key = value\n" {
+		t.Errorf("expected a final newline for content at the threshold, got: %q", got)
+	}
+}
+
+func TestSaveFileRejectsPathTraversal(t *testing.T) {
+	reader := io.NopCloser(strings.NewReader("content"))
+	if err := helpers.SaveFile("../outside.txt", reader, helpers.SaveOptions{}); err == nil {
+		t.Errorf("expected error for path outside the working directory, got: nil")
+	}
+}
+
+func TestSaveFileVerifiesBlobSHA(t *testing.T) {
+	dir := t.TempDir()
+	const helloSHA = "ce013625030ba8dba906f756967f9e9ca394464a"
+
+	opts := helpers.SaveOptions{Root: dir, BlobSHA: helloSHA}
+	if err := helpers.SaveFile("ok.txt", io.NopCloser(strings.NewReader("hello\n")), opts); err != nil {
+		t.Errorf("expected matching content to be saved, got: %v", err)
+	}
+
+	err := helpers.SaveFile("truncated.txt", io.NopCloser(strings.NewReader("hel")), opts)
+	var mismatch *helpers.BlobMismatchError
+	if !errors.As(err, &mismatch) || mismatch.Expected != helloSHA {
+		t.Errorf("expected a blob mismatch error, got: %v", err)
+	}
+	if _, err := os.Stat(filepath.Join(dir, "truncated.txt")); !os.IsNotExist(err) {
+		t.Errorf("expected the mismatching file to be removed, got: %v", err)
+	}
+}
+
+// failingReader returns content and then err, like a connection dropped mid-download.
+type failingReader struct {
+	content io.Reader
+	err     error
+}
+
+func (r *failingReader) Read(p []byte) (int, error) {
+	n, err := r.content.Read(p)
+	if err == io.EOF {
+		return n, r.err
+	}
+	return n, err
+}
+
+func TestSaveFileLeavesNothingOnFailure(t *testing.T) {
+	dir := t.TempDir()
+	target := filepath.Join(dir, "file.txt")
+	if err := os.WriteFile(target, []byte("previous"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	dropped := errors.New("connection reset")
+	reader := io.NopCloser(&failingReader{content: strings.NewReader("partial"), err: dropped})
+	if err := helpers.SaveFile("file.txt", reader, helpers.SaveOptions{Root: dir}); err == nil {
+		t.Fatalf("expected the read error to fail the save")
+	}
+
+	if saved, err := os.ReadFile(target); err != nil || string(saved) != "previous" {
+		t.Errorf("expected the existing file to be untouched, got: %q, %v", saved, err)
+	}
+	if entries, _ := os.ReadDir(dir); len(entries) != 1 {
+		t.Errorf("expected the .part file to be removed, got: %v", entries)
+	}
+}
+
+func TestSaveFileKeepsFileNamedLikeThePart(t *testing.T) {
+	dir := t.TempDir()
+	sibling := filepath.Join(dir, "file.txt.part")
+	if err := os.WriteFile(sibling, []byte("mine"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if err := helpers.SaveFile("file.txt", io.NopCloser(strings.NewReader("content")), helpers.SaveOptions{Root: dir}); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if saved, err := os.ReadFile(sibling); err != nil || string(saved) != "mine" {
+		t.Errorf("expected file.txt.part to be untouched, got: %q, %v", saved, err)
+	}
+	if saved, err := os.ReadFile(filepath.Join(dir, "file.txt")); err != nil || string(saved) != "content" {
+		t.Errorf("expected file.txt to be saved, got: %q, %v", saved, err)
+	}
+}
+
+func TestSaveFileToWriter(t *testing.T) {
+	dir := t.TempDir()
+	var out strings.Builder
+	opts := helpers.SaveOptions{Root: dir, Writer: &out}
+	if err := helpers.SaveFile("file.txt", io.NopCloser(strings.NewReader("content")), opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if out.String() != "content" {
+		t.Errorf("expected the content on the writer, got: %q", out.String())
+	}
+	if entries, err := os.ReadDir(dir); err != nil || len(entries) != 0 {
+		t.Errorf("expected nothing written under the root, got: %v, %v", entries, err)
+	}
+}
+
+func TestSaveFileExecutable(t *testing.T) {
+	if runtime.GOOS == "windows" {
+		t.Skip("file modes are not supported on Windows")
+	}
+	dir := t.TempDir()
+	opts := helpers.SaveOptions{Root: dir, Executable: true}
+	if err := helpers.SaveFile("run.sh", io.NopCloser(strings.NewReader("#!/bin/sh\n")), opts); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	info, err := os.Stat(filepath.Join(dir, "run.sh"))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if info.Mode().Perm() != 0o755 {
+		t.Errorf("expected mode 0755, got: %o", info.Mode().Perm())
+	}
+}
+
+func TestLocalPath(t *testing.T) {
+	cases := []struct {
+		file     string
+		dir      string
+		expected string
+	}{
+		{"lua/plugins/init.lua", "lua", "lua/plugins/init.lua"},
+		{".config/nvim/lua/init.lua", ".config/nvim/lua", "lua/init.lua"},
+		{"clib/lib/lib.c", "clib/lib", "lib/lib.c"},
+		{".github/workflows/ci.yml", ".github/workflows/", "workflows/ci.yml"},
+		{"profile/README.md", "", "profile/README.md"},
+	}
+
+	for _, c := range cases {
+		if got := helpers.LocalPath(c.file, c.dir); got != c.expected {
+			t.Errorf("LocalPath(%q, %q) = %q, expected %q", c.file, c.dir, got, c.expected)
+		}
+	}
+}
+
+func TestMoveTree(t *testing.T) {
+	src, dst := t.TempDir(), t.TempDir()
+	if err := os.MkdirAll(src+"/lua/plugins", 0o755); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.WriteFile(src+"/lua/plugins/init.lua", []byte("new"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.MkdirAll(dst+"/lua/plugins", 0o755); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if err := os.WriteFile(dst+"/lua/plugins/init.lua", []byte("old"), 0o644); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if err := helpers.MoveTree(src, dst); err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	moved, err := os.ReadFile(dst + "/lua/plugins/init.lua")
+	if err != nil || string(moved) != "new" {
+		t.Errorf("expected moved file with content new, got: %q (%v)", moved, err)
+	}
+	if _, err := os.Stat(src); !os.IsNotExist(err) {
+		t.Errorf("expected staging directory to be removed, got: %v", err)
+	}
+}
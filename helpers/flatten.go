@@ -0,0 +1,52 @@
+package helpers
+
+import (
+	"fmt"
+	"path"
+	"sort"
+	"strings"
+)
+
+// Collision is a file whose base name was already taken when flattening, and the name it got instead.
+type Collision struct {
+	File string
+	Name string
+}
+
+// FlattenPaths maps every file to its base name, so that all files can be saved in one directory.
+// The first file in sorted order keeps a base name, and the others sharing it get the first free
+// numeric suffix before the extension, e.g. config-1.toml, which keeps the mapping stable across runs.
+func FlattenPaths(files []string) (map[string]string, []Collision) {
+	sorted := append([]string(nil), files...)
+	sort.Strings(sorted)
+
+	names := make(map[string]string, len(sorted))
+	taken := map[string]bool{}
+	for _, file := range sorted {
+		if name := path.Base(file); !taken[name] {
+			taken[name] = true
+			names[file] = name
+		}
+	}
+
+	var collisions []Collision
+	for _, file := range sorted {
+		if _, ok := names[file]; ok {
+			continue
+		}
+		name := path.Base(file)
+		ext := path.Ext(name)
+		// A dotfile such as .gitignore has no extension besides its leading dot.
+		if ext == name {
+			ext = ""
+		}
+		stem := strings.TrimSuffix(name, ext)
+		for i := 1; taken[name]; i++ {
+			name = fmt.Sprintf("%s-%d%s", stem, i, ext)
+		}
+		taken[name] = true
+		names[file] = name
+		collisions = append(collisions, Collision{File: file, Name: name})
+	}
+	return names, collisions
+}
@@ -0,0 +1,42 @@
+package helpers_test
+
+import (
+	"reflect"
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestFlattenPaths(t *testing.T) {
+	files := []string{"dir/b/config.toml", "dir/a/config.toml", "dir/config-1.toml", "dir/README"}
+
+	names, collisions := helpers.FlattenPaths(files)
+
+	expected := map[string]string{
+		"dir/a/config.toml": "config.toml",
+		"dir/b/config.toml": "config-2.toml",
+		"dir/config-1.toml": "config-1.toml",
+		"dir/README":        "README",
+	}
+	if !reflect.DeepEqual(names, expected) {
+		t.Errorf("expected: %v, got: %v", expected, names)
+	}
+	if len(collisions) != 1 || collisions[0].File != "dir/b/config.toml" {
+		t.Errorf("expected a single collision for dir/b/config.toml, got: %v", collisions)
+	}
+}
+
+func TestFlattenPathsDotfiles(t *testing.T) {
+	files := []string{"a/.gitignore", "b/.gitignore", "a/.eslintrc.json", "b/.eslintrc.json"}
+
+	names, _ := helpers.FlattenPaths(files)
+
+	expected := map[string]string{
+		"a/.gitignore":     ".gitignore",
+		"b/.gitignore":     ".gitignore-1",
+		"a/.eslintrc.json": ".eslintrc.json",
+		"b/.eslintrc.json": ".eslintrc-1.json",
+	}
+	if !reflect.DeepEqual(names, expected) {
+		t.Errorf("expected: %v, got: %v", expected, names)
+	}
+}
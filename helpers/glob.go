@@ -0,0 +1,68 @@
+package helpers
+
+import (
+	"path"
+	"strings"
+)
+
+// ValidateGlob checks that pattern is well formed.
+func ValidateGlob(pattern string) error {
+	for _, segment := range strings.Split(pattern, "/") {
+		if _, err := path.Match(segment, ""); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// MatchGlob reports whether the slash-separated name matches pattern.
+// Each pattern segment uses path.Match syntax and "**" matches any number of segments.
+// A pattern without a slash is matched against the base name at any depth.
+func MatchGlob(pattern, name string) bool {
+	if !strings.Contains(pattern, "/") {
+		ok, _ := path.Match(pattern, path.Base(name))
+		return ok
+	}
+	return matchSegments(strings.Split(pattern, "/"), strings.Split(name, "/"))
+}
+
+// MatchAnyGlob reports whether name matches at least one of patterns.
+func MatchAnyGlob(patterns []string, name string) bool {
+	for _, pattern := range patterns {
+		if MatchGlob(pattern, name) {
+			return true
+		}
+	}
+	return false
+}
+
+func matchSegments(pattern, name []string) bool {
+	for len(pattern) > 0 {
+		if pattern[0] == "**" {
+			for i := 0; i <= len(name); i++ {
+				if matchSegments(pattern[1:], name[i:]) {
+					return true
+				}
+			}
+			return false
+		}
+
+		if len(name) == 0 {
+			return false
+		}
+		if ok, _ := path.Match(pattern[0], name[0]); !ok {
+			return false
+		}
+		pattern, name = pattern[1:], name[1:]
+	}
+	return len(name) == 0
+}
+
+// RelativePath returns the repository path file relative to dir, the directory of the repository URL.
+func RelativePath(file, dir string) string {
+	dir = strings.Trim(dir, "/")
+	if dir == "" {
+		return file
+	}
+	return strings.TrimPrefix(file, dir+"/")
+}
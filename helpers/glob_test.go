@@ -0,0 +1,39 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestMatchGlob(t *testing.T) {
+	cases := []struct {
+		pattern string
+		name    string
+		want    bool
+	}{
+		{"README.md", "README.md", true},
+		{"README.md", "docs/README.md", true},
+		{"*.rs", "src/main.rs", true},
+		{"src/*.rs", "src/main.rs", true},
+		{"src/*.rs", "src/bin/main.rs", false},
+		{"src/**/*.rs", "src/main.rs", true},
+		{"src/**/*.rs", "src/bin/tool/main.rs", true},
+		{"tests/**", "tests/data/input.txt", true},
+		{"tests/**", "src/tests.rs", false},
+	}
+
+	for _, c := range cases {
+		if got := helpers.MatchGlob(c.pattern, c.name); got != c.want {
+			t.Errorf("MatchGlob(%q, %q) = %v, expected %v", c.pattern, c.name, got, c.want)
+		}
+	}
+}
+
+func TestRelativePath(t *testing.T) {
+	if got := helpers.RelativePath("src/lua/init.lua", "src/lua/"); got != "init.lua" {
+		t.Errorf("expected: init.lua, got: %s", got)
+	}
+	if got := helpers.RelativePath("src/luarocks/init.lua", "src/lua"); got != "src/luarocks/init.lua" {
+		t.Errorf("expected: src/luarocks/init.lua, got: %s", got)
+	}
+}
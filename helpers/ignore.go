@@ -0,0 +1,98 @@
+package helpers
+
+import (
+	"bufio"
+	"fmt"
+	"io"
+	"os"
+	"path"
+	"strings"
+)
+
+// ignoreRule is one pattern line of an ignore file.
+type ignoreRule struct {
+	pattern string
+	negate  bool
+	dirOnly bool
+}
+
+// IgnoreRules are the patterns of a .gitignore style file.
+type IgnoreRules struct {
+	rules []ignoreRule
+}
+
+// LoadIgnoreFile reads the .gitignore style patterns in filename.
+func LoadIgnoreFile(filename string) (*IgnoreRules, error) {
+	file, err := os.Open(filename)
+	if err != nil {
+		return nil, err
+	}
+	defer file.Close()
+	return ParseIgnore(file)
+}
+
+// ParseIgnore reads newline-delimited .gitignore style patterns. Blank lines and lines starting
+// with # are skipped, ! negates a pattern, and a trailing / limits it to directories. A pattern with a
+// slash elsewhere is anchored at the root, one without matches names at any depth.
+func ParseIgnore(r io.Reader) (*IgnoreRules, error) {
+	var rules []ignoreRule
+	scanner := bufio.NewScanner(r)
+	for lineNumber := 1; scanner.Scan(); lineNumber++ {
+		line := strings.TrimRight(scanner.Text(), " \t\r")
+		if line == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+
+		var rule ignoreRule
+		if strings.HasPrefix(line, "!") {
+			rule.negate = true
+			line = line[1:]
+		} else if strings.HasPrefix(line, `\`) {
+			// Escapes a leading # or !.
+			line = line[1:]
+		}
+		if strings.HasSuffix(line, "/") {
+			rule.dirOnly = true
+			line = strings.TrimSuffix(line, "/")
+		}
+		if strings.Contains(line, "/") {
+			line = strings.TrimPrefix(line, "/")
+		}
+		if line == "" {
+			continue
+		}
+		if err := ValidateGlob(line); err != nil {
+			return nil, fmt.Errorf("invalid pattern on line %d: %v", lineNumber, err)
+		}
+		rule.pattern = line
+		rules = append(rules, rule)
+	}
+	if err := scanner.Err(); err != nil {
+		return nil, err
+	}
+	return &IgnoreRules{rules: rules}, nil
+}
+
+// Ignored reports whether the slash-separated file is ignored. A file is ignored when the last
+// pattern matching it or one of its parent directories is not negated.
+func (r *IgnoreRules) Ignored(file string) bool {
+	ignored := false
+	for _, rule := range r.rules {
+		if rule.matches(file) {
+			ignored = !rule.negate
+		}
+	}
+	return ignored
+}
+
+func (rule ignoreRule) matches(file string) bool {
+	if !rule.dirOnly && MatchGlob(rule.pattern, file) {
+		return true
+	}
+	for dir := path.Dir(file); dir != "." && dir != "/"; dir = path.Dir(dir) {
+		if MatchGlob(rule.pattern, dir) {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,36 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"strings"
+	"testing"
+)
+
+func TestIgnoreRules(t *testing.T) {
+	rules, err := helpers.ParseIgnore(strings.NewReader(`
+# build output
+build/
+*.log
+!keep.log
+/docs/*.md
+`))
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	cases := map[string]bool{
+		"build/out/app":      true,
+		"src/build/main.go":  true,
+		"src/main.go":        false,
+		"logs/debug.log":     true,
+		"logs/keep.log":      false,
+		"docs/guide.md":      true,
+		"src/docs/guide.md":  false,
+		"docs/api/README.md": false,
+	}
+	for file, expected := range cases {
+		if got := rules.Ignored(file); got != expected {
+			t.Errorf("Ignored(%q) = %v, expected %v", file, got, expected)
+		}
+	}
+}
@@ -0,0 +1,70 @@
+package helpers
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"repo-pack/config"
+)
+
+// lastSeenPath returns the file recording the commit each synced location was last downloaded at.
+func lastSeenPath() (string, error) {
+	dir, err := config.Dir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "last-seen.json"), nil
+}
+
+func loadLastSeen(filename string) (map[string]string, error) {
+	seen := map[string]string{}
+	data, err := os.ReadFile(filename)
+	if errors.Is(err, os.ErrNotExist) {
+		return seen, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	if err := json.Unmarshal(data, &seen); err != nil {
+		return nil, fmt.Errorf("error decoding %s: %w", filename, err)
+	}
+	return seen, nil
+}
+
+// LastSeenSHA returns the commit SHA recorded for key by RecordLastSeenSHA, or "" if there is none.
+func LastSeenSHA(key string) (string, error) {
+	filename, err := lastSeenPath()
+	if err != nil {
+		return "", err
+	}
+	seen, err := loadLastSeen(filename)
+	if err != nil {
+		return "", err
+	}
+	return seen[key], nil
+}
+
+// RecordLastSeenSHA stores sha as the commit key was last downloaded at.
+func RecordLastSeenSHA(key, sha string) error {
+	filename, err := lastSeenPath()
+	if err != nil {
+		return err
+	}
+	seen, err := loadLastSeen(filename)
+	if err != nil {
+		return err
+	}
+	seen[key] = sha
+
+	data, err := json.MarshalIndent(seen, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(filename), 0o755); err != nil {
+		return err
+	}
+	return os.WriteFile(filename, data, 0o644)
+}
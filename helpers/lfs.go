@@ -0,0 +1,63 @@
+package helpers
+
+import (
+	"bufio"
+	"bytes"
+	"regexp"
+	"strconv"
+	"strings"
+)
+
+// MaxLfsPointerSize is the largest body that is still considered a possible Git LFS pointer.
+const MaxLfsPointerSize = 1024
+
+var (
+	lfsVersionRegex = regexp.MustCompile(`^version https://\S*git-lfs\S*/spec/v1$`)
+	lfsOidRegex     = regexp.MustCompile(`^oid sha256:([0-9a-f]{64})$`)
+	lfsSizeRegex    = regexp.MustCompile(`^size (\d+)$`)
+)
+
+// LfsPointer holds the fields of a Git LFS pointer file.
+type LfsPointer struct {
+	Oid  string
+	Size int64
+}
+
+// ParseLfsPointer checks whether data is a Git LFS pointer and extracts its fields.
+// The version line may use any git-lfs spec host, and surrounding whitespace is tolerated,
+// but the version, oid and size lines must all be present.
+func ParseLfsPointer(data []byte) (pointer LfsPointer, ok bool) {
+	if len(data) > MaxLfsPointerSize {
+		return LfsPointer{}, false
+	}
+
+	var hasVersion, hasOid, hasSize bool
+	scanner := bufio.NewScanner(bytes.NewReader(bytes.TrimSpace(data)))
+	for first := true; scanner.Scan(); first = false {
+		line := strings.TrimSpace(scanner.Text())
+		if first {
+			if !lfsVersionRegex.MatchString(line) {
+				return LfsPointer{}, false
+			}
+			hasVersion = true
+			continue
+		}
+
+		if match := lfsOidRegex.FindStringSubmatch(line); match != nil {
+			pointer.Oid = match[1]
+			hasOid = true
+		} else if match := lfsSizeRegex.FindStringSubmatch(line); match != nil {
+			size, err := strconv.ParseInt(match[1], 10, 64)
+			if err != nil {
+				return LfsPointer{}, false
+			}
+			pointer.Size = size
+			hasSize = true
+		}
+	}
+
+	if !hasVersion || !hasOid || !hasSize {
+		return LfsPointer{}, false
+	}
+	return pointer, true
+}
@@ -0,0 +1,46 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+const lfsOid = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393"
+
+func TestParseLfsPointer(t *testing.T) {
+	pointer := "version https://git-lfs.github.com/spec/v1\noid sha256:" + lfsOid + "\nsize 12345\n"
+
+	got, ok := helpers.ParseLfsPointer([]byte(pointer))
+	if !ok {
+		t.Fatalf("expected pointer to be detected")
+	}
+
+	expected := helpers.LfsPointer{Oid: lfsOid, Size: 12345}
+	if got != expected {
+		t.Errorf("expected pointer: %+v, got: %+v", expected, got)
+	}
+}
+
+func TestParseLfsPointerWhitespacePrefixed(t *testing.T) {
+	pointer := "\n  version https://git-lfs.github.com/spec/v1\r\noid sha256:" + lfsOid + "\r\nsize 42\r\n"
+
+	if _, ok := helpers.ParseLfsPointer([]byte(pointer)); !ok {
+		t.Errorf("expected whitespace-prefixed pointer to be detected")
+	}
+}
+
+func TestParseLfsPointerAlternativeHost(t *testing.T) {
+	pointer := "version https://hawser.github.com/git-lfs/spec/v1\noid sha256:" + lfsOid + "\nsize 42\n"
+
+	if _, ok := helpers.ParseLfsPointer([]byte(pointer)); !ok {
+		t.Errorf("expected alternative-host pointer to be detected")
+	}
+}
+
+func TestParseLfsPointerRequiresAllFields(t *testing.T) {
+	pointer := "version https://git-lfs.github.com/spec/v1\nsize 42\n"
+
+	if _, ok := helpers.ParseLfsPointer([]byte(pointer)); ok {
+		t.Errorf("expected pointer without oid to be rejected")
+	}
+}
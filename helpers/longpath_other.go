@@ -0,0 +1,8 @@
+//go:build !windows
+
+package helpers
+
+// CheckPathLength only limits path lengths on Windows.
+func CheckPathLength(fullPath string, allowLong bool) error {
+	return nil
+}
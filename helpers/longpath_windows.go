@@ -0,0 +1,18 @@
+package helpers
+
+import "fmt"
+
+// maxWindowsPath is MAX_PATH, the path length many Windows programs cannot go beyond.
+const maxWindowsPath = 260
+
+// CheckPathLength rejects absolute paths over MAX_PATH unless allowLong is set, in which case the os
+// package writes them through the \\?\ long path prefix.
+func CheckPathLength(fullPath string, allowLong bool) error {
+	if allowLong || len(fullPath) < maxWindowsPath {
+		return nil
+	}
+	return fmt.Errorf(
+		"path %s is %d characters, too long for the Windows MAX_PATH limit of %d; use a shorter --output or pass --windows-long-paths",
+		fullPath, len(fullPath), maxWindowsPath,
+	)
+}
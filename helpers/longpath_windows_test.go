@@ -0,0 +1,22 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"strings"
+	"testing"
+)
+
+func TestCheckPathLength(t *testing.T) {
+	short := `C:\Users\me\repo\lua\init.lua`
+	long := `C:\Users\me\` + strings.Repeat(`nested\`, 40) + "init.lua"
+
+	if err := helpers.CheckPathLength(short, false); err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+	if err := helpers.CheckPathLength(long, false); err == nil || !strings.Contains(err.Error(), "--windows-long-paths") {
+		t.Errorf("expected an error suggesting --windows-long-paths, got: %v", err)
+	}
+	if err := helpers.CheckPathLength(long, true); err != nil {
+		t.Errorf("unexpected error with long paths allowed: %v", err)
+	}
+}
@@ -4,6 +4,7 @@ import (
 	"repo-pack/helpers"
 	"repo-pack/model"
 	"testing"
+	"time"
 )
 
 func TestParseRepoValidURL(t *testing.T) {
@@ -25,6 +26,19 @@ func TestParseRepoValidURL(t *testing.T) {
 	}
 }
 
+func TestParseRepoRootURL(t *testing.T) {
+	expected := model.RepoURLComponents{Owner: "owner", Repository: "repo"}
+	for _, url := range []string{"https://github.com/owner/repo", "https://github.com/owner/repo/", "https://github.com/owner/repo.git"} {
+		components, err := helpers.ParseRepoURL(url)
+		if err != nil {
+			t.Errorf("unexpected error for %s: %v", url, err)
+		}
+		if components != expected {
+			t.Errorf("expected components for %s: %+v, got: %+v", url, expected, components)
+		}
+	}
+}
+
 func TestParseRepoInvalidURL(t *testing.T) {
 	url := "invalid-url"
 	expected := model.RepoURLComponents{}
@@ -43,9 +57,9 @@ func TestParseRepoInvalidURL(t *testing.T) {
 }
 
 func TestParseRepoInvalidURLFormat(t *testing.T) {
-	url := "https://github.com/owner/repo/blob/main/file.txt"
+	url := "https://github.com/owner/repo/commits/main"
 	expected := model.RepoURLComponents{}
-	expectedErr := "invalid URL format: https://github.com/owner/repo/blob/main/file.txt"
+	expectedErr := "invalid URL format: https://github.com/owner/repo/commits/main"
 
 	components, err := helpers.ParseRepoURL(url)
 	if err == nil {
@@ -58,3 +72,252 @@ func TestParseRepoInvalidURLFormat(t *testing.T) {
 		t.Errorf("expected components: %+v, got: %+v", expected, components)
 	}
 }
+
+func TestParseHeader(t *testing.T) {
+	name, value, err := helpers.ParseHeader("X-Proxy-Token:  secret value ")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if name != "X-Proxy-Token" || value != "secret value" {
+		t.Errorf("expected X-Proxy-Token: secret value, got: %s: %s", name, value)
+	}
+}
+
+func TestParseHeaderMalformed(t *testing.T) {
+	for _, header := range []string{"no-colon", "Bad Name: value", ": value", "X-Header: a\r\nInjected: b"} {
+		if _, _, err := helpers.ParseHeader(header); err == nil {
+			t.Errorf("expected error for header %q, got: nil", header)
+		}
+	}
+}
+
+func TestParseRepoWikiURL(t *testing.T) {
+	url := "https://github.com/owner/repo/wiki"
+	expected := model.RepoURLComponents{
+		Owner:      "owner",
+		Repository: "repo.wiki",
+		Ref:        "master",
+		Wiki:       true,
+	}
+
+	components, err := helpers.ParseRepoURL(url)
+	if err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+
+	if components != expected {
+		t.Errorf("expected components: %+v, got: %+v", expected, components)
+	}
+}
+
+func TestParseResolve(t *testing.T) {
+	host, ip, err := helpers.ParseResolve("api.github.com:[2001:db8::1]")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+
+	if host != "api.github.com" || ip != "2001:db8::1" {
+		t.Errorf("expected api.github.com -> 2001:db8::1, got: %s -> %s", host, ip)
+	}
+
+	if _, _, err := helpers.ParseResolve("api.github.com:not-an-ip"); err == nil {
+		t.Errorf("expected error for invalid IP, got: nil")
+	}
+}
+
+func TestParseProxy(t *testing.T) {
+	proxy, err := helpers.ParseProxy("user:secret@proxy.example.com:3128")
+	if err != nil {
+		t.Fatalf("unexpected error: %v", err)
+	}
+	if proxy.Scheme != "http" || proxy.Host != "proxy.example.com:3128" || proxy.User.Username() != "user" {
+		t.Errorf("expected an http proxy with credentials, got: %s", proxy.Redacted())
+	}
+
+	for _, value := range []string{"ftp://proxy.example.com", "http://"} {
+		if _, err := helpers.ParseProxy(value); err == nil {
+			t.Errorf("%s: expected an error", value)
+		}
+	}
+}
+
+func TestParseRepoDotGithubURL(t *testing.T) {
+	url := "https://github.com/owner/.github/tree/main/.github/ISSUE_TEMPLATE"
+	expected := model.RepoURLComponents{
+		Owner:      "owner",
+		Repository: ".github",
+		Ref:        "main",
+		Dir:        ".github/ISSUE_TEMPLATE",
+	}
+
+	components, err := helpers.ParseRepoURL(url)
+	if err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+
+	if components != expected {
+		t.Errorf("expected components: %+v, got: %+v", expected, components)
+	}
+
+	localPath := helpers.LocalPath(".github/ISSUE_TEMPLATE/bug.md", components.Dir)
+	if localPath != "ISSUE_TEMPLATE/bug.md" {
+		t.Errorf("expected local path: ISSUE_TEMPLATE/bug.md, got: %s", localPath)
+	}
+}
+
+func TestParseRepoGitLabURL(t *testing.T) {
+	url := "https://gitlab.com/group/subgroup/project/-/tree/main/docs/guides"
+	expected := model.RepoURLComponents{
+		Host:       "gitlab.com",
+		Owner:      "group/subgroup",
+		Repository: "project",
+		Ref:        "main",
+		Dir:        "docs/guides",
+	}
+
+	components, err := helpers.ParseRepoURL(url)
+	if err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+
+	if components != expected {
+		t.Errorf("expected components: %+v, got: %+v", expected, components)
+	}
+}
+
+func TestParseRepoBlobURL(t *testing.T) {
+	url := "https://github.com/owner/repo/blob/main/assets/logo.png"
+	expected := model.RepoURLComponents{
+		Owner:      "owner",
+		Repository: "repo",
+		Ref:        "main",
+		Dir:        "assets/logo.png",
+		File:       true,
+	}
+
+	components, err := helpers.ParseRepoURL(url)
+	if err != nil {
+		t.Errorf("unexpected error: %v", err)
+	}
+
+	if components != expected {
+		t.Errorf("expected components: %+v, got: %+v", expected, components)
+	}
+
+	localPath := helpers.LocalPath("assets/logo.png", components.Dir)
+	if localPath != "logo.png" {
+		t.Errorf("expected local path: logo.png, got: %s", localPath)
+	}
+
+	if _, err := helpers.ParseRepoURL("https://github.com/owner/repo/blob/main"); err == nil {
+		t.Errorf("expected an error for a blob URL without a file path")
+	}
+}
+
+func TestParseRepoRawAndAPIURLs(t *testing.T) {
+	cases := map[string]model.RepoURLComponents{
+		"https://raw.githubusercontent.com/owner/repo/main/assets/logo.png": {
+			Owner: "owner", Repository: "repo", Ref: "main", Dir: "assets/logo.png", File: true,
+		},
+		"https://raw.githubusercontent.com/owner/repo/refs/heads/dev/README.md": {
+			Owner: "owner", Repository: "repo", Ref: "dev", Dir: "README.md", File: true,
+		},
+		"https://api.github.com/repos/owner/repo/contents/docs/guide?ref=v1.2": {
+			Owner: "owner", Repository: "repo", Ref: "v1.2", Dir: "docs/guide",
+		},
+		"https://api.github.com/repos/owner/repo/contents": {
+			Owner: "owner", Repository: "repo",
+		},
+	}
+	for url, expected := range cases {
+		components, err := helpers.ParseRepoURL(url)
+		if err != nil {
+			t.Errorf("unexpected error for %s: %v", url, err)
+		}
+		if components != expected {
+			t.Errorf("expected components for %s: %+v, got: %+v", url, expected, components)
+		}
+	}
+
+	for _, url := range []string{
+		"https://raw.githubusercontent.com/owner/repo/main",
+		"https://api.github.com/repos/owner/repo/commits",
+		"https://gist.github.com/owner/0123456789abcdef",
+	} {
+		if _, err := helpers.ParseRepoURL(url); err == nil {
+			t.Errorf("expected an error for %s", url)
+		}
+	}
+}
+
+func TestParseReleaseURLs(t *testing.T) {
+	cases := map[string]model.RepoURLComponents{
+		"https://github.com/owner/repo/releases/tag/v1.0": {
+			Owner: "owner", Repository: "repo", Ref: "v1.0", Release: true,
+		},
+		"https://github.com/owner/repo/releases/latest/": {
+			Owner: "owner", Repository: "repo", Release: true,
+		},
+	}
+	for url, expected := range cases {
+		components, err := helpers.ParseRepoURL(url)
+		if err != nil {
+			t.Errorf("unexpected error for %s: %v", url, err)
+		}
+		if components != expected {
+			t.Errorf("expected components for %s: %+v, got: %+v", url, expected, components)
+		}
+	}
+}
+
+func TestParseHostConcurrency(t *testing.T) {
+	cases := []struct {
+		entry string
+		host  string
+		limit int
+	}{
+		{"media.githubusercontent.com=2", "media.githubusercontent.com", 2},
+		{"4", "*", 4},
+	}
+	for _, c := range cases {
+		host, limit, err := helpers.ParseHostConcurrency(c.entry)
+		if err != nil {
+			t.Errorf("%s: unexpected error: %v", c.entry, err)
+		}
+		if host != c.host || limit != c.limit {
+			t.Errorf("%s: expected %s=%d, got %s=%d", c.entry, c.host, c.limit, host, limit)
+		}
+	}
+
+	for _, entry := range []string{"host=0", "host=many", "=3"} {
+		if _, _, err := helpers.ParseHostConcurrency(entry); err == nil {
+			t.Errorf("%s: expected an error", entry)
+		}
+	}
+}
+
+func TestParseSince(t *testing.T) {
+	now := time.Date(2024, 3, 10, 12, 0, 0, 0, time.UTC)
+	cases := map[string]time.Time{
+		"2024-03-01T08:30:00Z": time.Date(2024, 3, 1, 8, 30, 0, 0, time.UTC),
+		"2024-03-01":           time.Date(2024, 3, 1, 0, 0, 0, 0, time.UTC),
+		"7d":                   time.Date(2024, 3, 3, 12, 0, 0, 0, time.UTC),
+		"2w":                   time.Date(2024, 2, 25, 12, 0, 0, 0, time.UTC),
+		"12h":                  time.Date(2024, 3, 10, 0, 0, 0, 0, time.UTC),
+	}
+	for value, expected := range cases {
+		got, err := helpers.ParseSince(value, now)
+		if err != nil {
+			t.Errorf("%s: unexpected error: %v", value, err)
+		} else if !got.Equal(expected) {
+			t.Errorf("%s: expected %s, got %s", value, expected, got)
+		}
+	}
+
+	for _, value := range []string{"", "yesterday", "-3d", "d"} {
+		if _, err := helpers.ParseSince(value, now); err == nil {
+			t.Errorf("%q: expected an error", value)
+		}
+	}
+}
@@ -2,38 +2,92 @@ package helpers
 
 import (
 	"fmt"
+	"os"
 	"strings"
+	"sync"
+	"sync/atomic"
 	"time"
+	"unicode/utf8"
 )
 
+// IsTerminal reports whether f is attached to a terminal rather than a file or pipe.
+func IsTerminal(f *os.File) bool {
+	info, err := f.Stat()
+	if err != nil {
+		return false
+	}
+	return info.Mode()&os.ModeCharDevice != 0
+}
+
+// DefaultBarStyle draws the bar with full blocks on a blank background.
+const DefaultBarStyle = "█  "
+
+// ValidateBarStyle checks that style has exactly three glyphs: filled, in progress and empty.
+func ValidateBarStyle(style string) error {
+	if n := utf8.RuneCountInString(style); n != 3 {
+		return fmt.Errorf("%q has %d characters, expected 3: filled, in progress and empty", style, n)
+	}
+	return nil
+}
+
 type Bar struct {
+	Hidden bool
+
+	// Bytes makes the bar count bytes instead of files, showing sizes, the transfer rate and an ETA.
+	Bytes bool
+
+	// Style is the filled, in progress and empty glyphs the bar is drawn with.
+	// DefaultBarStyle is used if it is empty or not valid, see ValidateBarStyle.
+	Style string
+
 	startTime   time.Time
 	rate        string
-	graph       string
+	glyphs      []string
 	description string
 	percent     int64
 	Cur         int64
+	start       int64
 	total       int64
 	width       int
 }
 
 func (bar *Bar) Config(start, total int64, description string) {
 	bar.Cur = start
+	bar.start = start
 	bar.total = total
 	bar.width = 50
-	bar.graph = "█"
+	style := bar.Style
+	if ValidateBarStyle(style) != nil {
+		style = DefaultBarStyle
+	}
+	bar.glyphs = strings.Split(style, "")
 	bar.description = description
 	bar.startTime = time.Now()
 	bar.updateRate()
 }
 
+// getPercent returns how much of the total is done, 100 when there is nothing to do.
 func (bar *Bar) getPercent() int64 {
+	if bar.total <= 0 {
+		return 100
+	}
 	return int64((float64(bar.Cur) / float64(bar.total)) * 100)
 }
 
 func (bar *Bar) updateRate() {
-	completedWidth := int((float64(bar.Cur) / float64(bar.total)) * float64(bar.width))
-	bar.rate = strings.Repeat(bar.graph, completedWidth) + strings.Repeat(" ", bar.width-completedWidth)
+	completedWidth := bar.width
+	if bar.total > 0 {
+		completedWidth = min(int((float64(bar.Cur)/float64(bar.total))*float64(bar.width)), bar.width)
+	}
+	bar.rate = strings.Repeat(bar.glyphs[0], completedWidth)
+	if completedWidth < bar.width {
+		bar.rate += bar.glyphs[1] + strings.Repeat(bar.glyphs[2], bar.width-completedWidth-1)
+	}
+}
+
+// Grow adds n to the total, for work found to be larger than expected once under way.
+func (bar *Bar) Grow(n int64) {
+	bar.total += n
 }
 
 func (bar *Bar) Update(cur int64) {
@@ -43,18 +97,98 @@ func (bar *Bar) Update(cur int64) {
 
 func (bar *Bar) Play(cur int64) {
 	bar.Cur = cur
+	if bar.Hidden {
+		return
+	}
 	lastPercent := bar.percent
 	bar.percent = bar.getPercent()
 	if bar.percent != lastPercent {
 		bar.updateRate()
 	}
 	elapsedTime := time.Since(bar.startTime)
+	if bar.Bytes {
+		bytesPerSec := float64(bar.Cur-bar.start) / elapsedTime.Seconds()
+		eta := "-"
+		if bytesPerSec > 0 {
+			eta = time.Duration(float64(bar.total-bar.Cur) / bytesPerSec * float64(time.Second)).Round(time.Second).String()
+		}
+		fmt.Printf("\r%s |%-50s| %3d%% %s/%s %s/s ETA %s ", bar.description, bar.rate, bar.percent, FormatSize(bar.Cur), FormatSize(bar.total), FormatSize(int64(bytesPerSec)), eta)
+		return
+	}
 	itemsPerSec := float64(bar.Cur) / elapsedTime.Seconds()
 	fmt.Printf("\r%s |%-50s| %3d%% %3d/%d %.2f it/s", bar.description, bar.rate, bar.percent, bar.Cur, bar.total, itemsPerSec)
 }
 
 func (bar *Bar) Finish() {
+	if bar.Hidden {
+		return
+	}
 	bar.updateRate()
 	elapsedTime := time.Since(bar.startTime)
+	if bar.Bytes {
+		fmt.Printf("\r%s |%-20s| 100%% %s  Time: %s\n", bar.description, bar.rate, FormatSize(bar.total), elapsedTime.String())
+		return
+	}
 	fmt.Printf("\r%s |%-20s| 100%% %3d/%d  Time: %s\n", bar.description, bar.rate, bar.total, bar.total, elapsedTime.String())
 }
+
+// spinnerFrames are drawn in turn to show that the Spinner is alive.
+var spinnerFrames = []string{"|", "/", "-", "\\"}
+
+// Spinner shows that a step of unknown length, such as listing the files, is under way.
+type Spinner struct {
+	Hidden bool
+
+	// Unit names what Add counts, e.g. "directories listed"; the count is shown once it is above zero.
+	Unit string
+
+	count   atomic.Int64
+	done    chan struct{}
+	stopped sync.WaitGroup
+}
+
+// Start draws the spinner with description, redrawing it until Stop is called.
+func (s *Spinner) Start(description string) {
+	if s.Hidden {
+		return
+	}
+	s.done = make(chan struct{})
+	s.stopped.Add(1)
+	go func() {
+		defer s.stopped.Done()
+		ticker := time.NewTicker(100 * time.Millisecond)
+		defer ticker.Stop()
+
+		width := 0
+		for frame := 0; ; frame++ {
+			line := fmt.Sprintf("%s %s", description, spinnerFrames[frame%len(spinnerFrames)])
+			if count := s.count.Load(); count > 0 {
+				line += fmt.Sprintf(" %d %s", count, s.Unit)
+			}
+			width = max(width, utf8.RuneCountInString(line))
+			fmt.Printf("\r%-*s", width, line)
+
+			select {
+			case <-ticker.C:
+			case <-s.done:
+				fmt.Printf("\r%s\r", strings.Repeat(" ", width))
+				return
+			}
+		}
+	}()
+}
+
+// Add counts n more of the Unit, e.g. one more directory listed. It is safe for concurrent use.
+func (s *Spinner) Add(n int64) {
+	s.count.Add(n)
+}
+
+// Stop clears the spinner from the terminal.
+func (s *Spinner) Stop() {
+	if s.done == nil {
+		return
+	}
+	close(s.done)
+	s.stopped.Wait()
+	s.done = nil
+}
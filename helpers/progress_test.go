@@ -0,0 +1,27 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestBarWithNothingToDo(t *testing.T) {
+	// A run whose filters match nothing has a total of 0, which must not be divided by.
+	bar := &helpers.Bar{}
+	bar.Config(0, 0, "[-] Progress: ")
+	bar.Update(0)
+	bar.Finish()
+}
+
+func TestByteBarPastItsTotal(t *testing.T) {
+	// Listed sizes can fall short of the bytes read, e.g. for Git LFS objects, which must not widen the bar.
+	bar := &helpers.Bar{Bytes: true}
+	bar.Config(0, 10, "[-] Progress: ")
+	bar.Update(20)
+	bar.Finish()
+
+	bar = &helpers.Bar{Bytes: true}
+	bar.Config(0, 0, "[-] Progress: ")
+	bar.Update(0)
+	bar.Finish()
+}
@@ -0,0 +1,71 @@
+package helpers
+
+import (
+	"bufio"
+	"fmt"
+	"log"
+	"os"
+	"os/exec"
+	"os/signal"
+	"strings"
+	"syscall"
+)
+
+// Confirm prints prompt and reports whether the user answered yes on stdin.
+func Confirm(prompt string) bool {
+	fmt.Print(prompt)
+	answer, err := bufio.NewReader(os.Stdin).ReadString('\n')
+	if err != nil {
+		return false
+	}
+
+	switch strings.ToLower(strings.TrimSpace(answer)) {
+	case "y", "yes":
+		return true
+	default:
+		return false
+	}
+}
+
+// ReadSecret prints prompt and reads a line from stdin without echoing it. Echo is turned off with stty;
+// where that fails on a terminal, e.g. on Windows, a warning says the input is shown as it is typed.
+// An interrupt while reading turns echo back on before the process exits.
+func ReadSecret(prompt string) (string, error) {
+	fmt.Print(prompt)
+	if err := stty("-echo"); err == nil {
+		interrupts := make(chan os.Signal, 1)
+		signal.Notify(interrupts, os.Interrupt, syscall.SIGTERM)
+		done := make(chan struct{})
+		go func() {
+			select {
+			case <-interrupts:
+				stty("echo")
+				fmt.Println()
+				os.Exit(130)
+			case <-done:
+			}
+		}()
+		defer func() {
+			signal.Stop(interrupts)
+			close(done)
+			stty("echo")
+			fmt.Println()
+		}()
+	} else if IsTerminal(os.Stdin) {
+		log.Printf("warning: could not turn off echo (%v), the input is shown as it is typed", err)
+	}
+
+	line, err := bufio.NewReader(os.Stdin).ReadString('\n')
+	line = strings.TrimSpace(line)
+	if line == "" && err != nil {
+		return "", err
+	}
+	return line, nil
+}
+
+// stty applies setting to the terminal on stdin.
+func stty(setting string) error {
+	cmd := exec.Command("stty", setting)
+	cmd.Stdin = os.Stdin
+	return cmd.Run()
+}
@@ -0,0 +1,43 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestParseSize(t *testing.T) {
+	cases := map[string]int64{
+		"512":    512,
+		"100K":   100 * 1024,
+		"2MB":    2 * 1024 * 1024,
+		"1.5GiB": 3 * 512 * 1024 * 1024,
+	}
+
+	for input, expected := range cases {
+		got, err := helpers.ParseSize(input)
+		if err != nil {
+			t.Errorf("unexpected error for %s: %v", input, err)
+		} else if got != expected {
+			t.Errorf("ParseSize(%s) = %d, expected %d", input, got, expected)
+		}
+	}
+
+	if _, err := helpers.ParseSize("lots"); err == nil {
+		t.Errorf("expected error for invalid size, got: nil")
+	}
+}
+
+func TestFormatSize(t *testing.T) {
+	cases := map[int64]string{
+		0:                "0 B",
+		512:              "512 B",
+		1536:             "1.5 KiB",
+		1610612736:       "1.5 GiB",
+		5629499534213120: "5120.0 TiB",
+	}
+	for input, expected := range cases {
+		if got := helpers.FormatSize(input); got != expected {
+			t.Errorf("FormatSize(%d) = %s, expected %s", input, got, expected)
+		}
+	}
+}
@@ -0,0 +1,58 @@
+package helpers
+
+import (
+	"io"
+	"sync"
+	"time"
+)
+
+// Throttle caps the combined throughput of the readers sharing it. It is a token bucket holding up to
+// one second worth of bytes; reads that overdraw it sleep until the bucket would have covered them.
+type Throttle struct {
+	rate   float64
+	mu     sync.Mutex
+	tokens float64
+	last   time.Time
+}
+
+// NewThrottle creates a throttle allowing bytesPerSecond across all of its readers.
+func NewThrottle(bytesPerSecond int64) *Throttle {
+	return &Throttle{rate: float64(bytesPerSecond), tokens: float64(bytesPerSecond), last: time.Now()}
+}
+
+// Reader returns r with its reads counted against the throttle.
+func (t *Throttle) Reader(r io.ReadCloser) io.ReadCloser {
+	return &throttledReader{ReadCloser: r, throttle: t}
+}
+
+// wait takes n bytes from the bucket, sleeping for as long as that leaves it in deficit.
+func (t *Throttle) wait(n int) {
+	t.mu.Lock()
+	now := time.Now()
+	t.tokens = min(t.rate, t.tokens+now.Sub(t.last).Seconds()*t.rate)
+	t.last = now
+	t.tokens -= float64(n)
+	deficit := -t.tokens
+	t.mu.Unlock()
+
+	if deficit > 0 {
+		time.Sleep(time.Duration(deficit / t.rate * float64(time.Second)))
+	}
+}
+
+type throttledReader struct {
+	io.ReadCloser
+	throttle *Throttle
+}
+
+func (r *throttledReader) Read(p []byte) (int, error) {
+	// Reading at most one second worth of bytes at a time keeps each sleep short.
+	if limit := int(r.throttle.rate); limit > 0 && len(p) > limit {
+		p = p[:limit]
+	}
+	n, err := r.ReadCloser.Read(p)
+	if n > 0 {
+		r.throttle.wait(n)
+	}
+	return n, err
+}
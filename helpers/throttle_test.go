@@ -0,0 +1,42 @@
+package helpers_test
+
+import (
+	"bytes"
+	"io"
+	"os"
+	"path/filepath"
+	"repo-pack/helpers"
+	"sync"
+	"testing"
+	"time"
+)
+
+func TestThrottleCapsCombinedRate(t *testing.T) {
+	const rate = 64 << 10
+	throttle := helpers.NewThrottle(rate)
+	root := t.TempDir()
+
+	// Two files of one second worth each: the first second is the initial burst, the other must be waited for.
+	start := time.Now()
+	var wg sync.WaitGroup
+	for _, name := range []string{"a.bin", "b.bin"} {
+		wg.Add(1)
+		go func(name string) {
+			defer wg.Done()
+			reader := io.NopCloser(bytes.NewReader(make([]byte, rate)))
+			if err := helpers.SaveFile(name, reader, helpers.SaveOptions{Root: root, Throttle: throttle}); err != nil {
+				t.Errorf("unexpected error: %v", err)
+			}
+		}(name)
+	}
+	wg.Wait()
+
+	if elapsed := time.Since(start); elapsed < 900*time.Millisecond {
+		t.Errorf("expected the downloads to take at least a second, took %s", elapsed)
+	}
+	for _, name := range []string{"a.bin", "b.bin"} {
+		if info, err := os.Stat(filepath.Join(root, name)); err != nil || info.Size() != rate {
+			t.Errorf("%s: expected %d bytes, got %v, %v", name, rate, info, err)
+		}
+	}
+}
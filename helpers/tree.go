@@ -0,0 +1,119 @@
+package helpers
+
+import (
+	"fmt"
+	"sort"
+	"strings"
+)
+
+// RenderTree renders slash-separated file paths as an indented tree, directories first in sorted order
+// and marked with a trailing slash. Each level is indented by two spaces.
+func RenderTree(paths []string) string {
+	sorted := make([][]string, len(paths))
+	for i, p := range paths {
+		sorted[i] = strings.Split(strings.Trim(p, "/"), "/")
+	}
+	sort.Slice(sorted, func(i, j int) bool {
+		return treeLess(sorted[i], sorted[j])
+	})
+
+	var tree strings.Builder
+	var previous []string
+	for _, segments := range sorted {
+		dirs := segments[:len(segments)-1]
+		common := 0
+		for common < len(dirs) && common < len(previous) && dirs[common] == previous[common] {
+			common++
+		}
+		for depth := common; depth < len(dirs); depth++ {
+			tree.WriteString(strings.Repeat("  ", depth) + dirs[depth] + "/\n")
+		}
+		tree.WriteString(strings.Repeat("  ", len(dirs)) + segments[len(segments)-1] + "\n")
+		previous = dirs
+	}
+	return tree.String()
+}
+
+// treeLess orders paths so that the contents of a directory come before the files next to it.
+func treeLess(a, b []string) bool {
+	for i := 0; i < len(a) && i < len(b); i++ {
+		aIsDir, bIsDir := i < len(a)-1, i < len(b)-1
+		if a[i] != b[i] || aIsDir != bIsDir {
+			if aIsDir != bIsDir {
+				return aIsDir
+			}
+			return a[i] < b[i]
+		}
+	}
+	return len(a) < len(b)
+}
+
+// treeNode is a directory of the trie built by RenderBoxTree.
+type treeNode struct {
+	dirs  map[string]*treeNode
+	files []string
+	count int
+}
+
+func (n *treeNode) add(segments []string) {
+	n.count++
+	if len(segments) == 1 {
+		n.files = append(n.files, segments[0])
+		return
+	}
+	if n.dirs == nil {
+		n.dirs = map[string]*treeNode{}
+	}
+	child, ok := n.dirs[segments[0]]
+	if !ok {
+		child = &treeNode{}
+		n.dirs[segments[0]] = child
+	}
+	child.add(segments[1:])
+}
+
+// RenderBoxTree renders slash-separated file paths like the tree command, with box-drawing branches
+// under a "." root. Directories come first in sorted order, labelled with the number of files below them.
+func RenderBoxTree(paths []string) string {
+	root := &treeNode{}
+	for _, p := range paths {
+		root.add(strings.Split(strings.Trim(p, "/"), "/"))
+	}
+
+	var tree strings.Builder
+	tree.WriteString(".\n")
+	root.render(&tree, "")
+	return tree.String()
+}
+
+func (n *treeNode) render(tree *strings.Builder, prefix string) {
+	names := make([]string, 0, len(n.dirs))
+	for name := range n.dirs {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	files := append([]string(nil), n.files...)
+	sort.Strings(files)
+
+	entries := len(names) + len(files)
+	for i, name := range names {
+		branch, indent := "├── ", "│   "
+		if i == entries-1 {
+			branch, indent = "└── ", "    "
+		}
+		child := n.dirs[name]
+		unit := "files"
+		if child.count == 1 {
+			unit = "file"
+		}
+		fmt.Fprintf(tree, "%s%s%s/ (%d %s)\n", prefix, branch, name, child.count, unit)
+		child.render(tree, prefix+indent)
+	}
+	for i, file := range files {
+		branch := "├── "
+		if len(names)+i == entries-1 {
+			branch = "└── "
+		}
+		tree.WriteString(prefix + branch + file + "\n")
+	}
+}
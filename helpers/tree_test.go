@@ -0,0 +1,41 @@
+package helpers_test
+
+import (
+	"repo-pack/helpers"
+	"testing"
+)
+
+func TestRenderTree(t *testing.T) {
+	paths := []string{"lua/init.lua", "README.md", "lua/plugins/telescope.lua", "lua/plugins/cmp.lua", "after/ftplugin/go.lua"}
+	expected := "after/\n" +
+		"  ftplugin/\n" +
+		"    go.lua\n" +
+		"lua/\n" +
+		"  plugins/\n" +
+		"    cmp.lua\n" +
+		"    telescope.lua\n" +
+		"  init.lua\n" +
+		"README.md\n"
+
+	if got := helpers.RenderTree(paths); got != expected {
+		t.Errorf("expected:\n%s\ngot:\n%s", expected, got)
+	}
+}
+
+func TestRenderBoxTree(t *testing.T) {
+	paths := []string{"lua/init.lua", "README.md", "lua/plugins/telescope.lua", "lua/plugins/cmp.lua", "after/ftplugin/go.lua"}
+	expected := ".\n" +
+		"├── after/ (1 file)\n" +
+		"│   └── ftplugin/ (1 file)\n" +
+		"│       └── go.lua\n" +
+		"├── lua/ (3 files)\n" +
+		"│   ├── plugins/ (2 files)\n" +
+		"│   │   ├── cmp.lua\n" +
+		"│   │   └── telescope.lua\n" +
+		"│   └── init.lua\n" +
+		"└── README.md\n"
+
+	if got := helpers.RenderBoxTree(paths); got != expected {
+		t.Errorf("expected:\n%s\ngot:\n%s", expected, got)
+	}
+}
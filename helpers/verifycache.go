@@ -0,0 +1,85 @@
+package helpers
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+	"path"
+	"path/filepath"
+	"sync"
+	"time"
+)
+
+// VerificationCacheFile is the name of the sidecar recording verified files, kept in the output directory.
+const VerificationCacheFile = ".repo-pack-verified.json"
+
+// verifiedFile is what a file looked like on disk when it passed verification.
+type verifiedFile struct {
+	SHA256  string    `json:"sha256"`
+	Size    int64     `json:"size"`
+	ModTime time.Time `json:"mod_time"`
+}
+
+// VerificationCache remembers which saved files passed checksum verification, so that unchanged
+// files can be skipped on later runs. It is safe for concurrent use.
+type VerificationCache struct {
+	path    string
+	mu      sync.Mutex
+	entries map[string]verifiedFile
+}
+
+// LoadVerificationCache reads the cache stored at filename, a missing file gives an empty cache.
+func LoadVerificationCache(filename string) (*VerificationCache, error) {
+	cache := &VerificationCache{path: filename, entries: map[string]verifiedFile{}}
+
+	data, err := os.ReadFile(filename)
+	if errors.Is(err, os.ErrNotExist) {
+		return cache, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	if err := json.Unmarshal(data, &cache.entries); err != nil {
+		return nil, fmt.Errorf("error decoding verification cache %s: %w", filename, err)
+	}
+	return cache, nil
+}
+
+// Fresh reports whether localPath was verified against sum and has not changed on disk since.
+func (c *VerificationCache) Fresh(localPath, sum string) bool {
+	localPath = path.Clean(localPath)
+
+	c.mu.Lock()
+	entry, ok := c.entries[localPath]
+	c.mu.Unlock()
+	if !ok || entry.SHA256 != sum {
+		return false
+	}
+
+	info, err := os.Stat(filepath.FromSlash(localPath))
+	return err == nil && info.Size() == entry.Size && info.ModTime().Equal(entry.ModTime)
+}
+
+// Record stores that localPath, as info describes the file saved there, matched sum.
+func (c *VerificationCache) Record(localPath, sum string, info os.FileInfo) {
+	localPath = path.Clean(localPath)
+
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.entries[localPath] = verifiedFile{SHA256: sum, Size: info.Size(), ModTime: info.ModTime()}
+}
+
+// Save writes the cache back to the file it was loaded from.
+func (c *VerificationCache) Save() error {
+	c.mu.Lock()
+	data, err := json.MarshalIndent(c.entries, "", "  ")
+	c.mu.Unlock()
+	if err != nil {
+		return fmt.Errorf("error encoding verification cache: %w", err)
+	}
+	if err := os.WriteFile(c.path, data, 0o644); err != nil {
+		return fmt.Errorf("error writing verification cache %s: %w", c.path, err)
+	}
+	return nil
+}
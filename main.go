@@ -2,15 +2,46 @@ package main
 
 import (
 	"context"
-	"flag"
+	"encoding/json"
+	"errors"
 	"fmt"
+	"io"
 	"log"
-	"sync"
+	"net/http"
+	"net/url"
+	"os"
+	"os/signal"
+	"path"
+	"path/filepath"
+	"sort"
+	"strings"
+	"syscall"
+	"time"
 
+	"repo-pack/config"
+	"repo-pack/download"
 	"repo-pack/gh"
+	"repo-pack/gitlab"
 	"repo-pack/helpers"
+	"repo-pack/model"
 )
 
+// sessionBatchSize is how many files are downloaded between two session checkpoints
+const sessionBatchSize = 50
+
+// waitForRefAttempts and waitForRefDelay bound the listing retries of --wait-for-ref,
+// the delay doubles after every attempt
+const (
+	waitForRefAttempts = 5
+	waitForRefDelay    = 2 * time.Second
+)
+
+// autoRetryDelay is the pause before --auto-retry-run retries the files that failed
+const autoRetryDelay = 5 * time.Second
+
+// largeRepoSizeKB is the repository size above which whole-repository downloads ask for confirmation
+const largeRepoSizeKB = 500 * 1024
+
 func main() {
 	if err := run(); err != nil {
 		log.Fatal(err)
@@ -18,62 +49,1557 @@ func main() {
 }
 
 func run() error {
-	repoURL := flag.String("url", "", "GitHub repository URL")
-	token := flag.String("token", "", "GitHub personal access token")
-	flag.Parse()
+	if len(os.Args) > 1 && os.Args[1] == "version" {
+		return runVersion(os.Args[2:])
+	}
+	if len(os.Args) > 1 && os.Args[1] == "auth" {
+		return runAuth(os.Args[2:])
+	}
+	if len(os.Args) > 1 && os.Args[1] == "completions" {
+		return runCompletions(os.Args[2:])
+	}
+	if len(os.Args) > 1 && os.Args[1] == "config" {
+		return runConfig(os.Args[2:])
+	}
 
-	if *repoURL == "" {
-		err := fmt.Errorf("missing argument for repoURL")
-		return err
+	start := time.Now()
+	opts := parseFlags()
+	if opts.version {
+		printVersion(false)
+		return nil
+	}
+
+	// With --output - stdout carries the file content, so everything else printed goes to stderr.
+	stdout := os.Stdout
+	if opts.output == "-" {
+		os.Stdout = os.Stderr
 	}
 
-	components, err := helpers.ParseRepoURL(*repoURL)
+	cfg, err := config.Load()
 	if err != nil {
-		return fmt.Errorf("failed to parse repository URL: %v", err)
+		return fmt.Errorf("failed to load configuration: %v", err)
+	}
+	if cfg, err = cfg.WithProfile(opts.profile); err != nil {
+		return err
+	}
+	if cfg.Host != "" && cfg.Host != "github.com" {
+		gh.UseHost(cfg.Host)
+	}
+	if cfg.Concurrency != 0 && !opts.explicit["concurrency"] {
+		opts.concurrency = cfg.Concurrency
+	}
+	if err := enterOutputDir(&opts, cfg); err != nil {
+		return err
+	}
+	if opts.token == "" && opts.tokenFile != "" {
+		if opts.token, err = config.ReadTokenFile(opts.tokenFile); err != nil {
+			return fmt.Errorf("failed to read token: %v", err)
+		}
+	}
+	// Without a URL, as when resuming a session, the repository is taken to be on GitHub.
+	var urlComponents model.RepoURLComponents
+	if opts.repoURL != "" {
+		if urlComponents, err = helpers.ParseRepoURL(opts.repoURL); err != nil {
+			return fmt.Errorf("failed to parse repository URL: %v", err)
+		}
+	}
+	// The fallbacks hold GitHub tokens, which must not be sent to other hosts.
+	if opts.token == "" && onGitHub(urlComponents) {
+		if opts.token, err = cfg.Token(); err != nil {
+			return fmt.Errorf("failed to read token: %v", err)
+		}
+	}
+
+	annotate := helpers.GitHubActionsEnabled(opts.githubActions)
+	if opts.verbose {
+		gh.Logf = log.Printf
+	}
+
+	for _, pattern := range opts.priorities {
+		if err := helpers.ValidateGlob(pattern); err != nil {
+			return fmt.Errorf("invalid priority pattern %q: %v", pattern, err)
+		}
+	}
+	for _, pattern := range append(opts.include, opts.exclude...) {
+		if err := helpers.ValidateGlob(pattern); err != nil {
+			return fmt.Errorf("invalid filter pattern %q: %v", pattern, err)
+		}
+	}
+
+	fetchOpts := gh.FetchOptions{
+		MaxLfsSize:      lfsSizeCap(opts),
+		NoLfs:           opts.noLfs,
+		NoAnchor:        opts.noAnchor,
+		StripComponents: opts.prefixStrip,
+		LfsStats:        &gh.LfsStats{},
+	}
+	if opts.maxMemory > 0 {
+		fetchOpts.MemoryBudget = helpers.NewMemoryBudget(int64(opts.maxMemory))
+	}
+	if opts.since != "" {
+		since, err := helpers.ParseSince(opts.since, time.Now())
+		if err != nil {
+			return fmt.Errorf("invalid --since: %v", err)
+		}
+		fetchOpts.Since = since
+	}
+	if opts.maxRate > 0 {
+		fetchOpts.Save.Throttle = helpers.NewThrottle(int64(opts.maxRate))
+	}
+	if opts.output == "-" {
+		fetchOpts.Save.Writer = stdout
+	}
+	fetchOpts.Save.EnsureFinalNewline = opts.finalNewline
+	fetchOpts.Save.WindowsLongPaths = opts.longPaths
+	if opts.verifySums != "" {
+		checksums, err := helpers.LoadChecksums(opts.verifySums)
+		if err != nil {
+			return fmt.Errorf("failed to load checksums: %v", err)
+		}
+		switch opts.missingSums {
+		case "warn":
+		case "error":
+			checksums.RequireEntries = true
+		default:
+			return fmt.Errorf("invalid --missing-sums value: %s", opts.missingSums)
+		}
+		if opts.verifyCache {
+			cache, err := helpers.LoadVerificationCache(helpers.VerificationCacheFile)
+			if err != nil {
+				return fmt.Errorf("failed to load verification cache: %v", err)
+			}
+			checksums.Cache = cache
+		}
+		fetchOpts.Save.Checksums = checksums
+	} else if opts.verifyCache {
+		return fmt.Errorf("--verify-cache requires --verify-sums")
+	}
+	if opts.fromEncoding != "" {
+		transcoder, err := helpers.NewTranscoder(opts.fromEncoding, opts.encodingErrors)
+		if err != nil {
+			return err
+		}
+		fetchOpts.Save.Transcoder = transcoder
+	}
+
+	headers := http.Header{}
+	for _, header := range opts.headers {
+		name, value, err := helpers.ParseHeader(header)
+		if err != nil {
+			return err
+		}
+		headers.Add(name, value)
+	}
+	// Other hosting services are named by the URL, the GitHub hosts are always allowed.
+	headerHosts := append([]string{}, opts.headerHosts...)
+	if urlComponents.Host != "" {
+		headerHosts = append(headerHosts, urlComponents.Host)
+	}
+
+	resolve := map[string]string{}
+	for _, entry := range opts.resolve {
+		host, ip, err := helpers.ParseResolve(entry)
+		if err != nil {
+			return err
+		}
+		resolve[host] = ip
+	}
+
+	var proxy *url.URL
+	if opts.proxy != "" {
+		var err error
+		if proxy, err = helpers.ParseProxy(opts.proxy); err != nil {
+			return err
+		}
+	}
+
+	hostConcurrency := map[string]int{}
+	for _, entry := range opts.hostConcurrency {
+		host, limit, err := helpers.ParseHostConcurrency(entry)
+		if err != nil {
+			return err
+		}
+		hostConcurrency[host] = limit
 	}
 
+	gh.ConfigureClient(gh.ClientOptions{
+		ConnectTimeout:  opts.connectTimeout,
+		TransferTimeout: opts.transferTimeout,
+		IdleTimeout:     opts.idleTimeout,
+		Headers:         headers,
+		HeaderHosts:     headerHosts,
+		Resolve:         resolve,
+		Proxy:           proxy,
+		NoProxy:         opts.noProxy,
+		HostConcurrency: hostConcurrency,
+		WaitRateLimit:   opts.waitRateLimit,
+		MaxWait:         opts.maxWait,
+		OnRateLimitWait: func(wait time.Duration) {
+			if !opts.quiet {
+				fmt.Printf("\n[-] Waiting %s for the rate limit to reset\n", wait.Round(time.Second))
+			}
+		},
+	})
+
 	ctx := context.Background()
-	gh.FetchRepoIsPrivate(ctx, &components, *token)
 
-	files, _, err := gh.RepoListingSlashBranchSupport(ctx, &components, *token)
+	if opts.warmup {
+		if err := gh.Warmup(ctx); err != nil {
+			log.Printf("warning: connection warmup failed: %v", err)
+		}
+	}
+	if opts.promptToken && opts.token == "" {
+		if err := promptToken(ctx, &opts); err != nil {
+			return err
+		}
+	}
+	if opts.treeSHA != "" && !onGitHub(urlComponents) {
+		return fmt.Errorf("--tree-sha is only supported for GitHub URLs")
+	}
+	if opts.checkToken && onGitHub(urlComponents) {
+		if err := (gh.Provider{Token: opts.token}).CheckToken(ctx); err != nil {
+			return fmt.Errorf("token check failed: %w", err)
+		}
+	}
+
+	if opts.atomic && !opts.staged {
+		return fmt.Errorf("--atomic requires --staged")
+	}
+	if opts.staged && opts.resumeSession != "" {
+		return fmt.Errorf("--staged cannot be combined with --resume-session")
+	}
+	if opts.staged && opts.viaGit {
+		// The clone is saved straight into the output directory, which would give up the all or nothing guarantee.
+		return fmt.Errorf("--staged and --atomic cannot be combined with --via-git")
+	}
+	if opts.flatten && (opts.compare || opts.viaGit) {
+		return fmt.Errorf("--flatten cannot be combined with --compare or --via-git")
+	}
+	if opts.noLfs && opts.maxLfsSize > 0 {
+		return fmt.Errorf("--no-lfs cannot be combined with --max-lfs-size")
+	}
+	if (opts.maxFileSize > 0 || opts.maxTotalSize > 0) && opts.viaGit {
+		return fmt.Errorf("--max-file-size and --max-total-size cannot be combined with --via-git")
+	}
+	if opts.proxy != "" && opts.noProxy {
+		return fmt.Errorf("--proxy cannot be combined with --no-proxy")
+	}
+	if opts.prefixStrip < 0 {
+		return fmt.Errorf("--prefix-strip must not be negative")
+	}
+	if opts.prefixStrip > 0 && (opts.flatten || opts.compare) {
+		return fmt.Errorf("--prefix-strip cannot be combined with --flatten or --compare")
+	}
+	if opts.archive != "" && (opts.staged || opts.viaGit || opts.resumeSession != "" || opts.resume || opts.skipExisting || opts.onConflict != "") {
+		return fmt.Errorf("--archive cannot be combined with --staged, --via-git, --resume-session, --resume, --skip-existing or --on-conflict")
+	}
+	if opts.checksumFile != "" && (opts.archive != "" || opts.viaGit) {
+		return fmt.Errorf("--checksum-file cannot be combined with --archive or --via-git")
+	}
+	if opts.explicit["max-depth"] && opts.maxDepth < 1 {
+		return fmt.Errorf("--max-depth must be at least 1")
+	}
+	if opts.viaGit && (len(opts.include) > 0 || len(opts.exclude) > 0 || opts.maxDepth > 0 || opts.ignoreFile != "") {
+		return fmt.Errorf("--via-git cannot be combined with --include, --exclude, --max-depth or --ignore-file")
+	}
+	if opts.since != "" && opts.viaGit {
+		return fmt.Errorf("--since cannot be combined with --via-git")
+	}
+	if opts.viaGit && (opts.dryRun || opts.skipExisting || opts.resume || opts.onConflict != "" || opts.manifest != "" || opts.outputList != "" || opts.index != "") {
+		return fmt.Errorf("--via-git cannot be combined with --dry-run, --skip-existing, --resume, --on-conflict, --manifest, --output-list or --index")
+	}
+	if opts.list && (opts.compare || opts.viaGit) {
+		return fmt.Errorf("--list cannot be combined with --compare or --via-git")
+	}
+	if opts.output == "-" && (opts.archive != "" || opts.staged || opts.viaGit || opts.compare || opts.list || opts.resumeSession != "" || opts.withContext) {
+		return fmt.Errorf("--output - cannot be combined with --archive, --staged, --via-git, --compare, --list, --resume-session or --with-context")
+	}
+	if opts.list {
+		// Only the listing goes to stdout, so that it can be piped into other tools.
+		opts.quiet = true
+	}
+	if opts.existing, err = existingFiles(opts); err != nil {
+		return err
+	}
+
+	if opts.compare {
+		return compareWithRemote(ctx, opts, fetchOpts)
+	}
+
+	if opts.viaGit {
+		done, err := downloadViaGit(ctx, opts, fetchOpts)
+		if err != nil || done {
+			return err
+		}
+	}
+
+	session, err := loadOrCreateSession(ctx, opts)
 	if err != nil {
-		return fmt.Errorf("failed to get files via contents API: %v", err)
+		return err
+	}
+	if session == nil {
+		return nil
 	}
+	session.Provider = providerFor(session.Components, opts)
+	session.Concurrency = opts.concurrency
+	session.Stagger = time.Duration(opts.staggerMs) * time.Millisecond
+	session.FailFast = opts.failFast
+	session.Retries = opts.retries
+	if opts.verbose {
+		session.OnRetry = func(file string, attempt int, err error) {
+			log.Printf("retrying %s (attempt %d of %d): %v", file, attempt, opts.retries+1, err)
+		}
+	}
+	if opts.flatten {
+		paths := make([]string, len(session.Files))
+		for i, file := range session.Files {
+			paths[i] = file.Path
+		}
+		var collisions []helpers.Collision
+		fetchOpts.Flattened, collisions = helpers.FlattenPaths(paths)
+		if opts.verbose {
+			for _, collision := range collisions {
+				log.Printf("%s shares its name with another file, saving it as %s", collision.File, collision.Name)
+			}
+		}
+	}
+	switch opts.existing {
+	case download.ExistingNewer:
+		fetchOpts.IfNewer = true
+	case download.ExistingRename:
+		// Shared with session.FetchOptions, resolveExisting fills it in once the existing files are known.
+		fetchOpts.RemoteCopies = map[string]bool{}
+	}
+	session.FetchOptions = fetchOpts
+	session.VerifyBlobs = opts.verifyBlobs
+	components := session.Components
 
-	fmt.Printf("[-] Repository: %s/%s\n", components.Owner, components.Repository)
-	fmt.Printf("[-] GitHub Directory: %s\n", components.Dir)
-	fmt.Printf("[-] Fetching %d files\n", len(files))
+	if opts.list {
+		printListing(session, opts.verbose)
+		return nil
+	}
+	if opts.output == "-" && len(session.Files) != 1 {
+		return fmt.Errorf("--output - writes a single file to stdout, but %d files were selected", len(session.Files))
+	}
 
-	bar := &helpers.Bar{}
-	bar.Config(0, int64(len(files)), "[-] Progress: ")
+	if opts.prefixStrip > 0 {
+		for i := range session.Files {
+			file := &session.Files[i]
+			if file.State != download.StatePending || fetchOpts.LocalPath(file.Path, &components) != "" {
+				continue
+			}
+			file.State = download.StateSkipped
+			file.Error = fmt.Errorf("%w: --prefix-strip leaves nothing of its path", gh.ErrSkipped).Error()
+			if !opts.quiet {
+				log.Printf("warning: skipping %s, --prefix-strip %d leaves nothing of its path", file.Path, opts.prefixStrip)
+			}
+		}
+	}
 
-	var wg sync.WaitGroup
-	errorsCh := make(chan error, len(files))
+	skipOversized(session, opts)
 
-	// Use semaphores to manage the goroutines, this current implementation can affect performance if file number is too large
-	for _, file := range files {
-		wg.Add(1)
-		go func(file string) {
-			defer wg.Done()
+	if opts.resumeSession != "" {
+		rechecked := session.RecheckDownloaded(func(path string) string {
+			return fetchOpts.LocalPath(path, &components)
+		})
+		if rechecked > 0 && !opts.quiet {
+			log.Printf("%d previously downloaded files are missing or empty locally, downloading them again", rechecked)
+		}
+	}
 
-			err := gh.FetchPublicFile(ctx, file, &components)
-			if err != nil {
-				errorsCh <- fmt.Errorf("error fetching %s: %v", file, err)
-				return
+	// The lockfile is updated as files complete; a missing or unreadable one only costs the SHA comparison.
+	lock, err := download.LoadLockFile(download.LockFileName)
+	if err != nil && !errors.Is(err, os.ErrNotExist) {
+		log.Printf("warning: ignoring %s: %v", download.LockFileName, err)
+	}
+	err = resolveExisting(session, opts, lock, func(path string) string {
+		return fetchOpts.LocalPath(path, &components)
+	})
+	if err != nil {
+		return err
+	}
+	if lock == nil {
+		lock = download.NewLockFile()
+	}
+	if err := checkTotalSize(session, opts); err != nil {
+		return err
+	}
+	if opts.maxTotalSize > 0 && !opts.force {
+		// Git LFS objects are listed at the size of their pointer, so they get what the listed files leave.
+		session.FetchOptions.LfsBudget = gh.NewSizeBudget(max(int64(opts.maxTotalSize)-session.RemainingSize(), 0))
+	}
+
+	if !opts.quiet {
+		fmt.Printf("[-] Repository: %s/%s\n", components.Owner, components.Repository)
+		fmt.Printf("[-] GitHub Directory: %s\n", components.Dir)
+		verb := "Fetching"
+		if opts.dryRun {
+			verb = "Would fetch"
+		}
+		remaining, size := len(session.Remaining()), session.RemainingSize()
+		switch {
+		case size > 0 && session.RemainingSizeKnown():
+			fmt.Printf("[-] %s %d files (about %s)\n", verb, remaining, helpers.FormatSize(size))
+		case size > 0:
+			fmt.Printf("[-] %s %d files (at least %s, some sizes unknown)\n", verb, remaining, helpers.FormatSize(size))
+		case remaining > 0:
+			fmt.Printf("[-] %s %d files (size unknown)\n", verb, remaining)
+		default:
+			fmt.Printf("[-] %s %d files\n", verb, remaining)
+		}
+	}
+	if opts.printResolved && components.CommitSHA != "" {
+		fmt.Printf("[-] Commit: %s (%s)\n", components.CommitSHA, components.Ref)
+	}
+	if opts.estimateReqs {
+		printRequestEstimate(len(session.Remaining()))
+	}
+	if opts.dryRun {
+		var localPaths []string
+		for _, file := range session.Remaining() {
+			localPaths = append(localPaths, fetchOpts.LocalPath(file, &components))
+		}
+		if opts.tree {
+			fmt.Print(helpers.RenderBoxTree(localPaths))
+			return nil
+		}
+		for _, localPath := range localPaths {
+			fmt.Println(localPath)
+		}
+		return nil
+	}
+
+	var staging string
+	if opts.staged {
+		staging, err = os.MkdirTemp("", "repo-pack-staging-")
+		if err != nil {
+			return fmt.Errorf("failed to create staging directory: %v", err)
+		}
+		session.FetchOptions.Save.Root = staging
+	}
+
+	var archive *helpers.Archive
+	if opts.archive != "" {
+		archive, err = helpers.CreateArchive(opts.archive, opts.compression)
+		if err != nil {
+			return err
+		}
+		session.FetchOptions.Save.Archive = archive
+	}
+
+	// The bar counts bytes when the listing reported sizes, so that large files weigh accordingly.
+	sizes := map[string]int64{}
+	shas := map[string]string{}
+	var done, doneBytes, totalBytes int64
+	for _, file := range session.Files {
+		sizes[file.Path] = file.Size
+		shas[file.Path] = file.SHA
+		totalBytes += file.Size
+		if file.State == download.StateDownloaded || file.State == download.StateSkipped {
+			done++
+			doneBytes += file.Size
+		}
+	}
+	bar := &helpers.Bar{
+		Hidden: opts.quiet || opts.noProgress || !(opts.progress || helpers.IsTerminal(os.Stdout)),
+		Bytes:  totalBytes > 0 && session.RemainingSizeKnown(),
+		Style:  cfg.ProgressBarStyle,
+	}
+	if err := helpers.ValidateBarStyle(cfg.ProgressBarStyle); cfg.ProgressBarStyle != "" && err != nil && !bar.Hidden {
+		log.Printf("warning: ignoring progress_bar_style %v", err)
+	}
+	if bar.Bytes {
+		bar.Config(doneBytes, totalBytes, "[-] Progress: ")
+	} else {
+		bar.Config(done, int64(len(session.Files)), "[-] Progress: ")
+	}
+
+	// failures holds the last error of every file that failed, for --errors-json and the summary;
+	// a file that succeeds when retried is dropped.
+	failures := map[string]error{}
+	session.OnFileDone = func(file string, err error) {
+		switch {
+		case errors.Is(err, gh.ErrAlreadyVerified):
+			gh.Logf("skipped %s: %v", file, err)
+		case errors.Is(err, gh.ErrSkipped):
+			message := fmt.Sprintf("skipped %s: %v", file, err)
+			if annotate {
+				fmt.Println(helpers.WorkflowCommand("warning", message))
+			} else if !opts.quiet {
+				log.Println(message)
 			}
+		case err != nil:
+			failures[file] = err
+			err = fmt.Errorf("error fetching %s: %v", file, err)
+			if annotate {
+				fmt.Println(helpers.WorkflowCommand("error", err.Error()))
+			} else if !opts.errorsJSON || opts.verbose {
+				log.Println(err)
+			}
+			return
+		default:
+			delete(failures, file)
+			lock.Record(fetchOpts.LocalPath(file, &components), shas[file], time.Now())
+		}
+		if bar.Bytes {
+			size := sizes[file]
+			// Git LFS files are listed at the size of their pointer, count the object they pointed at instead.
+			if objectSize, ok := fetchOpts.LfsStats.ObjectSize(file); ok {
+				bar.Grow(objectSize - size)
+				size = objectSize
+			}
+			bar.Update(bar.Cur + size)
+		} else {
 			bar.Update(bar.Cur + 1)
-		}(file)
+		}
+	}
+
+	ctx, interrupted, stopInterrupts := interruptible(ctx, opts.quiet)
+	defer stopInterrupts()
+	session.Interrupted = interrupted
+
+	runErr := runBatches(ctx, session, opts)
+	retried, recovered := 0, 0
+	if runErr == nil && opts.autoRetryRun && session.Count(download.StateFailed) > 0 {
+		downloaded := session.Count(download.StateDownloaded)
+		retried = session.RetryFailed()
+		if !opts.quiet {
+			log.Printf("%d files failed, retrying them in %s", retried, autoRetryDelay)
+		}
+		time.Sleep(autoRetryDelay)
+		runErr = runBatches(ctx, session, opts)
+		recovered = session.Count(download.StateDownloaded) - downloaded
+	}
+	bar.Finish()
+
+	if retried > 0 && !opts.quiet {
+		fmt.Printf("[-] Retry recovered %d of %d failed files\n", recovered, retried)
+	}
+
+	if !opts.quiet || annotate {
+		gone := 0
+		for _, err := range failures {
+			if errors.Is(err, gh.ErrFileGone) {
+				gone++
+			}
+		}
+		printSummary(session, gone, fetchOpts.LfsStats, annotate)
+	}
+	if opts.verbose {
+		printRateLimit()
+	}
+	if opts.errorsJSON {
+		if err := writeErrorsJSON(os.Stderr, failures); err != nil {
+			return err
+		}
+	}
+
+	if checksums := fetchOpts.Save.Checksums; checksums != nil && checksums.Cache != nil {
+		if err := checksums.Cache.Save(); err != nil {
+			log.Printf("warning: %v", err)
+		}
+	}
+	if checksums := fetchOpts.Save.Checksums; checksums != nil && runErr == nil {
+		for _, file := range checksums.Missing() {
+			log.Printf("warning: no checksum for %s", file)
+		}
+		if unseen := checksums.Unseen(); len(unseen) > 0 {
+			for _, file := range unseen {
+				log.Printf("checksum listed but not downloaded: %s", file)
+			}
+			runErr = fmt.Errorf("%d files from %s were not downloaded", len(unseen), opts.verifySums)
+		}
+	}
+
+	if opts.withContext {
+		if err := writeContextFile(session, session.FetchOptions, opts.repoURL); err != nil {
+			return err
+		}
+	}
+
+	if archive != nil {
+		if err := archive.Close(); err != nil {
+			return fmt.Errorf("failed to write archive: %v", err)
+		}
+	}
+
+	if staging != "" {
+		if err := finishStaging(staging, runErr == nil && session.Count(download.StateFailed) == 0, opts); err != nil {
+			return err
+		}
+	}
+
+	if archive == nil && fetchOpts.Save.Writer == nil {
+		if err := lock.Save(download.LockFileName); err != nil {
+			log.Printf("warning: %v", err)
+		}
+	}
+
+	if opts.index != "" {
+		if err := writeIndex(opts.index, session, fetchOpts); err != nil {
+			return err
+		}
+	}
+	if opts.manifest != "" {
+		if err := writeManifest(opts.manifest, session.Manifest(time.Since(start))); err != nil {
+			return err
+		}
+	}
+	if opts.checksumFile != "" {
+		if err := writeChecksumFile(opts.checksumFile, session, fetchOpts, opts.checksumSkips); err != nil {
+			return err
+		}
+	}
+	if opts.outputList != "" {
+		if err := writeOutputList(opts.outputList, session, fetchOpts, opts.listSkipped); err != nil {
+			return err
+		}
+	}
+
+	if opts.skipIfUnchanged && runErr == nil && components.CommitSHA != "" && session.Count(download.StateFailed) == 0 {
+		if err := helpers.RecordLastSeenSHA(lastSeenKey(components, opts), components.CommitSHA); err != nil {
+			log.Printf("warning: failed to record the downloaded commit: %v", err)
+		}
+	}
+
+	return runErr
+}
+
+// downloadViaGit downloads the URL directory with a partial git clone. It reports false without an error
+// if git is not installed, so that the caller falls back to downloading over HTTP.
+func downloadViaGit(ctx context.Context, opts options, fetchOpts gh.FetchOptions) (bool, error) {
+	if opts.repoURL == "" {
+		return false, fmt.Errorf("missing argument for repoURL")
+	}
+	components, err := parseRepoURL(ctx, opts)
+	if err != nil {
+		return false, err
+	}
+
+	if !onGitHub(components) {
+		return false, fmt.Errorf("--via-git only supports GitHub repositories")
+	}
+	if components.Release {
+		return false, fmt.Errorf("--via-git cannot download release assets, which are not part of the repository")
+	}
+
+	// The files are only known once cloned, so ask about the local copy of the URL directory as a whole.
+	if opts.existing == download.ExistingAsk && !opts.yes && !opts.quiet {
+		target := fetchOpts.LocalPath(components.Dir, &components)
+		if target == "" {
+			target = "."
+		}
+		if entries, err := os.ReadDir(target); err == nil && len(entries) > 0 {
+			if !helpers.IsTerminal(os.Stdin) {
+				return false, fmt.Errorf("%s already exists; pass --overwrite to replace the files in it", target)
+			}
+			if !helpers.Confirm(fmt.Sprintf("%s already exists, overwrite the files in it? [y/N] ", target)) {
+				return true, nil
+			}
+		}
+	}
+
+	saved, err := gh.FetchViaGit(ctx, &components, opts.token, fetchOpts)
+	if errors.Is(err, gh.ErrGitUnavailable) {
+		log.Printf("warning: git not found, downloading over HTTP instead")
+		return false, nil
+	}
+	if err != nil {
+		return false, fmt.Errorf("failed to download via git: %v", err)
+	}
+
+	if !opts.quiet {
+		fmt.Printf("[-] Downloaded %d files via git\n", saved)
+	}
+	return true, nil
+}
+
+// compareWithRemote lists the URL directory and reports how the local copy differs from it,
+// as a human readable list or, with --json, as JSON. It downloads nothing.
+func compareWithRemote(ctx context.Context, opts options, fetchOpts gh.FetchOptions) error {
+	if opts.repoURL == "" {
+		return fmt.Errorf("missing argument for repoURL")
+	}
+	components, err := parseRepoURL(ctx, opts)
+	if err != nil {
+		return err
+	}
+
+	provider := providerFor(components, opts)
+	items, err := provider.ListItems(ctx, &components)
+	if err != nil {
+		return fmt.Errorf("failed to list files: %v", err)
+	}
+	if items, err = filterItems(items, opts, components.Dir); err != nil {
+		return err
+	}
+
+	// Git LFS files are listed as their pointers, which only GitHub serves apart from the objects.
+	var lfsPointer download.LfsPointerFunc
+	if onGitHub(components) && !opts.noLfs {
+		lfsPointer = func(file string) (helpers.LfsPointer, bool, error) {
+			return gh.FetchLfsPointer(ctx, file, &components, opts.token)
+		}
+	}
+	localRoot := fetchOpts.LocalRoot(&components)
+	// Wiki listings report no sizes, so wiki pages are always compared by content.
+	byContent := opts.compareContent || components.Wiki
+	changes, err := download.Compare(items, func(file string) string {
+		return fetchOpts.LocalPath(file, &components)
+	}, localRoot, byContent, lfsPointer)
+	if err != nil {
+		return fmt.Errorf("failed to compare: %v", err)
+	}
+	if changes, err = filterRemoved(changes, opts, components.Dir, localRoot); err != nil {
+		return err
+	}
+
+	if opts.json {
+		data, err := json.MarshalIndent(changes, "", "  ")
+		if err != nil {
+			return err
+		}
+		fmt.Println(string(data))
+		return nil
+	}
+
+	if len(changes) == 0 {
+		fmt.Println("[-] Local copy matches the remote")
+		return nil
+	}
+	for _, change := range changes {
+		fmt.Printf("%-8s %s\n", change.Change, change.Path)
+	}
+	return nil
+}
+
+// finishStaging moves the staged files into the working directory if the run succeeded. Otherwise the
+// staging directory is discarded with --atomic, or kept and reported so nothing downloaded is lost.
+func finishStaging(staging string, succeeded bool, opts options) error {
+	if succeeded {
+		currentDir, err := os.Getwd()
+		if err != nil {
+			return err
+		}
+		if err := helpers.MoveTree(staging, currentDir); err != nil {
+			return fmt.Errorf("failed to move staged files from %s: %v", staging, err)
+		}
+		return nil
+	}
+
+	if opts.atomic {
+		log.Printf("run failed, discarding staged files")
+		return os.RemoveAll(staging)
+	}
+	log.Printf("run failed, staged files were kept in %s", staging)
+	return nil
+}
+
+// enterOutputDir changes to the output directory, --output or else the configured default, creating it
+// if needed. Paths given on the command line are made absolute first, so they stay relative to where
+// repo-pack was started.
+func enterOutputDir(opts *options, cfg config.Config) error {
+	output := opts.output
+	if output == "" {
+		output = cfg.DefaultOutputDir
+	}
+	if output == "" || output == "-" {
+		return nil
+	}
+	output, err := config.ExpandHome(output)
+	if err != nil {
+		return err
+	}
+
+	for _, path := range []*string{&opts.resumeSession, &opts.verifySums, &opts.outputList, &opts.index, &opts.manifest, &opts.archive, &opts.ignoreFile, &opts.tokenFile, &opts.checksumFile} {
+		if *path == "" || *path == "-" {
+			continue
+		}
+		if *path, err = filepath.Abs(*path); err != nil {
+			return err
+		}
+	}
+
+	if err := os.MkdirAll(output, 0o755); err != nil {
+		return fmt.Errorf("failed to create output directory: %v", err)
+	}
+	return os.Chdir(output)
+}
+
+// existingFiles returns what to do with files that already exist locally, as chosen with --overwrite,
+// --skip-existing, --resume or --on-conflict. Without them, runs that don't write to the output directory directly,
+// such as --archive and --output -, and resumed sessions overwrite, and other runs ask.
+func existingFiles(opts options) (download.ExistingFiles, error) {
+	var modes []download.ExistingFiles
+	if opts.overwrite {
+		modes = append(modes, download.ExistingOverwrite)
+	}
+	if opts.skipExisting {
+		modes = append(modes, download.ExistingSkip)
 	}
+	if opts.resume {
+		modes = append(modes, download.ExistingResume)
+	}
+	if opts.onConflict != "" {
+		mode := download.ExistingFiles(opts.onConflict)
+		switch mode {
+		case download.ExistingSkip, download.ExistingOverwrite, download.ExistingRename, download.ExistingNewer:
+		default:
+			return "", fmt.Errorf("invalid --on-conflict value %q, expected skip, overwrite, rename or newer", opts.onConflict)
+		}
+		modes = append(modes, mode)
+	}
+
+	switch {
+	case len(modes) > 1:
+		return "", fmt.Errorf("--overwrite, --skip-existing, --resume and --on-conflict cannot be combined")
+	case len(modes) == 1:
+		return modes[0], nil
+	case opts.archive != "" || opts.resumeSession != "" || opts.output == "-":
+		return download.ExistingOverwrite, nil
+	default:
+		return download.ExistingAsk, nil
+	}
+}
+
+// resolveExisting marks pending files whose local copy already exists skipped, keeps them for overwriting
+// or has them saved as .remote copies, as opts.existing says. Asking is skipped with --yes, --quiet or --dry-run, and fails when stdin is not a terminal.
+func resolveExisting(session *download.Session, opts options, lock *download.LockFile, localPath func(path string) string) error {
+	var skipped int
+	switch opts.existing {
+	case download.ExistingOverwrite:
+		return nil
+	case download.ExistingSkip:
+		skipped = session.SkipUnchanged(nil, localPath)
+	case download.ExistingResume:
+		skipped = session.SkipUnchanged(lock, localPath)
+	case download.ExistingNewer:
+		// Whether the upstream file is newer is checked as each file is fetched, see gh.FetchOptions.IfNewer.
+		return nil
+	case download.ExistingRename:
+		existing := session.Existing(localPath)
+		for _, path := range existing {
+			session.FetchOptions.RemoteCopies[path] = true
+		}
+		if len(existing) > 0 && !opts.quiet {
+			log.Printf("%d files already exist locally and are kept, saving the downloaded versions next to them as .remote", len(existing))
+		}
+		return nil
+	default:
+		existing := session.Existing(localPath)
+		if len(existing) == 0 || opts.yes || opts.quiet || opts.dryRun {
+			return nil
+		}
+		if !helpers.IsTerminal(os.Stdin) {
+			return fmt.Errorf(
+				"%d files already exist locally, e.g. %s; pass --overwrite to replace them or --skip-existing to keep them",
+				len(existing),
+				localPath(existing[0]),
+			)
+		}
+		if !helpers.Confirm(fmt.Sprintf("%d files already exist locally, overwrite them? [y/N] ", len(existing))) {
+			return fmt.Errorf("not overwriting existing files, pass --skip-existing to keep them and download the rest")
+		}
+		return nil
+	}
+
+	if skipped > 0 && !opts.quiet {
+		log.Printf("%d files already exist locally and are kept, skipping them", skipped)
+	}
+	return nil
+}
+
+// interruptGrace is how long the downloads in progress get to finish after an interrupt.
+const interruptGrace = 10 * time.Second
+
+// interruptible handles Ctrl-C and SIGTERM while files download. The first signal closes the returned
+// channel so that no further downloads start; the returned context is cancelled once the downloads in
+// progress have had interruptGrace to finish, or at once on a second signal. Calling stop restores the
+// default handling.
+func interruptible(parent context.Context, quiet bool) (ctx context.Context, interrupted <-chan struct{}, stop func()) {
+	ctx, cancel := context.WithCancel(parent)
+	closed := make(chan struct{})
+	signals := make(chan os.Signal, 2)
+	signal.Notify(signals, os.Interrupt, syscall.SIGTERM)
 
 	go func() {
-		wg.Wait()
-		close(errorsCh)
-		bar.Finish()
+		select {
+		case <-signals:
+		case <-ctx.Done():
+			return
+		}
+		close(closed)
+		if !quiet {
+			log.Printf("interrupted, letting the downloads in progress finish for up to %s, interrupt again to stop now", interruptGrace)
+		}
+		select {
+		case <-signals:
+		case <-time.After(interruptGrace):
+		case <-ctx.Done():
+		}
+		cancel()
 	}()
 
-	for err := range errorsCh {
-		log.Println(err)
+	return ctx, closed, func() {
+		signal.Stop(signals)
+		cancel()
+	}
+}
+
+// runBatches downloads the pending files of session, checkpointing it after every batch with --resume-session.
+// It returns the error that aborted the run, if any.
+func runBatches(ctx context.Context, session *download.Session, opts options) error {
+	batchSize := 0
+	if opts.resumeSession != "" {
+		batchSize = sessionBatchSize
+	}
+	for {
+		attempted, err := session.RunNextBatch(ctx, batchSize)
+		if opts.resumeSession != "" && attempted > 0 {
+			if err := session.Save(opts.resumeSession); err != nil {
+				return err
+			}
+		}
+		if err != nil || attempted == 0 {
+			return err
+		}
+	}
+}
+
+// providerFor returns the provider for the hosting service of components.
+func providerFor(components model.RepoURLComponents, opts options) download.Provider {
+	if components.Host == "gitlab.com" {
+		return gitlab.Provider{Token: opts.token}
+	}
+	return gh.Provider{Token: opts.token, TreeSHA: opts.treeSHA, Concurrency: opts.concurrency}
+}
+
+// onGitHub reports whether components refers to GitHub, where the GitHub-only API features apply.
+func onGitHub(components model.RepoURLComponents) bool {
+	return components.Host == ""
+}
+
+// rawURL returns the URL serving the content of the repository file path.
+func rawURL(path string, components *model.RepoURLComponents) string {
+	if components.Host == "gitlab.com" {
+		return gitlab.RawURL(path, components)
+	}
+	return gh.RawURL(path, components)
+}
+
+// promptToken asks for a GitHub token on the terminal when the repository is not visible without one,
+// as GitHub reports private repositories as not found, and offers to store the token in the keyring.
+// It does nothing if stdin is not a terminal, so that scripts fail as they would without --prompt-token.
+func promptToken(ctx context.Context, opts *options) error {
+	components, err := helpers.ParseRepoURL(opts.repoURL)
+	if err != nil || !onGitHub(components) || components.Wiki || !helpers.IsTerminal(os.Stdin) {
+		return nil
+	}
+	if _, err := gh.FetchRepoInfo(ctx, &components, ""); !errors.Is(err, gh.ErrRepositoryNotFound) {
+		return nil
+	}
+
+	prompt := fmt.Sprintf("%s/%s was not found, it may be private. Enter GitHub token: ", components.Owner, components.Repository)
+	token, err := helpers.ReadSecret(prompt)
+	if err != nil {
+		return fmt.Errorf("failed to read token: %v", err)
+	}
+	if token == "" {
+		return fmt.Errorf("no token given")
+	}
+	if _, err := gh.FetchRepoInfo(ctx, &components, token); err != nil {
+		return fmt.Errorf("failed to access the repository: %w", gh.Provider{Token: token}.ExplainNotFound(ctx, &components, err))
+	}
+	opts.token = token
+
+	if helpers.Confirm("Save the token in the keyring? [y/N] ") {
+		if err := config.StoreKeyringToken(token); err != nil {
+			log.Printf("warning: token not saved: %v", err)
+		} else {
+			fmt.Println("[-] Token stored in the keyring, set \"use_keyring\": true in the configuration file to use it")
+		}
+	}
+	return nil
+}
+
+// parseRepoURL parses the repository URL, resolving the default branch for URLs that name no ref.
+func parseRepoURL(ctx context.Context, opts options) (model.RepoURLComponents, error) {
+	components, err := helpers.ParseRepoURL(opts.repoURL)
+	if err != nil {
+		return components, fmt.Errorf("failed to parse repository URL: %v", err)
+	}
+	if components.Release {
+		// A release URL without a tag names the latest release, resolved when listing its assets.
+		return components, nil
+	}
+	if err := gh.ResolveDefaultBranch(ctx, &components, opts.token); err != nil {
+		if onGitHub(components) {
+			err = gh.Provider{Token: opts.token}.ExplainNotFound(ctx, &components, err)
+		}
+		return components, fmt.Errorf("failed to resolve the default branch: %w", err)
+	}
+	return components, nil
+}
+
+// loadOrCreateSession resumes the session stored at the --resume-session path if there is one,
+// otherwise it resolves the file list for the repository URL and starts a new session.
+// It returns a nil session if there is nothing to do: the user declined the download, or
+// --skip-if-unchanged found the ref at the commit of the last run.
+func loadOrCreateSession(ctx context.Context, opts options) (*download.Session, error) {
+	if opts.resumeSession != "" {
+		session, err := download.LoadSession(opts.resumeSession)
+		if err == nil {
+			return session, nil
+		}
+		if !errors.Is(err, os.ErrNotExist) {
+			return nil, fmt.Errorf("failed to load session: %v", err)
+		}
+	}
+
+	if opts.repoURL == "" {
+		err := fmt.Errorf("missing argument for repoURL")
+		return nil, err
+	}
+
+	components, err := parseRepoURL(ctx, opts)
+	if err != nil {
+		return nil, err
+	}
+
+	if components.Release && (opts.since != "" || opts.existing == download.ExistingNewer) {
+		return nil, fmt.Errorf("--since and --on-conflict newer need commit history, which release assets don't have")
+	}
+
+	if opts.skipIfUnchanged && !components.Wiki && !components.Release && onGitHub(components) {
+		unchanged, err := resolveAndCompare(ctx, &components, opts)
+		if err != nil {
+			log.Printf("warning: could not check for changes, downloading anyway: %v", err)
+		} else if unchanged {
+			if !opts.quiet {
+				fmt.Println("[-] No changes since last run")
+			}
+			return nil, nil
+		}
+	}
+
+	if !components.Wiki && !components.File && !components.Release && onGitHub(components) && isNearRoot(components.Dir) {
+		proceed, err := confirmRepoSize(ctx, &components, opts)
+		if err != nil {
+			return nil, err
+		}
+		if !proceed {
+			return nil, nil
+		}
+	}
+
+	// Listing a large directory through the contents API can take a while, so show that it is under way.
+	spinner := &helpers.Spinner{
+		Hidden: opts.quiet || opts.noProgress || !(opts.progress || helpers.IsTerminal(os.Stdout)),
+		Unit:   "directories listed",
+	}
+	gh.OnListDirectory = func(string) { spinner.Add(1) }
+	spinner.Start("[-] Listing files")
+	provider := providerFor(components, opts)
+	items, err := listFiles(ctx, provider, &components, opts)
+	spinner.Stop()
+	gh.OnListDirectory = nil
+	if err != nil && components.Wiki {
+		return nil, fmt.Errorf("failed to list the wiki of %s: %v", strings.TrimSuffix(components.Repository, ".wiki"), err)
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to get files via contents API: %v", err)
+	}
+	if items, err = filterItems(items, opts, components.Dir); err != nil {
+		return nil, err
+	}
+
+	files := make([]string, len(items))
+	itemsByPath := map[string]gh.Item{}
+	for i, item := range items {
+		files[i] = item.Path
+		itemsByPath[item.Path] = item
+	}
+
+	files = download.SortUnique(files)
+	if opts.interleave {
+		files = download.Interleave(files, components.Dir)
+	}
+	files = download.Prioritize(files, opts.priorities, components.Dir)
+
+	// The session file records which commit it was created from, so resolve the ref once up front.
+	if !components.Wiki && !components.Release && onGitHub(components) && components.CommitSHA == "" && (opts.printResolved || opts.withContext || opts.resumeSession != "") {
+		sha, err := gh.ResolveCommitSHA(ctx, components, opts.token)
+		if err != nil {
+			return nil, fmt.Errorf("failed to resolve %s to a commit: %v", components.Ref, err)
+		}
+		components.CommitSHA = sha
+	}
+
+	session := download.NewSession(components, files)
+	for i := range session.Files {
+		item := itemsByPath[session.Files[i].Path]
+		session.Files[i].SHA = item.SHA
+		session.Files[i].Size = item.Size
+		session.Files[i].Mode = item.Mode
+		if components.Release {
+			// The URL of other listings is that of the API entry, not of the content.
+			session.Files[i].URL = item.URL
+		}
+	}
+	return session, nil
+}
+
+// filterItems keeps the items selected by --include, --exclude and --max-depth and not ignored by --ignore-file.
+func filterItems(items []gh.Item, opts options, dir string) ([]gh.Item, error) {
+	var ignore *helpers.IgnoreRules
+	if opts.ignoreFile != "" {
+		var err error
+		if ignore, err = helpers.LoadIgnoreFile(opts.ignoreFile); err != nil {
+			return nil, fmt.Errorf("failed to read ignore file: %v", err)
+		}
+	}
+
+	paths := make([]string, len(items))
+	for i, item := range items {
+		paths[i] = item.Path
+	}
+	selected := map[string]bool{}
+	for _, file := range download.FilterDepth(download.Filter(paths, opts.include, opts.exclude, dir), opts.maxDepth, dir) {
+		selected[file] = ignore == nil || !ignore.Ignored(file)
+	}
+
+	var filtered []gh.Item
+	for _, item := range items {
+		if selected[item.Path] {
+			filtered = append(filtered, item)
+		}
+	}
+	return filtered, nil
+}
+
+// filterRemoved drops the removed changes, local files without a remote counterpart, whose repository path
+// the filters of opts leave out, as they were never meant to be compared. localRoot is where the URL
+// directory dir is saved.
+func filterRemoved(changes []download.FileChange, opts options, dir, localRoot string) ([]download.FileChange, error) {
+	var removed []gh.Item
+	for _, change := range changes {
+		if change.Change == download.ChangeRemoved {
+			removed = append(removed, gh.Item{Path: path.Join(strings.Trim(dir, "/"), helpers.RelativePath(change.Path, localRoot))})
+		}
+	}
+	kept, err := filterItems(removed, opts, dir)
+	if err != nil {
+		return nil, err
+	}
+	selected := map[string]bool{}
+	for _, item := range kept {
+		selected[item.Path] = true
+	}
+
+	var filtered []download.FileChange
+	for _, change := range changes {
+		repoPath := path.Join(strings.Trim(dir, "/"), helpers.RelativePath(change.Path, localRoot))
+		if change.Change != download.ChangeRemoved || selected[repoPath] {
+			filtered = append(filtered, change)
+		}
+	}
+	return filtered, nil
+}
+
+// lfsSizeCap returns the size above which Git LFS objects are skipped: --max-lfs-size, or --max-file-size
+// if that is lower, as listings report Git LFS files at the size of their pointer. Zero means no limit.
+func lfsSizeCap(opts options) int64 {
+	limit := int64(opts.maxLfsSize)
+	if fileLimit := int64(opts.maxFileSize); fileLimit > 0 && (limit == 0 || fileLimit < limit) {
+		limit = fileLimit
+	}
+	return limit
+}
+
+// skipOversized marks the pending files listed above --max-file-size skipped, warning about each.
+func skipOversized(session *download.Session, opts options) {
+	if opts.maxFileSize <= 0 {
+		return
+	}
+	for i := range session.Files {
+		file := &session.Files[i]
+		if file.State != download.StatePending || file.Size <= int64(opts.maxFileSize) {
+			continue
+		}
+		file.State = download.StateSkipped
+		file.Error = fmt.Errorf("%w: %s is over --max-file-size", gh.ErrSkipped, helpers.FormatSize(file.Size)).Error()
+		if !opts.quiet {
+			log.Printf("warning: skipping %s, %s is over --max-file-size %s", file.Path, helpers.FormatSize(file.Size), helpers.FormatSize(int64(opts.maxFileSize)))
+		}
+	}
+}
+
+// checkTotalSize fails if the pending files add up to more than --max-total-size, unless --force or --dry-run is given.
+func checkTotalSize(session *download.Session, opts options) error {
+	size := session.RemainingSize()
+	if opts.maxTotalSize <= 0 || size <= int64(opts.maxTotalSize) || opts.force || opts.dryRun {
+		return nil
+	}
+	return fmt.Errorf(
+		"the %d files to download add up to %s, over --max-total-size %s; narrow the selection or pass --force",
+		len(session.Remaining()),
+		helpers.FormatSize(size),
+		helpers.FormatSize(int64(opts.maxTotalSize)),
+	)
+}
+
+// lastSeenKey identifies the location downloaded by a run for --skip-if-unchanged, together with the
+// --include, --exclude and --max-depth filters that chose its files. Runs without filters keep the bare location.
+func lastSeenKey(components model.RepoURLComponents, opts options) string {
+	key := fmt.Sprintf("%s/%s@%s:%s", components.Owner, components.Repository, components.Ref, strings.Trim(components.Dir, "/"))
+
+	var filters []string
+	for _, filter := range []struct {
+		name     string
+		patterns []string
+	}{{"include", opts.include}, {"exclude", opts.exclude}} {
+		if len(filter.patterns) > 0 {
+			patterns := append([]string{}, filter.patterns...)
+			sort.Strings(patterns)
+			filters = append(filters, filter.name+"="+strings.Join(patterns, ","))
+		}
 	}
+	if opts.maxDepth > 0 {
+		filters = append(filters, fmt.Sprintf("max-depth=%d", opts.maxDepth))
+	}
+	if len(filters) > 0 {
+		key += "?" + strings.Join(filters, "&")
+	}
+	return key
+}
+
+// resolveAndCompare resolves the ref of components to its commit and reports whether it is the
+// commit recorded by the last complete run. Refs with slashes are resolved as listing does, so that
+// the key looked up is the one recorded from the listed components.
+func resolveAndCompare(ctx context.Context, components *model.RepoURLComponents, opts options) (bool, error) {
+	if err := gh.ResolveRef(ctx, components, opts.token); err != nil {
+		return false, err
+	}
+
+	last, err := helpers.LastSeenSHA(lastSeenKey(*components, opts))
+	if err != nil {
+		return false, err
+	}
+	return last == components.CommitSHA, nil
+}
+
+// listFiles lists the files of the repository. With --wait-for-ref it retries with backoff while the
+// listing is empty or lacks --expect-file, to ride out replication lag right after a push, and fails
+// if the listing is still incomplete after the last attempt.
+func listFiles(ctx context.Context, provider download.Provider, components *model.RepoURLComponents, opts options) ([]gh.Item, error) {
+	wait := opts.waitForRef || opts.expectFile != ""
+	delay := waitForRefDelay
+	for attempt := 1; ; attempt++ {
+		// Listing may move directory parts into the ref, so every attempt starts from the parsed URL.
+		listed := *components
+		items, err := provider.ListItems(ctx, &listed)
+		// A ref or directory just pushed may not be visible yet.
+		if err != nil && !(wait && attempt < waitForRefAttempts && errors.Is(err, gh.ErrNotFound)) {
+			return nil, err
+		}
+
+		if err == nil && (!wait || listingComplete(items, listed.Dir, opts.expectFile)) {
+			*components = listed
+			return items, nil
+		}
+		if err == nil && attempt == waitForRefAttempts {
+			if opts.expectFile != "" {
+				return nil, fmt.Errorf("%s is still not listed after %d attempts", opts.expectFile, attempt)
+			}
+			return nil, fmt.Errorf("the listing is still empty after %d attempts", attempt)
+		}
+
+		if !opts.quiet {
+			log.Printf("listing incomplete, retrying in %s (attempt %d of %d)", delay, attempt, waitForRefAttempts)
+		}
+		select {
+		case <-time.After(delay):
+		case <-ctx.Done():
+			return nil, ctx.Err()
+		}
+		delay *= 2
+	}
+}
+
+// listingComplete reports whether items is non-empty and contains expectFile, if one is given
+func listingComplete(items []gh.Item, dir, expectFile string) bool {
+	if len(items) == 0 {
+		return false
+	}
+	if expectFile == "" {
+		return true
+	}
+	for _, item := range items {
+		if helpers.RelativePath(item.Path, dir) == strings.Trim(expectFile, "/") {
+			return true
+		}
+	}
+	return false
+}
+
+// isNearRoot reports whether dir is the repository root or a top-level directory
+func isNearRoot(dir string) bool {
+	return !strings.Contains(strings.Trim(dir, "/"), "/")
+}
+
+// confirmRepoSize warns about very large repositories and asks whether to continue.
+// The prompt is skipped with --yes or --quiet, or when stdin is not a terminal. The size is only a courtesy
+// check, so a failure to fetch it is a warning and the download goes ahead.
+func confirmRepoSize(ctx context.Context, components *model.RepoURLComponents, opts options) (bool, error) {
+	info, err := gh.FetchRepoInfo(ctx, components, opts.token)
+	if ctxErr := ctx.Err(); ctxErr != nil {
+		return false, ctxErr
+	}
+	if err != nil {
+		if !opts.quiet {
+			log.Printf("warning: could not check the repository size: %v", err)
+		}
+		return true, nil
+	}
+	if info.Size <= largeRepoSizeKB {
+		return true, nil
+	}
+
+	log.Printf("warning: %s/%s is %d MB, downloading near its root may take a long time", components.Owner, components.Repository, info.Size/1024)
+	if opts.yes || opts.quiet || !helpers.IsTerminal(os.Stdin) {
+		return true, nil
+	}
+	return helpers.Confirm("Continue? [y/N] "), nil
+}
+
+// indexEntry is the provenance of one downloaded file in --index.
+type indexEntry struct {
+	URL string `json:"url"`
+	SHA string `json:"sha,omitempty"`
+}
+
+// writeIndex writes a JSON object mapping the local path of every downloaded file to its raw source URL
+// and blob SHA.
+func writeIndex(filename string, session *download.Session, fetchOpts gh.FetchOptions) error {
+	index := map[string]indexEntry{}
+	for _, file := range session.Files {
+		if file.State == download.StateDownloaded {
+			source := file.URL
+			if source == "" {
+				source = rawURL(file.Path, &session.Components)
+			}
+			index[fetchOpts.LocalPath(file.Path, &session.Components)] = indexEntry{URL: source, SHA: file.SHA}
+		}
+	}
+
+	data, err := json.MarshalIndent(index, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := os.WriteFile(filename, append(data, '\n'), 0o644); err != nil {
+		return fmt.Errorf("failed to write index: %v", err)
+	}
+	return nil
+}
+
+// writeManifest writes the JSON report of the run to filename.
+func writeManifest(filename string, manifest download.Manifest) error {
+	data, err := json.MarshalIndent(manifest, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := os.WriteFile(filename, append(data, '\n'), 0o644); err != nil {
+		return fmt.Errorf("failed to write manifest: %v", err)
+	}
+	return nil
+}
+
+// printListing prints the repository path of each pending file, one per line. With details set,
+// the size reported by the listing and the blob SHA follow as tab-separated columns.
+func printListing(session *download.Session, details bool) {
+	for _, file := range session.Files {
+		if file.State != download.StatePending {
+			continue
+		}
+		if details {
+			fmt.Printf("%s\t%d\t%s\n", file.Path, file.Size, file.SHA)
+		} else {
+			fmt.Println(file.Path)
+		}
+	}
+}
 
+// fileError is one entry of the --errors-json report.
+type fileError struct {
+	Path    string `json:"path"`
+	Kind    string `json:"kind"`
+	Message string `json:"message"`
+}
+
+// writeErrorsJSON writes failures to w as a JSON array sorted by path, each entry giving the kind of the
+// error from gh.ErrorKind along with its message. No failures give an empty array.
+func writeErrorsJSON(w io.Writer, failures map[string]error) error {
+	report := []fileError{}
+	for path, err := range failures {
+		report = append(report, fileError{Path: path, Kind: gh.ErrorKind(err), Message: err.Error()})
+	}
+	sort.Slice(report, func(i, j int) bool { return report[i].Path < report[j].Path })
+
+	data, err := json.Marshal(report)
+	if err != nil {
+		return err
+	}
+	_, err = fmt.Fprintln(w, string(data))
+	return err
+}
+
+// writeOutputList writes the local paths of the downloaded files, and of skipped ones if includeSkipped is set,
+// one per line to filename, or to stdout if filename is "-".
+func writeOutputList(filename string, session *download.Session, fetchOpts gh.FetchOptions, includeSkipped bool) error {
+	var list strings.Builder
+	for _, file := range session.Files {
+		if file.State == download.StateDownloaded || (includeSkipped && file.State == download.StateSkipped) {
+			list.WriteString(fetchOpts.LocalPath(file.Path, &session.Components))
+			list.WriteByte('\n')
+		}
+	}
+
+	if filename == "-" {
+		_, err := os.Stdout.WriteString(list.String())
+		return err
+	}
+	if err := os.WriteFile(filename, []byte(list.String()), 0o644); err != nil {
+		return fmt.Errorf("failed to write output list: %v", err)
+	}
+	return nil
+}
+
+// writeChecksumFile writes the SHA-256 of every downloaded file, and of skipped ones that exist locally if
+// includeSkipped is set, as a sha256sum style file to filename (- for stdout). Paths are relative to the
+// output directory.
+func writeChecksumFile(filename string, session *download.Session, fetchOpts gh.FetchOptions, includeSkipped bool) error {
+	sums := map[string]string{}
+	for _, file := range session.Files {
+		if file.State != download.StateDownloaded && !(includeSkipped && file.State == download.StateSkipped) {
+			continue
+		}
+		localPath := fetchOpts.LocalPath(file.Path, &session.Components)
+		sum, err := helpers.FileSHA256(filepath.FromSlash(localPath))
+		if errors.Is(err, os.ErrNotExist) {
+			// Skipped files never downloaded, or files a failed --staged run left in its staging directory.
+			continue
+		}
+		if err != nil {
+			return fmt.Errorf("failed to hash %s: %v", localPath, err)
+		}
+		sums[localPath] = sum
+	}
+
+	var list strings.Builder
+	if err := helpers.WriteChecksums(&list, sums); err != nil {
+		return err
+	}
+	if filename == "-" {
+		_, err := os.Stdout.WriteString(list.String())
+		return err
+	}
+	if err := os.WriteFile(filename, []byte(list.String()), 0o644); err != nil {
+		return fmt.Errorf("failed to write checksum file: %v", err)
+	}
+	return nil
+}
+
+// contextFileName is the file --with-context writes next to the downloaded files
+const contextFileName = "REPO_PACK_CONTEXT.md"
+
+// writeContextFile describes where the downloaded files came from: the repository, ref, commit and the
+// tree of files saved. It contains nothing run-specific, so identical downloads produce identical files.
+func writeContextFile(session *download.Session, fetchOpts gh.FetchOptions, repoURL string) error {
+	components := session.Components
+
+	var files []string
+	for _, file := range session.Files {
+		if file.State == download.StateDownloaded {
+			files = append(files, fetchOpts.LocalPath(file.Path, &components))
+		}
+	}
+
+	var content strings.Builder
+	fmt.Fprintf(&content, "# %s/%s\n\n", components.Owner, components.Repository)
+	if repoURL != "" {
+		fmt.Fprintf(&content, "- Source: %s\n", repoURL)
+	}
+	fmt.Fprintf(&content, "- Ref: %s\n", components.Ref)
+	if components.CommitSHA != "" {
+		fmt.Fprintf(&content, "- Commit: %s\n", components.CommitSHA)
+	}
+	fmt.Fprintf(&content, "- Directory: /%s\n", strings.Trim(components.Dir, "/"))
+	fmt.Fprintf(&content, "- Files: %d\n\n", len(files))
+	fmt.Fprintf(&content, "## Files\n\n```\n%s```\n", helpers.RenderTree(files))
+
+	reader := io.NopCloser(strings.NewReader(content.String()))
+	if err := helpers.SaveFile(contextFileName, reader, helpers.SaveOptions{Root: fetchOpts.Save.Root, Archive: fetchOpts.Save.Archive}); err != nil {
+		return fmt.Errorf("failed to write %s: %v", contextFileName, err)
+	}
 	return nil
 }
+
+// printSummary reports how many files were downloaded and, under GitHub Actions, mirrors it as a notice.
+// gone is how many of the failed files were listed but missing when downloaded, and lfs counts the
+// downloaded files that came from Git LFS.
+func printSummary(session *download.Session, gone int, lfs *gh.LfsStats, annotate bool) {
+	failed := fmt.Sprintf("%d failed", session.Count(download.StateFailed))
+	if gone > 0 {
+		failed += fmt.Sprintf(", %d of them listed but no longer available upstream", gone)
+	}
+	summary := fmt.Sprintf(
+		"Downloaded %d of %d files (%d skipped, %s)",
+		session.Count(download.StateDownloaded),
+		len(session.Files),
+		session.Count(download.StateSkipped),
+		failed,
+	)
+	if lfs.Files() > 0 {
+		summary += fmt.Sprintf(", %d via LFS (%s)", lfs.Files(), helpers.FormatSize(lfs.Bytes()))
+	}
+	fmt.Printf("[-] %s\n", summary)
+	if annotate {
+		fmt.Println(helpers.WorkflowCommand("notice", summary))
+	}
+}
+
+// printRequestEstimate reports the API requests made so far, which for a fresh run is the listing,
+// next to the raw downloads still to come, which do not count against the API rate limit.
+func printRequestEstimate(downloads int) {
+	usage := gh.CurrentAPIUsage()
+	var kinds []string
+	if usage.Trees > 0 {
+		kinds = append(kinds, fmt.Sprintf("%d trees", usage.Trees))
+	}
+	if usage.Contents > 0 {
+		kinds = append(kinds, fmt.Sprintf("%d contents", usage.Contents))
+	}
+	if usage.Other > 0 {
+		kinds = append(kinds, fmt.Sprintf("%d other", usage.Other))
+	}
+
+	line := fmt.Sprintf("[-] Estimated API requests: %d", usage.Total())
+	if len(kinds) > 0 {
+		line += fmt.Sprintf(" (%s)", strings.Join(kinds, ", "))
+	}
+	fmt.Printf("%s; raw downloads: %d (not rate-limited)\n", line, downloads)
+}
+
+// printRateLimit reports the API rate limit budget left after the run, if GitHub reported one
+func printRateLimit() {
+	rateLimit, ok := gh.LastRateLimit()
+	if !ok {
+		return
+	}
+
+	line := fmt.Sprintf("[-] Rate limit: %d/%d", rateLimit.Remaining, rateLimit.Limit)
+	if untilReset := time.Until(rateLimit.Reset); untilReset > 0 {
+		line += fmt.Sprintf(", resets in %s", untilReset.Round(time.Minute))
+	}
+	fmt.Println(line)
+}
@@ -0,0 +1,186 @@
+package main
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+
+	"repo-pack/download"
+	"repo-pack/model"
+)
+
+func newSizedSession(sizes ...int64) *download.Session {
+	files := make([]string, len(sizes))
+	for i := range sizes {
+		files[i] = string(rune('a' + i))
+	}
+	session := download.NewSession(model.RepoURLComponents{}, files)
+	for i, size := range sizes {
+		session.Files[i].Size = size
+	}
+	return session
+}
+
+func TestSkipOversized(t *testing.T) {
+	session := newSizedSession(100, 2000, 1000)
+	skipOversized(session, options{maxFileSize: 1000, quiet: true})
+
+	expected := []download.FileState{download.StatePending, download.StateSkipped, download.StatePending}
+	for i, state := range expected {
+		if session.Files[i].State != state {
+			t.Errorf("expected %s to be %s, got: %s", session.Files[i].Path, state, session.Files[i].State)
+		}
+	}
+}
+
+func TestCheckTotalSize(t *testing.T) {
+	session := newSizedSession(600, 600)
+
+	if err := checkTotalSize(session, options{maxTotalSize: 1000}); err == nil {
+		t.Errorf("expected 1200 bytes to be over a cap of 1000")
+	}
+	if err := checkTotalSize(session, options{maxTotalSize: 1000, force: true}); err != nil {
+		t.Errorf("expected --force to allow the run, got: %v", err)
+	}
+	if err := checkTotalSize(session, options{maxTotalSize: 2000}); err != nil {
+		t.Errorf("expected 1200 bytes to be under a cap of 2000, got: %v", err)
+	}
+
+	// Files skipped by --max-file-size don't count.
+	skipOversized(session, options{maxFileSize: 500, quiet: true})
+	if err := checkTotalSize(session, options{maxTotalSize: 1000}); err != nil {
+		t.Errorf("expected skipped files to be left out, got: %v", err)
+	}
+}
+
+func TestLfsSizeCap(t *testing.T) {
+	tests := []struct {
+		opts     options
+		expected int64
+	}{
+		{options{}, 0},
+		{options{maxLfsSize: 500}, 500},
+		{options{maxFileSize: 100}, 100},
+		{options{maxLfsSize: 500, maxFileSize: 100}, 100},
+		{options{maxLfsSize: 100, maxFileSize: 500}, 100},
+	}
+	for _, test := range tests {
+		if got := lfsSizeCap(test.opts); got != test.expected {
+			t.Errorf("%+v: expected %d, got: %d", test.opts, test.expected, got)
+		}
+	}
+}
+
+func TestExistingFiles(t *testing.T) {
+	tests := []struct {
+		name     string
+		opts     options
+		expected download.ExistingFiles
+		wantErr  bool
+	}{
+		{"default", options{}, download.ExistingAsk, false},
+		{"overwrite", options{overwrite: true}, download.ExistingOverwrite, false},
+		{"skip existing", options{skipExisting: true}, download.ExistingSkip, false},
+		{"resume", options{resume: true}, download.ExistingResume, false},
+		{"on conflict rename", options{onConflict: "rename"}, download.ExistingRename, false},
+		{"on conflict newer", options{onConflict: "newer"}, download.ExistingNewer, false},
+		{"on conflict invalid", options{onConflict: "merge"}, "", true},
+		{"overwrite and skip existing", options{overwrite: true, skipExisting: true}, "", true},
+		{"resume and on conflict", options{resume: true, onConflict: "skip"}, "", true},
+		{"archive", options{archive: "out.zip"}, download.ExistingOverwrite, false},
+		{"stdout", options{output: "-"}, download.ExistingOverwrite, false},
+		{"resumed session", options{resumeSession: "session.json"}, download.ExistingOverwrite, false},
+		{"archive with skip existing", options{archive: "out.zip", skipExisting: true}, download.ExistingSkip, false},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got, err := existingFiles(tt.opts)
+			if (err != nil) != tt.wantErr {
+				t.Fatalf("expected error: %v, got: %v", tt.wantErr, err)
+			}
+			if got != tt.expected {
+				t.Errorf("expected %q, got: %q", tt.expected, got)
+			}
+		})
+	}
+}
+
+func TestResolveExisting(t *testing.T) {
+	dir := t.TempDir()
+	for _, name := range []string{"current", "changed"} {
+		if err := os.WriteFile(filepath.Join(dir, name), []byte("content"), 0o644); err != nil {
+			t.Fatalf("unexpected error: %v", err)
+		}
+	}
+	localPath := func(path string) string { return filepath.ToSlash(filepath.Join(dir, path)) }
+	lock := download.NewLockFile()
+	lock.Files[localPath("current")] = download.LockEntry{SHA: "current-sha"}
+	lock.Files[localPath("changed")] = download.LockEntry{SHA: "old-sha"}
+
+	tests := []struct {
+		existing download.ExistingFiles
+		skipped  map[string]string
+		renamed  []string
+	}{
+		{existing: download.ExistingOverwrite},
+		{existing: download.ExistingNewer},
+		{existing: download.ExistingAsk},
+		{
+			existing: download.ExistingSkip,
+			skipped:  map[string]string{"current": "already exists", "changed": "already exists"},
+		},
+		{
+			existing: download.ExistingResume,
+			skipped:  map[string]string{"current": "unchanged since the last run"},
+		},
+		{existing: download.ExistingRename, renamed: []string{"changed", "current"}},
+	}
+	for _, tt := range tests {
+		t.Run(string(tt.existing), func(t *testing.T) {
+			session := download.NewSession(model.RepoURLComponents{}, []string{"current", "changed", "missing"})
+			session.Files[0].SHA = "current-sha"
+			session.Files[1].SHA = "new-sha"
+			session.FetchOptions.RemoteCopies = map[string]bool{}
+
+			opts := options{existing: tt.existing, yes: true, quiet: true}
+			if err := resolveExisting(session, opts, lock, localPath); err != nil {
+				t.Fatalf("unexpected error: %v", err)
+			}
+			for _, file := range session.Files {
+				reason, skip := tt.skipped[file.Path]
+				if skip != (file.State == download.StateSkipped) {
+					t.Errorf("expected %s skipped: %v, got state: %s", file.Path, skip, file.State)
+				}
+				if skip && !strings.Contains(file.Error, reason) {
+					t.Errorf("expected %s to be skipped as %q, got: %q", file.Path, reason, file.Error)
+				}
+			}
+			if len(session.FetchOptions.RemoteCopies) != len(tt.renamed) {
+				t.Errorf("expected %v to be saved as .remote copies, got: %v", tt.renamed, session.FetchOptions.RemoteCopies)
+			}
+			for _, path := range tt.renamed {
+				if !session.FetchOptions.RemoteCopies[path] {
+					t.Errorf("expected %s to be saved as a .remote copy", path)
+				}
+			}
+		})
+	}
+}
+
+func TestLastSeenKey(t *testing.T) {
+	components := model.RepoURLComponents{Owner: "owner", Repository: "repo", Ref: "main", Dir: "/docs/"}
+	tests := []struct {
+		opts     options
+		expected string
+	}{
+		{options{}, "owner/repo@main:docs"},
+		{options{include: stringList{"*.md", "*.go"}}, "owner/repo@main:docs?include=*.go,*.md"},
+		{options{exclude: stringList{"tests/**"}, maxDepth: 2}, "owner/repo@main:docs?exclude=tests/**&max-depth=2"},
+	}
+	for _, tt := range tests {
+		if got := lastSeenKey(components, tt.opts); got != tt.expected {
+			t.Errorf("expected %q, got: %q", tt.expected, got)
+		}
+	}
+}
@@ -3,6 +3,20 @@ package model
 type RepoURLComponents struct {
 	Owner      string
 	Repository string
-	Ref        string
+	Ref        string // empty for repository URLs until the default branch has been resolved
 	Dir        string
+	Wiki       bool
+
+	// Release is set for release URLs, the files are then the assets of the release tagged Ref,
+	// or of the latest release while Ref is empty
+	Release bool `json:",omitempty"`
+
+	// File is set for single file URLs, Dir is then the path of the file
+	File bool `json:",omitempty"`
+
+	// CommitSHA is the full SHA that Ref resolved to, when it has been resolved
+	CommitSHA string `json:",omitempty"`
+
+	// Host is the hosting service of repositories not on GitHub, e.g. gitlab.com
+	Host string `json:",omitempty"`
 }
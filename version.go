@@ -0,0 +1,91 @@
+package main
+
+import (
+	"flag"
+	"fmt"
+	"runtime"
+	"runtime/debug"
+)
+
+// Set at release time by goreleaser through -ldflags "-X main.version=...".
+var (
+	version = "dev"
+	commit  = "none"
+	date    = "unknown"
+)
+
+// buildInfo describes the binary for bug reports.
+type buildInfo struct {
+	Version   string
+	Commit    string
+	Date      string
+	Modified  bool
+	GoVersion string
+	Platform  string
+}
+
+// currentBuildInfo combines the release ldflags with the VCS details the Go toolchain embeds in
+// binaries built from a checkout, so `go build` and `go install` binaries are identifiable too.
+func currentBuildInfo() buildInfo {
+	info := buildInfo{
+		Version:   version,
+		Commit:    commit,
+		Date:      date,
+		GoVersion: runtime.Version(),
+		Platform:  runtime.GOOS + "/" + runtime.GOARCH,
+	}
+
+	embedded, ok := debug.ReadBuildInfo()
+	if !ok {
+		return info
+	}
+	if info.Version == "dev" && embedded.Main.Version != "" && embedded.Main.Version != "(devel)" {
+		info.Version = embedded.Main.Version
+	}
+	for _, setting := range embedded.Settings {
+		switch setting.Key {
+		case "vcs.revision":
+			if info.Commit == "none" {
+				info.Commit = setting.Value
+			}
+		case "vcs.time":
+			if info.Date == "unknown" {
+				info.Date = setting.Value
+			}
+		case "vcs.modified":
+			info.Modified = setting.Value == "true"
+		}
+	}
+	return info
+}
+
+// printVersion prints the version, and with verbose the commit, build date and toolchain as well.
+func printVersion(verbose bool) {
+	info := currentBuildInfo()
+	fmt.Printf("repo-pack %s\n", info.Version)
+	if !verbose {
+		return
+	}
+
+	revision := info.Commit
+	if info.Modified {
+		revision += " (modified)"
+	}
+	fmt.Printf("commit:   %s\n", revision)
+	fmt.Printf("built:    %s\n", info.Date)
+	fmt.Printf("go:       %s\n", info.GoVersion)
+	fmt.Printf("platform: %s\n", info.Platform)
+}
+
+// runVersion implements the version subcommand.
+func runVersion(args []string) error {
+	flags := flag.NewFlagSet("version", flag.ContinueOnError)
+	var verbose bool
+	flags.BoolVar(&verbose, "v", false, "Also print the commit, build date and Go version (shorthand for --verbose)")
+	flags.BoolVar(&verbose, "verbose", false, "Also print the commit, build date and Go version")
+	if err := flags.Parse(args); err != nil {
+		return err
+	}
+	printVersion(verbose)
+	return nil
+}